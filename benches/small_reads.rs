@@ -0,0 +1,58 @@
+//! Benchmarks `Reader::read` under small-read workloads, where the
+//! caller pulls plaintext out a handful of bytes at a time instead of
+//! a whole chunk at once. This is the path `Buf`'s own read-position
+//! tracking (see `Buf::read_plaintext`) is meant to keep cheap: each
+//! small read should cost a bounds-checked copy out of the current
+//! chunk's plaintext, not any extra bookkeeping to figure out where
+//! that chunk left off.
+
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use stream::{Reader, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+fn bench_small_reads(c: &mut Criterion) {
+    let plaintext_len = 4 * CHUNK_SIZE;
+    let ciphertext = seal(&vec![0xab; plaintext_len]);
+
+    let mut group = c.benchmark_group("reader_small_reads");
+    group.throughput(Throughput::Bytes(plaintext_len as u64));
+    for read_size in [1usize, 16, 256, 4096] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(read_size),
+            &read_size,
+            |b, &read_size| {
+                b.iter(|| {
+                    let key = KEY.into();
+                    let mut r =
+                        Reader::<_, ChaCha20Poly1305>::new(ciphertext.as_slice(), &key).unwrap();
+                    let mut buf = vec![0u8; read_size];
+                    let mut total = 0usize;
+                    loop {
+                        let n = r.read(&mut buf).unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        total += n;
+                    }
+                    black_box(total)
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_small_reads);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+//! Generates golden test vectors covering the wire-format variants this
+//! crate's native [`Writer`]/[`Reader`] support, plus a few corrupted
+//! negative cases, so other implementations (and future versions of
+//! this one) can cross-check against pinned output instead of just
+//! this crate's own round-trip tests.
+//!
+//! Run with `cargo run --example golden_vectors --features vectors` and
+//! redirect stdout to a file. Every key, nonce prefix, and plaintext is
+//! derived from a fixed seed instead of real randomness, so the output
+//! is byte-for-byte identical on every run.
+//!
+//! This only covers `ChaCha20Poly1305`, the one AEAD this crate
+//! actually depends on, and the native format's fixed [`CHUNK_SIZE`];
+//! the interop modules (`tink`, `age`, `aead_stream`) have their own
+//! configurable chunk sizes and are out of scope here.
+
+use std::io::Write as _;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{dump_vectors, DigestAlgorithm, Vector, Writer, CHUNK_SIZE};
+
+/// Deterministically fills a buffer from `seed`, standing in for a
+/// CSPRNG so golden vectors stay reproducible across runs and
+/// languages.
+fn fill(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed ^ 0x9E3779B97F4A7C15;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state & 0xff) as u8
+        })
+        .collect()
+}
+
+struct Seeds {
+    key: [u8; 32],
+    nonce_prefix: [u8; 4],
+    plaintext: Vec<u8>,
+}
+
+fn seeds(base: u64, plaintext_len: usize) -> Seeds {
+    Seeds {
+        key: fill(base, 32).try_into().unwrap(),
+        nonce_prefix: fill(base + 1, 4).try_into().unwrap(),
+        plaintext: fill(base + 2, plaintext_len),
+    }
+}
+
+fn valid_vector(name: &str, s: &Seeds, ciphertext: Vec<u8>) -> Vector {
+    Vector {
+        name: name.to_string(),
+        key: s.key.to_vec(),
+        ciphertext,
+        valid: true,
+        plaintext: s.plaintext.clone(),
+    }
+}
+
+fn plain_vector(name: &str, s: &Seeds) -> Vector {
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &s.key.into(), s.nonce_prefix).unwrap();
+    w.write_all(&s.plaintext).unwrap();
+    valid_vector(name, s, w.finish().unwrap())
+}
+
+fn digest_vector(name: &str, s: &Seeds, alg: DigestAlgorithm) -> Vector {
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_digest(
+        Vec::new(),
+        &s.key.into(),
+        s.nonce_prefix,
+        Some(alg),
+    )
+    .unwrap();
+    w.write_all(&s.plaintext).unwrap();
+    valid_vector(name, s, w.finish().unwrap())
+}
+
+fn padded_vector(name: &str, s: &Seeds) -> Vector {
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &s.key.into(), s.nonce_prefix)
+            .unwrap();
+    w.write_all(&s.plaintext).unwrap();
+    valid_vector(name, s, w.finish().unwrap())
+}
+
+fn derived_nonce_vector(name: &str, s: &Seeds) -> Vector {
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_derived_nonces(
+        Vec::new(),
+        &s.key.into(),
+        s.nonce_prefix,
+    )
+    .unwrap();
+    w.write_all(&s.plaintext).unwrap();
+    valid_vector(name, s, w.finish().unwrap())
+}
+
+fn truncated_vector(name: &str, valid: &Vector) -> Vector {
+    let mut ciphertext = valid.ciphertext.clone();
+    ciphertext.truncate(ciphertext.len() - 4);
+    Vector {
+        name: name.to_string(),
+        key: valid.key.clone(),
+        ciphertext,
+        valid: false,
+        plaintext: Vec::new(),
+    }
+}
+
+fn bit_flipped_vector(name: &str, valid: &Vector) -> Vector {
+    let mut ciphertext = valid.ciphertext.clone();
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 0x01;
+    Vector {
+        name: name.to_string(),
+        key: valid.key.clone(),
+        ciphertext,
+        valid: false,
+        plaintext: Vec::new(),
+    }
+}
+
+const SIZES: &[(&str, usize)] = &[
+    ("empty", 0),
+    ("short", 13),
+    ("one chunk", CHUNK_SIZE),
+    ("multiple chunks", CHUNK_SIZE * 2 + 17),
+];
+
+fn main() {
+    let mut vectors = Vec::new();
+    let mut base = 0u64;
+
+    for &(size_name, len) in SIZES {
+        let s = seeds(base, len);
+        base += 100;
+
+        let plain = plain_vector(&format!("plain, {size_name}"), &s);
+        vectors.push(truncated_vector(
+            &format!("plain, {size_name}, truncated"),
+            &plain,
+        ));
+        vectors.push(bit_flipped_vector(
+            &format!("plain, {size_name}, bit flip"),
+            &plain,
+        ));
+        vectors.push(plain);
+
+        vectors.push(digest_vector(
+            &format!("sha256 digest, {size_name}"),
+            &s,
+            DigestAlgorithm::Sha256,
+        ));
+        vectors.push(digest_vector(
+            &format!("blake3 digest, {size_name}"),
+            &s,
+            DigestAlgorithm::Blake3,
+        ));
+        vectors.push(padded_vector(&format!("padded, {size_name}"), &s));
+        vectors.push(derived_nonce_vector(
+            &format!("derived nonces, {size_name}"),
+            &s,
+        ));
+    }
+
+    let json = dump_vectors(&vectors).unwrap();
+    std::io::stdout().write_all(json.as_bytes()).unwrap();
+}
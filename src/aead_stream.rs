@@ -0,0 +1,262 @@
+//! Interop with the RustCrypto `aead` crate's `stream` module
+//! (`StreamBE32`/`StreamLE31`, as used by `EncryptorBE32` and friends).
+//!
+//! `aead::stream` doesn't define a ciphertext framing of its own: it's
+//! a pair of nonce-overlay conventions -- `StreamBE32` (a big-endian
+//! 32-bit counter) and `StreamLE31` (a little-endian 31-bit counter) --
+//! that callers combine with their own chunk size and header format.
+//! This module reproduces those two nonce overlays so a chunk sequence
+//! encrypted with `aead::stream::EncryptorBE32`/`EncryptorLE31` (or
+//! decrypted with the matching `Decryptor`) can be reframed through
+//! this crate's `Read`/`Write` API, as long as the caller already
+//! knows the chunk size and nonce prefix the other side used -- the
+//! same out-of-band agreement `aead::stream`'s own callers need, since
+//! it has no header of its own either.
+//!
+//! Both overlays split a 96-bit nonce into an 8-byte prefix and a
+//! 4-byte counter; the counter's top bit is reserved to mark the final
+//! chunk, leaving 31 bits (2,147,483,647 chunks) of actual counter
+//! range either way.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::Error;
+
+/// The length, in bytes, of the nonce prefix shared by both overlays.
+pub const PREFIX_LEN: usize = 8;
+
+const TAG_SIZE: usize = 16;
+
+/// The highest counter value that still leaves room for the
+/// last-chunk flag in the top bit.
+const MAX_COUNTER: u32 = (1 << 31) - 1;
+
+/// The counter endianness used to build a chunk's nonce, matching
+/// `aead::stream::StreamBE32` and `StreamLE31` respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// `aead::stream::StreamBE32`.
+    Big,
+    /// `aead::stream::StreamLE31`.
+    Little,
+}
+
+/// Builds the nonce for chunk `counter`. `last` must be `true` only
+/// for the final chunk of the stream.
+fn build_nonce(
+    endian: Endian,
+    prefix: &[u8; PREFIX_LEN],
+    counter: u32,
+    last: bool,
+) -> GenericArray<u8, U12> {
+    let counter = if last { counter | (1 << 31) } else { counter };
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    let bytes = match endian {
+        Endian::Big => counter.to_be_bytes(),
+        Endian::Little => counter.to_le_bytes(),
+    };
+    nonce[PREFIX_LEN..].copy_from_slice(&bytes);
+    nonce
+}
+
+/// Encrypts a plaintext as a sequence of fixed-size chunks using
+/// `aead::stream`-compatible nonces.
+pub struct AeadStreamWriter<W, A> {
+    w: W,
+    aead: A,
+    endian: Endian,
+    prefix: [u8; PREFIX_LEN],
+    counter: u32,
+    chunk_size: usize,
+    buf: Vec<u8>,
+}
+
+impl<W, A> AeadStreamWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Starts a new chunk sequence. Unlike [`Writer`](crate::Writer),
+    /// no header is written: `prefix` and `chunk_size` must be conveyed
+    /// to the reader out of band, matching `aead::stream`'s own
+    /// header-less design.
+    pub fn new(
+        w: W,
+        key: &Key<A>,
+        endian: Endian,
+        prefix: [u8; PREFIX_LEN],
+        chunk_size: usize,
+    ) -> Self {
+        Self {
+            w,
+            aead: A::new(key),
+            endian,
+            prefix,
+            counter: 0,
+            chunk_size,
+            buf: Vec::with_capacity(chunk_size),
+        }
+    }
+
+    fn flush_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = build_nonce(self.endian, &self.prefix, self.counter, last);
+        let mut chunk = std::mem::take(&mut self.buf);
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, b"", &mut chunk)
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        chunk.extend_from_slice(&tag);
+        self.w.write_all(&chunk)?;
+        if self.counter == MAX_COUNTER {
+            return Err(io::Error::other(Error::NonceOverflow));
+        }
+        self.counter += 1;
+        self.buf = Vec::with_capacity(self.chunk_size);
+        Ok(())
+    }
+
+    /// Finishes the sequence: seals any buffered plaintext as the
+    /// final chunk, then returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_chunk(true)?;
+        Ok(self.w)
+    }
+}
+
+impl<W, A> Write for AeadStreamWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let total = data.len();
+        let mut data = data;
+        while !data.is_empty() {
+            let room = self.chunk_size - self.buf.len();
+            let n = data.len().min(room);
+            self.buf.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.buf.len() == self.chunk_size {
+                self.flush_chunk(false)?;
+            }
+        }
+        Ok(total)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Decrypts a chunk sequence written by [`AeadStreamWriter`], or by
+/// `aead::stream::EncryptorBE32`/`EncryptorLE31` directly.
+pub struct AeadStreamReader<R, A> {
+    r: R,
+    aead: A,
+    endian: Endian,
+    prefix: [u8; PREFIX_LEN],
+    counter: u32,
+    chunk_size: usize,
+    cbuf: Vec<u8>,
+    pbuf: Vec<u8>,
+    ppos: usize,
+    done: bool,
+}
+
+impl<R, A> AeadStreamReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a chunk sequence. `endian`, `prefix`, and `chunk_size`
+    /// must match what the writer used.
+    pub fn new(
+        r: R,
+        key: &Key<A>,
+        endian: Endian,
+        prefix: [u8; PREFIX_LEN],
+        chunk_size: usize,
+    ) -> Self {
+        Self {
+            r,
+            aead: A::new(key),
+            endian,
+            prefix,
+            counter: 0,
+            chunk_size,
+            cbuf: Vec::new(),
+            pbuf: Vec::new(),
+            ppos: 0,
+            done: false,
+        }
+    }
+
+    fn advance(&mut self) -> io::Result<()> {
+        let target = self.chunk_size + TAG_SIZE;
+        let mut chunk = [0u8; 4096];
+        while self.cbuf.len() < target + 1 {
+            let want = (target + 1 - self.cbuf.len()).min(chunk.len());
+            let n = self.r.read(&mut chunk[..want])?;
+            if n == 0 {
+                break;
+            }
+            self.cbuf.extend_from_slice(&chunk[..n]);
+        }
+        if self.cbuf.is_empty() {
+            self.done = true;
+            self.pbuf.clear();
+            return Ok(());
+        }
+        let last = self.cbuf.len() <= target;
+        let chunk_len = self.cbuf.len().min(target);
+        if chunk_len < TAG_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::InvalidHeader,
+            ));
+        }
+        let plaintext_len = chunk_len - TAG_SIZE;
+
+        let nonce = build_nonce(self.endian, &self.prefix, self.counter, last);
+        let mut plaintext = self.cbuf[..plaintext_len].to_vec();
+        let tag: aead::Tag<A> =
+            GenericArray::clone_from_slice(&self.cbuf[plaintext_len..chunk_len]);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut plaintext, &tag)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::Aead))?;
+        self.cbuf.drain(..chunk_len);
+        if self.counter == MAX_COUNTER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::NonceOverflow,
+            ));
+        }
+        self.counter += 1;
+        self.done = last;
+        self.pbuf = plaintext;
+        self.ppos = 0;
+        Ok(())
+    }
+}
+
+impl<R, A> Read for AeadStreamReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.ppos >= self.pbuf.len() && !self.done {
+            self.advance()?;
+        }
+        let avail = &self.pbuf[self.ppos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.ppos += n;
+        Ok(n)
+    }
+}
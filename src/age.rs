@@ -0,0 +1,230 @@
+//! Partial interop with the [age](https://age-encryption.org/v1) (and
+//! `rage`) file format, gated behind the `age` feature.
+//!
+//! age's file format is a textual header naming one or more recipient
+//! stanzas (X25519, scrypt, ...) that each wrap the same random 16-byte
+//! file key, followed by an HMAC over that header, followed by the
+//! payload: the file key run through age's own STREAM construction.
+//!
+//! This module implements only the payload STREAM layer -- [`AgeWriter`]
+//! and [`AgeReader`] take an already-unwrapped file key and handle the
+//! 64 KiB chunking, per-chunk key derivation, and nonce construction
+//! that age specifies for it. Parsing the textual header and
+//! wrapping/unwrapping the file key via X25519 or scrypt recipient
+//! stanzas is a separate concern, typically layered on top the same way
+//! the upstream `age` crate itself delegates its payload encryption to
+//! a STREAM primitive, and isn't implemented here.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::U12;
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, Key, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::Error;
+
+/// The length, in bytes, of an age file key.
+pub const FILE_KEY_LEN: usize = 16;
+
+/// The length, in bytes, of the random nonce that salts the payload
+/// key derivation. age writes this immediately before the payload.
+pub const PAYLOAD_NONCE_LEN: usize = 16;
+
+/// The plaintext size of every payload chunk but the last.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+const TAG_SIZE: usize = 16;
+
+/// The length, in bytes, of the big-endian chunk counter packed into
+/// the nonce.
+const COUNTER_LEN: usize = 11;
+
+/// Derives age's payload key: `HKDF-SHA256(salt = payload_nonce, ikm =
+/// file_key, info = "payload")`.
+fn derive_payload_key(
+    file_key: &[u8; FILE_KEY_LEN],
+    payload_nonce: &[u8; PAYLOAD_NONCE_LEN],
+) -> Key<ChaCha20Poly1305> {
+    let hk = Hkdf::<Sha256>::new(Some(payload_nonce), file_key);
+    let mut key = Key::<ChaCha20Poly1305>::default();
+    hk.expand(b"payload", &mut key)
+        .expect("32 bytes is within HKDF-SHA256's output size limit");
+    key
+}
+
+/// Builds the nonce for chunk `counter`: an 88-bit big-endian counter
+/// followed by a one-byte final-chunk flag. `last` must be `true` only
+/// for the final chunk of the payload.
+fn chunk_nonce(counter: u128, last: bool) -> GenericArray<u8, U12> {
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..COUNTER_LEN].copy_from_slice(&counter.to_be_bytes()[16 - COUNTER_LEN..]);
+    nonce[COUNTER_LEN] = last as u8;
+    nonce
+}
+
+/// Encrypts a plaintext as age's payload STREAM, writing the random
+/// payload nonce followed by the chunked ciphertext to `w`.
+pub struct AgeWriter<W> {
+    w: W,
+    aead: ChaCha20Poly1305,
+    counter: u128,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> AgeWriter<W> {
+    /// Starts a new payload, writing `payload_nonce` to `w` immediately.
+    ///
+    /// `payload_nonce` must be fresh for every payload encrypted under
+    /// `file_key`; it should come from a cryptographically secure RNG.
+    pub fn new(
+        mut w: W,
+        file_key: &[u8; FILE_KEY_LEN],
+        payload_nonce: [u8; PAYLOAD_NONCE_LEN],
+    ) -> io::Result<Self> {
+        w.write_all(&payload_nonce)?;
+        let key = derive_payload_key(file_key, &payload_nonce);
+        Ok(Self {
+            w,
+            aead: ChaCha20Poly1305::new(&key),
+            counter: 0,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    fn flush_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(self.counter, last);
+        let mut chunk = std::mem::take(&mut self.buf);
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, b"", &mut chunk)
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        chunk.extend_from_slice(&tag);
+        self.w.write_all(&chunk)?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other(Error::NonceOverflow))?;
+        self.buf = Vec::with_capacity(CHUNK_SIZE);
+        Ok(())
+    }
+
+    /// Finishes the payload: seals any buffered plaintext as the final
+    /// chunk -- an empty one if the plaintext was empty, or ended
+    /// exactly on a chunk boundary, per age's STREAM rules -- then
+    /// returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_chunk(true)?;
+        Ok(self.w)
+    }
+}
+
+impl<W: Write> Write for AgeWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let total = data.len();
+        let mut data = data;
+        while !data.is_empty() {
+            let room = CHUNK_SIZE - self.buf.len();
+            let n = data.len().min(room);
+            self.buf.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.buf.len() == CHUNK_SIZE {
+                self.flush_chunk(false)?;
+            }
+        }
+        Ok(total)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Decrypts a payload written by [`AgeWriter`].
+pub struct AgeReader<R> {
+    r: R,
+    aead: ChaCha20Poly1305,
+    counter: u128,
+    cbuf: Vec<u8>,
+    pbuf: Vec<u8>,
+    ppos: usize,
+    done: bool,
+}
+
+impl<R: Read> AgeReader<R> {
+    /// Opens a payload, reading its random nonce from `r`.
+    pub fn new(mut r: R, file_key: &[u8; FILE_KEY_LEN]) -> io::Result<Self> {
+        let mut payload_nonce = [0u8; PAYLOAD_NONCE_LEN];
+        r.read_exact(&mut payload_nonce)?;
+        let key = derive_payload_key(file_key, &payload_nonce);
+        Ok(Self {
+            r,
+            aead: ChaCha20Poly1305::new(&key),
+            counter: 0,
+            cbuf: Vec::new(),
+            pbuf: Vec::new(),
+            ppos: 0,
+            done: false,
+        })
+    }
+
+    fn advance(&mut self) -> io::Result<()> {
+        let target = CHUNK_SIZE + TAG_SIZE;
+        let mut chunk = [0u8; 4096];
+        while self.cbuf.len() < target + 1 {
+            let want = (target + 1 - self.cbuf.len()).min(chunk.len());
+            let n = self.r.read(&mut chunk[..want])?;
+            if n == 0 {
+                break;
+            }
+            self.cbuf.extend_from_slice(&chunk[..n]);
+        }
+        if self.cbuf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::InvalidHeader,
+            ));
+        }
+        let last = self.cbuf.len() <= target;
+        let chunk_len = self.cbuf.len().min(target);
+        if chunk_len < TAG_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::InvalidHeader,
+            ));
+        }
+        let plaintext_len = chunk_len - TAG_SIZE;
+
+        let nonce = chunk_nonce(self.counter, last);
+        let mut plaintext = self.cbuf[..plaintext_len].to_vec();
+        let tag: aead::Tag<ChaCha20Poly1305> =
+            GenericArray::clone_from_slice(&self.cbuf[plaintext_len..chunk_len]);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut plaintext, &tag)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::Aead))?;
+        self.cbuf.drain(..chunk_len);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::NonceOverflow))?;
+        self.done = last;
+        self.pbuf = plaintext;
+        self.ppos = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for AgeReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.ppos >= self.pbuf.len() && !self.done {
+            self.advance()?;
+        }
+        let avail = &self.pbuf[self.ppos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.ppos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,259 @@
+//! Line-wrapped Base64 ASCII armor, gated behind the `armor` feature,
+//! for embedding ciphertext in text-only contexts like email, YAML, or
+//! terminals.
+//!
+//! This is a plain text encoding, independent of the STREAM framing
+//! itself: [`ArmorWriter`] wraps any [`Write`], and [`ArmorReader`]
+//! wraps any [`Read`], so they compose with
+//! [`Writer`](crate::Writer)/[`Reader`](crate::Reader) the same way a
+//! gzip adapter composes with a socket -- encrypt first, then armor the
+//! result, and unarmor before decrypting.
+//!
+//! ```text
+//! -----BEGIN STREAM-----
+//! <base64, wrapped at 64 columns>
+//! -----END STREAM-----
+//! ```
+//!
+//! [`ArmorWriter::with_headers`] additionally supports a PEM-style
+//! `STREAM MESSAGE` block with `key: value` headers before the body,
+//! e.g. to record the stream format version or key ID so the armored
+//! file is self-describing and greppable without decoding it first:
+//!
+//! ```text
+//! -----BEGIN STREAM MESSAGE-----
+//! version: 1
+//! key-id: 0102030405060708
+//!
+//! <base64, wrapped at 64 columns>
+//! -----END STREAM MESSAGE-----
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+
+use crate::Error;
+
+/// The marker line written before a plain armored body.
+pub const BEGIN_MARKER: &str = "-----BEGIN STREAM-----";
+
+/// The marker line written after a plain armored body.
+pub const END_MARKER: &str = "-----END STREAM-----";
+
+/// The marker line written before a header-carrying armored body. See
+/// [`ArmorWriter::with_headers`].
+pub const BEGIN_MESSAGE_MARKER: &str = "-----BEGIN STREAM MESSAGE-----";
+
+/// The marker line written after a header-carrying armored body.
+pub const END_MESSAGE_MARKER: &str = "-----END STREAM MESSAGE-----";
+
+/// The number of Base64 characters per line of armored output.
+const LINE_WIDTH: usize = 64;
+
+/// Wraps a [`Write`]r, Base64-encoding everything written to it between
+/// [`BEGIN_MARKER`] and [`END_MARKER`], line-wrapped at [`LINE_WIDTH`]
+/// columns.
+pub struct ArmorWriter<W> {
+    w: W,
+    /// The marker line to write after the armored body; paired with
+    /// whichever `BEGIN_*` marker was written by `new`/`with_headers`.
+    end_marker: &'static str,
+    /// Raw bytes not yet long enough to fill a 3-byte Base64 group.
+    buf: Vec<u8>,
+    /// Base64 characters already encoded but not yet written out as a
+    /// full line.
+    line: String,
+}
+
+impl<W: Write> ArmorWriter<W> {
+    /// Starts a new armored stream, writing [`BEGIN_MARKER`] to `w`
+    /// immediately.
+    pub fn new(mut w: W) -> io::Result<Self> {
+        writeln!(w, "{BEGIN_MARKER}")?;
+        Ok(Self {
+            w,
+            end_marker: END_MARKER,
+            buf: Vec::with_capacity(3),
+            line: String::with_capacity(LINE_WIDTH),
+        })
+    }
+
+    /// Like [`ArmorWriter::new`], but writes a PEM-style
+    /// [`BEGIN_MESSAGE_MARKER`] block, recording `headers` as `key:
+    /// value` lines before the armored body. `headers` might record the
+    /// stream format version or key ID, so the file can be identified
+    /// by `grep` without decoding it first.
+    pub fn with_headers(mut w: W, headers: &[(&str, &str)]) -> io::Result<Self> {
+        writeln!(w, "{BEGIN_MESSAGE_MARKER}")?;
+        for (key, value) in headers {
+            writeln!(w, "{key}: {value}")?;
+        }
+        writeln!(w)?;
+        Ok(Self {
+            w,
+            end_marker: END_MESSAGE_MARKER,
+            buf: Vec::with_capacity(3),
+            line: String::with_capacity(LINE_WIDTH),
+        })
+    }
+
+    fn encode_groups(&mut self) -> io::Result<()> {
+        while self.buf.len() >= 3 {
+            let group: Vec<u8> = self.buf.drain(..3).collect();
+            self.line.push_str(&STANDARD.encode(group));
+            self.flush_full_lines()?;
+        }
+        Ok(())
+    }
+
+    fn flush_full_lines(&mut self) -> io::Result<()> {
+        while self.line.len() >= LINE_WIDTH {
+            let rest = self.line.split_off(LINE_WIDTH);
+            writeln!(self.w, "{}", self.line)?;
+            self.line = rest;
+        }
+        Ok(())
+    }
+
+    /// Finishes the armored stream: flushes any trailing partial group
+    /// (Base64-padded as needed), the last (possibly short) line, and
+    /// the matching end marker, then returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.buf.is_empty() {
+            self.line.push_str(&STANDARD.encode(&self.buf));
+        }
+        if !self.line.is_empty() {
+            writeln!(self.w, "{}", self.line)?;
+        }
+        writeln!(self.w, "{}", self.end_marker)?;
+        Ok(self.w)
+    }
+}
+
+impl<W: Write> Write for ArmorWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        self.encode_groups()?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Unwraps an [`ArmorWriter`]'s output, presenting the decoded bytes
+/// through [`Read`]. Transparently recognizes both the plain
+/// [`BEGIN_MARKER`] form and the header-carrying
+/// [`BEGIN_MESSAGE_MARKER`] form; see [`ArmorReader::headers`].
+pub struct ArmorReader<R> {
+    r: BufReader<R>,
+    /// The marker line that ends the armored body; whichever one
+    /// pairs with the `BEGIN_*` marker `new` read.
+    end_marker: &'static str,
+    /// The `key: value` headers read from a [`BEGIN_MESSAGE_MARKER`]
+    /// block, in order. Empty for the plain [`BEGIN_MARKER`] form.
+    headers: Vec<(String, String)>,
+    /// Decoded bytes from the current line not yet returned to the
+    /// caller.
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> ArmorReader<R> {
+    /// Opens an armored stream, reading and validating its begin marker
+    /// (and, for the [`BEGIN_MESSAGE_MARKER`] form, its headers) from
+    /// `r`.
+    pub fn new(r: R) -> io::Result<Self> {
+        let mut r = BufReader::new(r);
+        let mut line = String::new();
+        r.read_line(&mut line)?;
+        let (end_marker, has_headers) = match line.trim_end() {
+            BEGIN_MARKER => (END_MARKER, false),
+            BEGIN_MESSAGE_MARKER => (END_MESSAGE_MARKER, true),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::InvalidHeader,
+                ))
+            }
+        };
+
+        let mut headers = Vec::new();
+        if has_headers {
+            loop {
+                let mut header_line = String::new();
+                let n = r.read_line(&mut header_line)?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        Error::InvalidHeader,
+                    ));
+                }
+                let header_line = header_line.trim_end();
+                if header_line.is_empty() {
+                    break;
+                }
+                let (key, value) = header_line.split_once(':').ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader)
+                })?;
+                headers.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        Ok(Self {
+            r,
+            end_marker,
+            headers,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    /// The `key: value` headers recorded in a [`BEGIN_MESSAGE_MARKER`]
+    /// block, in the order they appeared. Empty for the plain
+    /// [`BEGIN_MARKER`] form.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    fn advance(&mut self) -> io::Result<()> {
+        let mut line = String::new();
+        let n = self.r.read_line(&mut line)?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::InvalidHeader,
+            ));
+        }
+        let line = line.trim_end();
+        if line == self.end_marker {
+            self.done = true;
+            self.buf.clear();
+            self.pos = 0;
+            return Ok(());
+        }
+        self.buf = STANDARD
+            .decode(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ArmorReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.buf.len() && !self.done {
+            self.advance()?;
+        }
+        let avail = &self.buf[self.pos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
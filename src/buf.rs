@@ -0,0 +1,204 @@
+use zeroize::Zeroize;
+
+use crate::CHUNK_SIZE;
+
+/// The length, in bytes, of an AEAD authentication tag.
+///
+/// This is fixed at 16 bytes, which covers every AEAD currently usable
+/// with this crate (`ChaCha20Poly1305`, `Aes256Gcm`, ...).
+pub(crate) const TAG_SIZE: usize = 16;
+
+/// A fixed-capacity buffer large enough to hold one plaintext chunk or
+/// one ciphertext chunk (plaintext plus an authentication tag).
+///
+/// [`Writer`](crate::Writer) accumulates plaintext here before sealing
+/// it in place; [`Reader`](crate::Reader) opens ciphertext into the
+/// same space.
+///
+/// The backing storage is zeroized whenever it's cleared and on drop,
+/// so plaintext doesn't linger in memory past the chunk that used it.
+///
+/// `bytes` is zero-initialized up front in [`Buf::new`], which costs a
+/// memset of the full chunk size on every `Writer`/`Reader`
+/// construction. Skipping that via `MaybeUninit` plus manual
+/// initialization tracking was evaluated and rejected: `storage_mut`
+/// hands out `&mut [u8; CHUNK_SIZE + TAG_SIZE]` (a reference to a fully
+/// initialized array) to both `Writer::flush_chunk` and the AEAD calls
+/// in `reader.rs`, and producing that reference over backing memory
+/// that isn't fully initialized is its own source of undefined
+/// behavior independent of whether the uninitialized bytes are ever
+/// read. Avoiding that would mean reshaping `storage_mut`'s signature
+/// and auditing every caller's access pattern against it -- real
+/// unsafe-code surface this crate has never needed (there is no other
+/// `unsafe` in it) for a one-time, per-stream memset. Not worth it
+/// here: this crate would rather pay the memset than carry the first
+/// unsafe block in a crypto library on a correctness argument this
+/// subtle.
+/// `bytes`'s storage: inline by default, or heap-allocated behind the
+/// `boxed` feature.
+///
+/// The inline array lives wherever its `Buf` lives, which is normally
+/// fine -- but a `Writer`/`Reader` embedding it is
+/// `CHUNK_SIZE + TAG_SIZE` bytes bigger than its other fields, so
+/// moving one around (returning it by value, boxing it up one level
+/// higher, recursing through it) copies that whole chunk each time.
+/// `boxed` moves the bytes to the heap once at construction so the
+/// `Writer`/`Reader` itself stays a handful of machine words.
+///
+/// Letting `boxed`'s allocation go through a caller-supplied allocator
+/// was evaluated and doesn't fit yet. The premise that this crate
+/// already enables `allocator_api` doesn't hold: it has no
+/// `#![feature(...)]` anywhere and no `rust-toolchain.toml` pinning a
+/// nightly compiler, so it targets stable Rust, and `core::alloc::Allocator`
+/// is still nightly-only. The stable workaround, the `allocator-api2`
+/// polyfill crate, would still mean threading a second generic
+/// allocator parameter through `Buf` and up through every one of
+/// `Writer`'s and `Reader`'s constructors (`new`, `with_digest`,
+/// `with_padding`, `with_derived_nonces`, `with_key_id`,
+/// `with_provider`, ...), doubling the constructor surface with `_in`
+/// variants the way `std::vec::Vec`/`Box` do -- a much bigger surface
+/// change than this one heap buffer. Left for a future request that's
+/// willing to take on that API growth.
+///
+/// Swapping `Storage` for a `heapless::Vec` was also evaluated, for
+/// firmware wanting a statically-sized, no-alloc buffer, and doesn't
+/// carry its weight here: the default (non-`boxed`) `Storage` above is
+/// already a plain fixed-size array with no allocation at all, so
+/// `heapless::Vec` would only add an unused length field and bounds
+/// checks `Buf` doesn't need (`len`/`cap` already live alongside it).
+/// The actual no-alloc blocker is
+/// [`Reader`](crate::Reader)'s ciphertext buffer, `cbuf: Vec<u8>`,
+/// which genuinely grows at runtime and isn't this type -- and the
+/// rest of this crate has no `#![no_std]` support (see the audit note
+/// in `reader.rs`) to make a `heapless` dependency pay for itself
+/// outside that one field.
+#[cfg(not(feature = "boxed"))]
+type Storage = [u8; CHUNK_SIZE + TAG_SIZE];
+
+#[cfg(feature = "boxed")]
+type Storage = Box<[u8]>;
+
+pub(crate) struct Buf {
+    bytes: Storage,
+    len: usize,
+    /// How much of `bytes[..len]` [`Buf::read_plaintext`] has already
+    /// handed back to a caller. [`Buf::set_len`] and [`Buf::clear`]
+    /// both reset this to zero, since either one means the buffer now
+    /// holds a fresh chunk's plaintext (or nothing) with no prior reads
+    /// against it -- which is what let this replace a second,
+    /// caller-tracked read-position field ([`Reader`](crate::Reader)
+    /// used to keep its own `ppos` in step with this buffer's `len` by
+    /// hand; now advancing `pos` and clearing it back to zero both live
+    /// on the one type that actually owns the invariant).
+    pos: usize,
+    /// The length at which [`Buf::is_full`] reports full and
+    /// [`Buf::fill`] stops accepting more bytes. This is [`CHUNK_SIZE`]
+    /// unless a digest footer needs to fit after the buffered
+    /// plaintext too, in which case it's smaller by the footer's
+    /// length.
+    cap: usize,
+}
+
+impl Buf {
+    pub(crate) fn new(cap: usize) -> Self {
+        debug_assert!(cap <= CHUNK_SIZE);
+        Self {
+            #[cfg(not(feature = "boxed"))]
+            bytes: [0u8; CHUNK_SIZE + TAG_SIZE],
+            #[cfg(feature = "boxed")]
+            bytes: vec![0u8; CHUNK_SIZE + TAG_SIZE].into_boxed_slice(),
+            len: 0,
+            pos: 0,
+            cap,
+        }
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == self.cap
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn remaining(&self) -> usize {
+        self.cap - self.len
+    }
+
+    /// Copies as much of `data` as fits before the buffer reaches its
+    /// capacity, returning the number of bytes copied.
+    pub(crate) fn fill(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(self.remaining());
+        self.bytes[self.len..self.len + n].copy_from_slice(&data[..n]);
+        self.len += n;
+        n
+    }
+
+    /// Appends `data` past the buffer's normal capacity, up to
+    /// [`CHUNK_SIZE`].
+    ///
+    /// Only valid for the final chunk of a stream, after which a
+    /// digest or padding footer may follow the buffered plaintext.
+    pub(crate) fn append_footer(&mut self, data: &[u8]) {
+        assert!(self.len + data.len() <= CHUNK_SIZE);
+        self.bytes[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+
+    /// How many more bytes can be appended past the buffer's normal
+    /// capacity via [`Buf::append_footer`] before hitting [`CHUNK_SIZE`].
+    pub(crate) fn footer_room(&self) -> usize {
+        CHUNK_SIZE - self.len
+    }
+
+    /// The full backing storage, for in-place AEAD operations that need
+    /// room to grow the plaintext into a ciphertext-plus-tag.
+    pub(crate) fn storage_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+
+    pub(crate) fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.bytes.len());
+        self.len = len;
+        self.pos = 0;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.bytes[..self.len].zeroize();
+        self.len = 0;
+        self.pos = 0;
+    }
+
+    /// Whether every byte written into this buffer (up to [`Buf::len`])
+    /// has already been handed back via [`Buf::read_plaintext`].
+    pub(crate) fn is_drained(&self) -> bool {
+        self.pos >= self.len
+    }
+
+    /// Copies as much not-yet-consumed plaintext as fits into `out`,
+    /// advancing the internal read position by however much was
+    /// copied. Returns the number of bytes copied.
+    pub(crate) fn read_plaintext(&mut self, out: &mut [u8]) -> usize {
+        let avail = &self.bytes[self.pos..self.len];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.pos += n;
+        n
+    }
+
+    /// The not-yet-consumed plaintext, without advancing the internal
+    /// read position the way [`Buf::read_plaintext`] does.
+    ///
+    /// For [`Reader::peek`](crate::Reader::peek), which needs to hand a
+    /// caller a look at buffered plaintext without committing to having
+    /// returned it.
+    pub(crate) fn plaintext_remaining(&self) -> &[u8] {
+        &self.bytes[self.pos..self.len]
+    }
+}
+
+impl Drop for Buf {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
@@ -0,0 +1,81 @@
+//! Shared layout arithmetic for locating a chunk by index in a stream
+//! sealed with the default, fixed-size, prefix-and-counter nonce
+//! construction -- the one thing [`patch`](crate::patch),
+//! [`encrypted_file`](crate::encrypted_file), and [`mmap`](crate::mmap)
+//! all need before they can touch one chunk without walking the stream
+//! from the start the way [`Reader`](crate::Reader) does.
+//!
+//! [`ChunkLayout::compute`] needs only the ciphertext's total length:
+//! every non-final chunk is exactly [`CHUNK_SIZE`] bytes of plaintext,
+//! so only the final chunk's length varies, and it's pinned down by
+//! whatever's left over after dividing the rest evenly.
+
+use std::io;
+
+use crate::buf::TAG_SIZE;
+use crate::header::HEADER_LEN;
+use crate::{Error, CHUNK_SIZE};
+
+/// Which chunk is the final one, how long its plaintext is, and the
+/// stream's total plaintext length.
+pub(crate) struct ChunkLayout {
+    pub(crate) final_chunk_index: u64,
+    pub(crate) final_chunk_len: usize,
+    pub(crate) total_len: u64,
+}
+
+impl ChunkLayout {
+    /// Derives a stream's chunk layout from `body_len`, the ciphertext's
+    /// length with the header excluded.
+    pub(crate) fn compute(body_len: u64) -> io::Result<Self> {
+        let full_ct_len = (CHUNK_SIZE + TAG_SIZE) as u64;
+        let full_chunks = body_len / full_ct_len;
+        let remainder = body_len % full_ct_len;
+        if remainder == 0 {
+            if full_chunks == 0 {
+                return Ok(Self {
+                    final_chunk_index: 0,
+                    final_chunk_len: 0,
+                    total_len: 0,
+                });
+            }
+            let final_chunk_index = full_chunks - 1;
+            Ok(Self {
+                final_chunk_index,
+                final_chunk_len: CHUNK_SIZE,
+                total_len: final_chunk_index * CHUNK_SIZE as u64 + CHUNK_SIZE as u64,
+            })
+        } else if remainder < TAG_SIZE as u64 {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::TruncatedChunk {
+                    chunk: full_chunks,
+                    offset: HEADER_LEN as u64 + full_chunks * full_ct_len,
+                },
+            ))
+        } else {
+            let final_chunk_len = (remainder - TAG_SIZE as u64) as usize;
+            Ok(Self {
+                final_chunk_index: full_chunks,
+                final_chunk_len,
+                total_len: full_chunks * CHUNK_SIZE as u64 + final_chunk_len as u64,
+            })
+        }
+    }
+
+    /// The plaintext length of chunk `index`, which must be
+    /// `<= self.final_chunk_index`.
+    pub(crate) fn chunk_len(&self, index: u64) -> usize {
+        if index == self.final_chunk_index {
+            self.final_chunk_len
+        } else {
+            CHUNK_SIZE
+        }
+    }
+
+    /// The ciphertext offset (sealed plaintext plus tag) at which chunk
+    /// `index` begins.
+    pub(crate) fn chunk_offset(index: u64) -> u64 {
+        HEADER_LEN as u64 + index * (CHUNK_SIZE + TAG_SIZE) as u64
+    }
+}
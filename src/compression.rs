@@ -0,0 +1,227 @@
+//! An optional compress-then-encrypt adapter, gated behind the
+//! `compression` feature, for streams whose plaintext is large and
+//! compressible (e.g. backups).
+//!
+//! [`CompressWriter`] Deflate-compresses plaintext before handing it to
+//! a [`Writer`](crate::Writer), and [`CompressReader`] inflates it back
+//! after a [`Reader`](crate::Reader) decrypts it, the same way
+//! [`ArmorWriter`](crate::ArmorWriter)/[`ArmorReader`](crate::ArmorReader)
+//! wrap the core types for an orthogonal concern.
+//!
+//! The header's compressed flag is plain metadata, like its digest and
+//! padding flags: every AEAD call in this crate uses empty associated
+//! data, so no header field is cryptographically bound to the
+//! ciphertext. [`CompressReader::new`] only uses the flag to refuse to
+//! inflate a stream that wasn't written by [`CompressWriter`].
+//!
+//! Only Deflate is supported, via the `flate2` crate's pure-Rust
+//! backend; streams that would benefit from a higher-ratio codec like
+//! Zstd are out of scope for this module.
+//!
+//! # Security
+//!
+//! Compressing plaintext before encrypting it can leak information
+//! about that plaintext through the *length* of the resulting
+//! ciphertext, even though the ciphertext's bytes stay opaque -- the
+//! same class of compression-oracle side channel as CRIME and BREACH.
+//! If the plaintext mixes a secret (a session token, a password) with
+//! data an attacker can influence and observe the length of (a
+//! request path, a form field they control that ends up in the same
+//! stream), repeatedly compressing guesses alongside the secret and
+//! watching which guesses compress smaller can reveal the secret one
+//! byte at a time, because Deflate's back-references make the output
+//! shrink when a guess happens to overlap with the secret. Don't wrap
+//! [`CompressWriter`] around plaintext built this way; it's not a
+//! defect in this module specifically, but a property of compressing
+//! attacker-influenced data next to secrets under any codec.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+
+use crate::nonce::PREFIX_LEN;
+use crate::{Error, Reader, Writer};
+
+/// The size, in bytes, of the scratch buffer used to hold one call's
+/// worth of compressed or decompressed output.
+const BUF_SIZE: usize = 8 * 1024;
+
+/// Compresses plaintext written to it and seals the result through a
+/// [`Writer`].
+pub struct CompressWriter<W, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    w: Writer<W, A>,
+    compress: Compress,
+    scratch: [u8; BUF_SIZE],
+}
+
+impl<W, A> CompressWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Starts a new stream, writing its header (marked compressed) to
+    /// `w` immediately. See [`Writer::new`] for `nonce_prefix`'s
+    /// requirements.
+    pub fn new(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Ok(Self {
+            w: Writer::new_compressed(w, key, nonce_prefix)?,
+            compress: Compress::new(Compression::default(), false),
+            scratch: [0u8; BUF_SIZE],
+        })
+    }
+
+    /// Runs `input` through the Deflate compressor, writing every
+    /// produced byte to the inner [`Writer`] as it's made, until `flush`
+    /// is satisfied.
+    fn pump(&mut self, mut input: &[u8], flush: FlushCompress) -> io::Result<()> {
+        loop {
+            let before_in = self.compress.total_in();
+            let before_out = self.compress.total_out();
+            let status = self
+                .compress
+                .compress(input, &mut self.scratch, flush)
+                .map_err(|_| io::Error::other(Error::Compression))?;
+            let consumed = (self.compress.total_in() - before_in) as usize;
+            let produced = (self.compress.total_out() - before_out) as usize;
+            if produced > 0 {
+                self.w.write_all(&self.scratch[..produced])?;
+            }
+            input = &input[consumed..];
+            match status {
+                Status::StreamEnd => return Ok(()),
+                _ if input.is_empty() && produced == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Finishes the stream: flushes the last of the compressor's
+    /// internal state, seals any buffered plaintext as the final chunk,
+    /// then returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.pump(&[], FlushCompress::Finish)?;
+        self.w.finish()
+    }
+}
+
+impl<W, A> Write for CompressWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.pump(data, FlushCompress::None)?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Opens a stream sealed by [`CompressWriter`], decrypting through a
+/// [`Reader`] and inflating the result.
+pub struct CompressReader<R, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    r: Reader<R, A>,
+    decompress: Decompress,
+    /// Ciphertext-derived, still-compressed bytes read from `r` but not
+    /// yet consumed by the decompressor.
+    in_buf: Vec<u8>,
+    r_done: bool,
+    /// Decompressed plaintext not yet returned to the caller.
+    out_buf: Vec<u8>,
+    out_pos: usize,
+    stream_end: bool,
+}
+
+impl<R, A> CompressReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a stream, reading and validating its header from `r` and
+    /// rejecting one that isn't marked compressed.
+    pub fn new(r: R, key: &Key<A>) -> io::Result<Self> {
+        let r = Reader::new(r, key)?;
+        if !r.compressed() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::InvalidHeader,
+            ));
+        }
+        Ok(Self {
+            r,
+            decompress: Decompress::new(false),
+            in_buf: Vec::new(),
+            r_done: false,
+            out_buf: Vec::new(),
+            out_pos: 0,
+            stream_end: false,
+        })
+    }
+
+    /// Decrypts and inflates until `out_buf` has bytes to return or the
+    /// stream is exhausted.
+    fn advance(&mut self) -> io::Result<()> {
+        while self.out_pos >= self.out_buf.len() && !self.stream_end {
+            if self.in_buf.is_empty() && !self.r_done {
+                let mut chunk = [0u8; BUF_SIZE];
+                let n = self.r.read(&mut chunk)?;
+                if n == 0 {
+                    self.r_done = true;
+                } else {
+                    self.in_buf.extend_from_slice(&chunk[..n]);
+                }
+            }
+            let flush = if self.r_done {
+                FlushDecompress::Finish
+            } else {
+                FlushDecompress::None
+            };
+            let mut scratch = [0u8; BUF_SIZE];
+            let before_in = self.decompress.total_in();
+            let before_out = self.decompress.total_out();
+            let status = self
+                .decompress
+                .decompress(&self.in_buf, &mut scratch, flush)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::Compression))?;
+            let consumed = (self.decompress.total_in() - before_in) as usize;
+            let produced = (self.decompress.total_out() - before_out) as usize;
+            self.in_buf.drain(..consumed);
+            self.out_buf = scratch[..produced].to_vec();
+            self.out_pos = 0;
+            if status == Status::StreamEnd {
+                self.stream_end = true;
+            } else if consumed == 0 && produced == 0 && self.r_done {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    Error::Compression,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R, A> Read for CompressReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.advance()?;
+        let avail = &self.out_buf[self.out_pos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,121 @@
+//! Convergent nonce-prefix derivation, for dedup storage backends.
+//!
+//! Every [`Writer::new`](crate::Writer::new)-style constructor in this
+//! crate takes `nonce_prefix` as a caller-supplied value specifically so
+//! callers with their own source of per-stream uniqueness don't have to
+//! go through an RNG; [`convergent_nonce_prefix`] is one such source,
+//! for callers that want the opposite of uniqueness -- a backup or
+//! archival system that stores ciphertexts in a content-addressed
+//! store, say, wants two uploads of the same plaintext under the same
+//! key to produce the exact same ciphertext, so the store only pays to
+//! keep one copy.
+//!
+//! [`convergent_nonce_prefix`] derives `nonce_prefix` from the key and
+//! the full plaintext via HKDF-SHA256, instead of drawing it from an
+//! RNG: the same `(key, plaintext)` pair always expands to the same
+//! prefix, and since the AEAD itself is deterministic given a fixed key
+//! and nonce, the whole ciphertext comes out byte-identical too.
+//! Nothing else in this crate changes -- [`Reader`](crate::Reader)
+//! opens a convergently-sealed stream exactly like any other, since the
+//! prefix still arrives in the header the normal way; only the writing
+//! side needs to know where this particular prefix came from.
+//!
+//! # Privacy trade-offs
+//!
+//! Convergence is a direct trade against two things this crate's other
+//! nonce choices don't give up:
+//!
+//! - **Equality leaks, even without the key.** Two ciphertexts that
+//!   happen to be identical *must* have sealed identical plaintext
+//!   under the same key. Anyone who can see a store's ciphertexts --
+//!   including the storage provider dedup exists to save money for --
+//!   learns which uploads are duplicates of each other, and how many
+//!   times a given one recurs, without ever decrypting anything. A
+//!   random `nonce_prefix` gives none of that away; this does, by
+//!   design, because dedup can't work without it.
+//! - **Confirmation-of-a-file attacks are possible for whoever holds
+//!   `key`.** Deriving the prefix from `key` (not from the plaintext
+//!   alone, the way naive convergent-encryption schemes do) means an
+//!   attacker without `key` can't compute the prefix for a guessed
+//!   plaintext and check it against a target ciphertext. An attacker
+//!   who *does* hold `key` -- a scenario this crate otherwise treats as
+//!   total compromise -- can still use this to confirm a specific file
+//!   is present in a store of ciphertexts they can see but not
+//!   decrypt, which isn't possible against a randomly-chosen prefix
+//!   even with the key.
+//!
+//! Both are inherent to deduplicating encrypted data at all, not a
+//! shortcoming of this particular derivation; callers should only reach
+//! for [`convergent_nonce_prefix`] when storage savings are worth those
+//! two properties, and keep using a random prefix everywhere else.
+//!
+//! # Streaming trade-off
+//!
+//! [`convergent_nonce_prefix`] hashes the whole plaintext before
+//! returning anything, so callers need it in memory up front; this
+//! necessarily gives up the online, write-as-you-go property
+//! [`Writer`](crate::Writer) otherwise offers, and only deduplicates
+//! whole streams that match byte-for-byte, not chunks reused across
+//! otherwise-different streams. Deduplicating at chunk granularity
+//! would mean deriving a nonce per [`cdc`](crate::cdc) chunk instead of
+//! per stream, which needs its own explicit per-chunk nonce on the wire
+//! (unlike the counter-based schemes [`length_prefixed`](crate::length_prefixed)
+//! and [`Writer`](crate::Writer) use) and isn't what this module does.
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::nonce::PREFIX_LEN;
+
+/// Derives a stream's `nonce_prefix` from `key` and the full `plaintext`
+/// it's about to seal, via HKDF-SHA256, so that encrypting the same
+/// plaintext under the same key always produces the same prefix -- and
+/// so the same ciphertext -- instead of a fresh one each time. See the
+/// module-level doc comment for the privacy and streaming trade-offs
+/// this implies.
+///
+/// Pass the result straight to [`Writer::new`](crate::Writer::new),
+/// [`MessageWriter::new`](crate::MessageWriter::new), or
+/// [`LengthPrefixedWriter::new`](crate::LengthPrefixedWriter::new) in
+/// place of a randomly-generated prefix; nothing downstream needs to
+/// know it was derived this way.
+pub fn convergent_nonce_prefix(key: &[u8], plaintext: &[u8]) -> [u8; PREFIX_LEN] {
+    let hk = Hkdf::<Sha256>::new(None, key);
+    let mut prefix = [0u8; PREFIX_LEN];
+    hk.expand(plaintext, &mut prefix)
+        .expect("4 bytes is well within HKDF-SHA256's output size limit");
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convergent_nonce_prefix;
+
+    #[test]
+    fn identical_inputs_derive_identical_prefixes() {
+        let key = [0x42u8; 32];
+        let plaintext = b"the quick brown fox";
+        assert_eq!(
+            convergent_nonce_prefix(&key, plaintext),
+            convergent_nonce_prefix(&key, plaintext)
+        );
+    }
+
+    #[test]
+    fn different_plaintexts_derive_different_prefixes() {
+        let key = [0x42u8; 32];
+        assert_ne!(
+            convergent_nonce_prefix(&key, b"the quick brown fox"),
+            convergent_nonce_prefix(&key, b"the lazy dog")
+        );
+    }
+
+    #[test]
+    fn different_keys_derive_different_prefixes_for_the_same_plaintext() {
+        let plaintext = b"the quick brown fox";
+        assert_ne!(
+            convergent_nonce_prefix(&[0x42u8; 32], plaintext),
+            convergent_nonce_prefix(&[0x24u8; 32], plaintext)
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Standalone, self-contained sealing for transports where "stream of
+//! bytes, delivered in order" doesn't hold -- UDP, QUIC datagrams, and
+//! similar, where each packet arrives (or doesn't) on its own, possibly
+//! out of order.
+//!
+//! Every other framing in this crate assumes the next bytes a
+//! [`Reader`](crate::Reader)/[`MessageReader`](crate::MessageReader)
+//! sees are the next bytes the matching writer produced: the chunk
+//! counter advances by exactly one per chunk, in order, because the
+//! underlying [`Read`](std::io::Read) guarantees it. That assumption is
+//! exactly what a datagram transport doesn't give you. [`DatagramSealer`]
+//! and [`DatagramOpener`] drop it: each datagram carries its own nonce
+//! prefix and an explicit sequence number rather than relying on either
+//! side tracking "the next expected one", so datagrams can be opened in
+//! any order, or not at all if the transport drops them, with no shared
+//! state between calls other than the key.
+//!
+//! Repeating the 4-byte nonce prefix on every datagram, instead of
+//! sending it once up front the way [`Writer::new`](crate::Writer::new)
+//! does, was the deliberate choice here: "send it once" only works if
+//! that one packet is guaranteed to arrive, which is exactly what these
+//! transports don't guarantee. Four bytes of repeated overhead per
+//! datagram is a small price for not having a single dropped packet
+//! take the whole session down with it.
+//!
+//! The sequence number is supplied by the caller rather than kept as
+//! internal state, unlike every counter elsewhere in this crate:
+//! QUIC already assigns each datagram a monotonic packet number, and a
+//! caller building on top of it should reuse that number as the AEAD
+//! counter instead of keeping a second one in lockstep. Whatever the
+//! caller uses, it must never repeat under the same key and nonce
+//! prefix, the same requirement [`Writer::new`](crate::Writer::new)'s
+//! `nonce_prefix` places on prefixes.
+//!
+//! That same lack of ordering means a re-sent or duplicated datagram
+//! looks exactly like a legitimate one to [`DatagramOpener::open`]: it
+//! has no notion of "already seen". [`ReplayWindow`] adds that back as
+//! an opt-in companion rather than a change to `open` itself --
+//! [`DatagramOpener::open_checked`] is the version that consults one.
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::{Error, Result};
+
+/// The length, in bytes, of a datagram's nonce prefix.
+pub const PREFIX_LEN: usize = 4;
+
+/// The length, in bytes, of a datagram's explicit sequence number.
+const SEQ_LEN: usize = 8;
+
+const TAG_SIZE: usize = 16;
+
+/// The number of trailing sequence numbers [`ReplayWindow`] remembers
+/// behind the highest one it's seen, matching the window size common
+/// IPsec implementations default to.
+const WINDOW_SIZE: u64 = 64;
+
+/// Builds the 96-bit nonce for sequence number `seq`.
+fn build_nonce(prefix: &[u8; PREFIX_LEN], seq: u64) -> GenericArray<u8, U12> {
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..].copy_from_slice(&seq.to_be_bytes());
+    nonce
+}
+
+/// Reads a datagram's explicit sequence number without decrypting it,
+/// after checking it's at least long enough to hold one.
+fn peek_seq(datagram: &[u8]) -> Result<u64> {
+    if datagram.len() < PREFIX_LEN + SEQ_LEN + TAG_SIZE {
+        return Err(Error::InvalidHeader);
+    }
+    let mut seq_buf = [0u8; SEQ_LEN];
+    seq_buf.copy_from_slice(&datagram[PREFIX_LEN..PREFIX_LEN + SEQ_LEN]);
+    Ok(u64::from_be_bytes(seq_buf))
+}
+
+/// Seals plaintexts into standalone datagrams, each carrying its own
+/// nonce prefix and an explicit, caller-supplied sequence number.
+pub struct DatagramSealer<A> {
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+}
+
+impl<A> DatagramSealer<A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Creates a sealer for one session's worth of datagrams.
+    ///
+    /// `nonce_prefix` must be unique for every session sealed under
+    /// `key`, the same requirement as [`Writer::new`](crate::Writer::new).
+    pub fn new(key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> Self {
+        Self {
+            aead: A::new(key),
+            nonce_prefix,
+        }
+    }
+
+    /// Seals `plaintext` as one self-contained datagram carrying `seq`.
+    ///
+    /// `seq` must never repeat across calls on this sealer: it's used
+    /// as the chunk counter half of the AEAD nonce, the same as the
+    /// counter [`Writer`](crate::Writer) keeps internally, except the
+    /// caller supplies it directly instead of it being tracked here.
+    pub fn seal(&self, seq: u64, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = build_nonce(&self.nonce_prefix, seq);
+        let mut sealed = plaintext.to_vec();
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, b"", &mut sealed)
+            .expect("sealing a datagram cannot fail");
+        let mut out = Vec::with_capacity(PREFIX_LEN + SEQ_LEN + sealed.len() + TAG_SIZE);
+        out.extend_from_slice(&self.nonce_prefix);
+        out.extend_from_slice(&seq.to_be_bytes());
+        out.extend_from_slice(&sealed);
+        out.extend_from_slice(&tag);
+        out
+    }
+}
+
+/// Opens datagrams sealed by [`DatagramSealer`].
+pub struct DatagramOpener<A> {
+    aead: A,
+}
+
+impl<A> DatagramOpener<A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Creates an opener for datagrams sealed under `key`.
+    pub fn new(key: &Key<A>) -> Self {
+        Self { aead: A::new(key) }
+    }
+
+    /// Opens one datagram, returning its sequence number and plaintext.
+    ///
+    /// Datagrams can be opened in any order, and opening one doesn't
+    /// require having opened any other: detecting replay or reordering
+    /// from the returned sequence number, if the caller's transport
+    /// needs that, is the caller's job, since this type keeps no
+    /// "next expected sequence number" to check against.
+    pub fn open(&self, datagram: &[u8]) -> Result<(u64, Vec<u8>)> {
+        let seq = peek_seq(datagram)?;
+        let mut nonce_prefix = [0u8; PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&datagram[..PREFIX_LEN]);
+
+        let sealed = &datagram[PREFIX_LEN + SEQ_LEN..];
+        let plaintext_len = sealed.len() - TAG_SIZE;
+        let nonce = build_nonce(&nonce_prefix, seq);
+        let mut plaintext = sealed[..plaintext_len].to_vec();
+        let tag: aead::Tag<A> = GenericArray::clone_from_slice(&sealed[plaintext_len..]);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut plaintext, &tag)
+            .map_err(|_| Error::Aead)?;
+        Ok((seq, plaintext))
+    }
+
+    /// Like [`DatagramOpener::open`], but checks `window` first and
+    /// rejects a duplicate or too-old sequence number with
+    /// [`Error::Replayed`] instead of decrypting it again, the way a
+    /// DTLS or IPsec receiver's sliding-window anti-replay filter does.
+    ///
+    /// The check happens before decryption, so a flood of replayed
+    /// datagrams costs a window lookup each instead of a full AEAD
+    /// call; `window` only advances once `datagram` has also
+    /// successfully authenticated.
+    pub fn open_checked(
+        &self,
+        window: &mut ReplayWindow,
+        datagram: &[u8],
+    ) -> Result<(u64, Vec<u8>)> {
+        let seq = peek_seq(datagram)?;
+        if window.is_replay(seq) {
+            return Err(Error::Replayed);
+        }
+        let (seq, plaintext) = self.open(datagram)?;
+        window.accept(seq);
+        Ok((seq, plaintext))
+    }
+}
+
+/// A sliding-window replay filter for [`DatagramOpener`], modeled on
+/// the anti-replay window DTLS and IPsec receivers use: a sequence
+/// number is accepted at most once, and only if it isn't so far behind
+/// the highest one seen that it's fallen out of the window entirely.
+///
+/// Kept separate from [`DatagramOpener`] itself rather than built in,
+/// so callers that already dedupe elsewhere (a transport with its own
+/// anti-replay, say) aren't stuck paying for a second window they don't
+/// need: see [`DatagramOpener::open`] for opening without one.
+pub struct ReplayWindow {
+    highest: Option<u64>,
+    /// Bitmap of the `WINDOW_SIZE` sequence numbers at and below
+    /// `highest`; bit 0 is `highest` itself.
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// Creates an empty window: the first sequence number checked
+    /// against it is always accepted.
+    pub fn new() -> Self {
+        Self {
+            highest: None,
+            seen: 0,
+        }
+    }
+
+    /// Returns whether `seq` is a duplicate, or far enough behind the
+    /// highest sequence number seen to be indistinguishable from one,
+    /// without recording it as seen.
+    ///
+    /// Call [`ReplayWindow::accept`] once `seq`'s datagram has
+    /// successfully authenticated to actually advance the window;
+    /// this method alone never does.
+    pub fn is_replay(&self, seq: u64) -> bool {
+        match self.highest {
+            None => false,
+            Some(highest) if seq > highest => false,
+            Some(highest) => {
+                let back = highest - seq;
+                back >= WINDOW_SIZE || self.seen & (1 << back) != 0
+            }
+        }
+    }
+
+    /// Records `seq` as seen, advancing the window if `seq` is now the
+    /// highest sequence number seen.
+    ///
+    /// Call only after `seq`'s datagram has successfully authenticated:
+    /// marking an unauthenticated sequence number as seen would let an
+    /// attacker burn a window slot for a sequence number that was
+    /// never legitimately sent, at no cost to themselves.
+    pub fn accept(&mut self, seq: u64) {
+        match self.highest {
+            None => {
+                self.highest = Some(seq);
+                self.seen = 1;
+            }
+            Some(highest) if seq > highest => {
+                let shift = seq - highest;
+                self.seen = if shift >= WINDOW_SIZE {
+                    0
+                } else {
+                    self.seen << shift
+                };
+                self.seen |= 1;
+                self.highest = Some(seq);
+            }
+            Some(highest) => {
+                let back = highest - seq;
+                if back < WINDOW_SIZE {
+                    self.seen |= 1 << back;
+                }
+            }
+        }
+    }
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
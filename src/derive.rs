@@ -0,0 +1,112 @@
+//! HKDF-derived per-chunk nonces.
+//!
+//! [`nonce::build`](crate::nonce::build) constructs a chunk's nonce by
+//! concatenating the stream's random prefix with the chunk counter and
+//! final-chunk flag. That's enough entropy as long as the prefix itself
+//! is large relative to the nonce: for a 96-bit nonce that's a 32-bit
+//! prefix, a comfortable margin against collisions across streams.
+//!
+//! [`NonceDeriver`] offers a stronger alternative: it runs the stream's
+//! key and random prefix through HKDF-SHA256 once (the "extract" step)
+//! and then expands a fresh 96-bit nonce per chunk from the counter and
+//! final-chunk flag. Every output bit depends on the whole key and
+//! prefix rather than just the fixed slots the naive concatenation
+//! assigns them, which is the construction Tink's streaming AEAD uses
+//! for the same reason. See [`Writer::with_derived_nonces`].
+//!
+//! [`NonceDeriver::new_bound`] goes one step further, for
+//! [`Version::V2`](crate::Version::V2): it also mixes the wire version,
+//! [`CHUNK_SIZE`], and the AEAD algorithm's [`AlgorithmId`] into every
+//! chunk's HKDF context, so the same key and `nonce_prefix` can't derive
+//! colliding nonces across two streams that disagree on any of those --
+//! e.g. one sealed with `large_chunks` enabled and one without, or one
+//! sealed under `ChaCha20Poly1305` and one (mistakenly) opened as
+//! `Aes256Gcm`. See [`Writer::with_bound_nonces`].
+//!
+//! [`Writer::with_derived_nonces`]: crate::Writer::with_derived_nonces
+//! [`Writer::with_bound_nonces`]: crate::Writer::with_bound_nonces
+//! [`CHUNK_SIZE`]: crate::CHUNK_SIZE
+
+use aead::generic_array::typenum::U12;
+use aead::generic_array::GenericArray;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::header::Version;
+use crate::nonce::PREFIX_LEN;
+use crate::CHUNK_SIZE;
+
+/// A stable, 4-byte identifier for a concrete AEAD algorithm, used by
+/// [`NonceDeriver::new_bound`] to bind [`Version::V2`]'s derived nonces
+/// to the exact algorithm a stream was sealed under.
+///
+/// This crate accepts any `A` satisfying [`Writer`](crate::Writer)'s
+/// usual `AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit` bound,
+/// including algorithms it's never heard of, so it can't hand out an
+/// identifier for every possible `A` itself. It implements this trait
+/// below for the one concrete algorithm it already depends on
+/// ([`ChaCha20Poly1305`](chacha20poly1305::ChaCha20Poly1305)); any other
+/// `A` needs its own `impl AlgorithmId` before
+/// [`Writer::with_bound_nonces`](crate::Writer::with_bound_nonces) will
+/// accept it.
+pub trait AlgorithmId {
+    /// This algorithm's identifier. Pick four bytes unique among every
+    /// algorithm ever used with the same key -- IANA's AEAD registry ID
+    /// (as a big-endian `u32`), say, for anything registered there.
+    const ALGORITHM_ID: [u8; 4];
+}
+
+impl AlgorithmId for chacha20poly1305::ChaCha20Poly1305 {
+    /// IANA AEAD registry ID 3 (`AEAD_CHACHA20_POLY1305`, RFC 8439).
+    const ALGORITHM_ID: [u8; 4] = 3u32.to_be_bytes();
+}
+
+/// Derives per-chunk nonces from a stream's key and random prefix.
+pub(crate) struct NonceDeriver {
+    hk: Hkdf<Sha256>,
+    /// Extra bytes prepended to every chunk's HKDF `info`, ahead of the
+    /// counter and final-chunk flag. Empty for [`NonceDeriver::new`]
+    /// (`V1`); the encoded version, [`CHUNK_SIZE`], and
+    /// [`AlgorithmId::ALGORITHM_ID`] for [`NonceDeriver::new_bound`]
+    /// (`V2`).
+    context: Vec<u8>,
+}
+
+impl NonceDeriver {
+    /// Runs the HKDF extract step over `ikm` (the stream's AEAD key)
+    /// salted with `nonce_prefix`, for [`Version::V1`]'s unbound
+    /// derived nonces.
+    pub(crate) fn new(ikm: &[u8], nonce_prefix: &[u8; PREFIX_LEN]) -> Self {
+        Self {
+            hk: Hkdf::<Sha256>::new(Some(nonce_prefix), ikm),
+            context: Vec::new(),
+        }
+    }
+
+    /// Like [`NonceDeriver::new`], but for [`Version::V2`]: every
+    /// chunk's nonce additionally depends on the wire version,
+    /// [`CHUNK_SIZE`], and `A`'s [`AlgorithmId`].
+    pub(crate) fn new_bound<A: AlgorithmId>(ikm: &[u8], nonce_prefix: &[u8; PREFIX_LEN]) -> Self {
+        let mut context = Vec::with_capacity(1 + 8 + 4);
+        context.push(Version::V2.to_byte());
+        context.extend_from_slice(&(CHUNK_SIZE as u64).to_be_bytes());
+        context.extend_from_slice(&A::ALGORITHM_ID);
+        Self {
+            hk: Hkdf::<Sha256>::new(Some(nonce_prefix), ikm),
+            context,
+        }
+    }
+
+    /// Expands the nonce for chunk `counter`. `last` must be `true`
+    /// only for the final chunk of the stream.
+    pub(crate) fn derive(&self, counter: u64, last: bool) -> GenericArray<u8, U12> {
+        let mut info = self.context.clone();
+        info.extend_from_slice(&counter.to_be_bytes());
+        info.push(last as u8);
+        let mut nonce = GenericArray::<u8, U12>::default();
+        self.hk
+            .expand(&info, &mut nonce)
+            .expect("12 bytes is well within HKDF-SHA256's output size limit");
+        nonce
+    }
+}
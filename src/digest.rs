@@ -0,0 +1,76 @@
+//! Whole-stream digest footer.
+//!
+//! When enabled, the [`Writer`](crate::Writer) hashes every plaintext
+//! byte as it is written and, once the stream is finished, seals the
+//! digest into one extra chunk appended after the last plaintext chunk.
+//! [`Reader`](crate::Reader) recomputes the same digest while decrypting
+//! and compares it against the sealed footer, returning
+//! [`Error::DigestMismatch`](crate::Error::DigestMismatch) if they
+//! disagree. Since the footer chunk is itself AEAD-sealed, an attacker
+//! cannot forge a matching digest for tampered plaintext.
+
+use sha2::Digest as _;
+
+/// The digest algorithm used for a stream's footer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DigestAlgorithm {
+    /// SHA-256, as implemented by [`sha2`].
+    Sha256,
+    /// BLAKE3, as implemented by [`blake3`].
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    /// The length, in bytes, of a digest produced by this algorithm.
+    pub const fn digest_len(self) -> usize {
+        32
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            Self::Sha256 => 1,
+            Self::Blake3 => 2,
+        }
+    }
+
+    pub(crate) fn from_byte(b: u8) -> Option<Option<Self>> {
+        match b {
+            0 => Some(None),
+            1 => Some(Some(Self::Sha256)),
+            2 => Some(Some(Self::Blake3)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn hasher(self) -> Hasher {
+        match self {
+            Self::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            Self::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+/// A running hash over the plaintext of a stream.
+pub(crate) enum Hasher {
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
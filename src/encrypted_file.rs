@@ -0,0 +1,329 @@
+//! A random-access, `Read + Write + Seek` view over an already-written
+//! stream, for callers that want to patch an encrypted file almost the
+//! way they'd patch a plain one.
+//!
+//! [`Writer`](crate::Writer) only ever appends; [`rewrite_chunk`] added
+//! a way to overwrite one known chunk of a stream sealed with the
+//! default nonce construction, but a caller working at a plaintext byte
+//! offset still has to work out which chunk that offset falls in, load
+//! it, splice in their change, and reseal it by hand. [`EncryptedFile`]
+//! does that bookkeeping once, behind ordinary [`Read`], [`Write`], and
+//! [`Seek`] impls over the stream's plaintext.
+//!
+//! # Chunk cache
+//!
+//! [`EncryptedFile`] keeps at most one chunk's plaintext decrypted at a
+//! time. A read or write at some plaintext offset decrypts that
+//! offset's chunk into the cache if it isn't already there -- evicting
+//! and, if it was written to, resealing and writing back whatever chunk
+//! was cached before. [`Write::flush`](std::io::Write::flush) reseals
+//! the cached chunk without evicting it, so a caller can make sure a
+//! change has actually reached `S` without losing the benefit of the
+//! cache for the next access to the same chunk.
+//!
+//! # Limitations
+//!
+//! - This only opens a stream written by
+//!   [`Writer::new`](crate::Writer::new) (or an equivalent plain
+//!   constructor): [`EncryptedFile::open`] rejects a header with
+//!   padding, a digest footer, derived nonces, or compression set, the
+//!   same set [`rewrite_chunk`] already can't handle and for the same
+//!   reasons -- a digest or length footer over the whole plaintext
+//!   would need recomputing on every write, and derived nonces aren't
+//!   the construction this module rebuilds.
+//! - Only overwrites are supported: [`Write::write`](std::io::Write)
+//!   never extends the stream past the length it had when
+//!   [`EncryptedFile::open`] was called. Writing at or past that length
+//!   returns `Ok(0)`, which a `write_all` call turns into a
+//!   [`WriteZero`](std::io::ErrorKind::WriteZero) error -- growing a
+//!   stream means sealing new chunks in order from the old final chunk
+//!   onward, which is exactly the append-only job
+//!   [`Writer`](crate::Writer) already does.
+//! - There's no flush-on-drop: like [`Writer`](crate::Writer), which
+//!   silently drops whatever's buffered if dropped without calling
+//!   [`Writer::finish`](crate::Writer::finish), an [`EncryptedFile`]
+//!   dropped without a final [`flush`](std::io::Write::flush) (or
+//!   [`into_inner`](EncryptedFile::into_inner), which flushes for you)
+//!   silently loses whatever change is sitting in its chunk cache.
+//!
+//! # Security
+//!
+//! **Every chunk this type writes to is nonce-reused the moment it's
+//! written to a second time.** Flushing a dirty chunk reseals it under
+//! exactly the nonce it already had -- the same
+//! `nonce::build(&nonce_prefix, index, last)` [`Writer`](crate::Writer)
+//! used the first time -- the same construction
+//! [`crate::rewrite_chunk`] uses and the same hazard described in its
+//! module's "Safety" section. [`EncryptedFile`] doesn't just risk this
+//! once per chunk the way a single [`crate::rewrite_chunk`] call does:
+//! its whole API is built to be written to repeatedly, at arbitrary
+//! offsets, for as long as it's kept open (a database-style access
+//! pattern is exactly what [`Read`] + [`Write`] + [`Seek`] invites), so
+//! without a guard a chunk touched more than once during the file's
+//! lifetime -- the expected use, not an edge case -- would be sealed
+//! twice under the same `(key, nonce)` pair. That would leak the
+//! keystream for that nonce to anyone who can see two of that chunk's
+//! ciphertexts (an earlier version of the file, a backup, a filesystem
+//! snapshot taken between writes), which in turn lets them forge or
+//! decrypt *other* chunks sealed under the same key.
+//!
+//! To make that the exception rather than the default, every
+//! [`EncryptedFile`] tracks which chunk indices it has already reseal-ed
+//! and refused a second reseal of one with
+//! [`Error::ChunkAlreadyRewritten`](crate::Error::ChunkAlreadyRewritten)
+//! instead of writing it. That only holds for a single open instance,
+//! though, the same as [`crate::nonce`]'s fixed 96-bit prefix-and-counter
+//! layout leaves no spare bits to record a rewrite generation *on
+//! disk*: closing and reopening the same stream (or opening it a second
+//! time from another process) starts a fresh, empty set of tracked
+//! chunks, so it can't stop a chunk from being rewritten again across
+//! separate opens. Don't reopen the same offset range of a stream for
+//! writing more than once unless every ciphertext version before the
+//! final one is guaranteed to be unrecoverable -- which backups and
+//! snapshots exist specifically to defeat.
+
+use std::collections::HashSet;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::buf::TAG_SIZE;
+use crate::chunk_layout::ChunkLayout;
+use crate::header::{peek_header, HEADER_LEN};
+use crate::nonce::{self, PREFIX_LEN};
+use crate::{Error, CHUNK_SIZE};
+
+/// The one chunk of plaintext [`EncryptedFile`] keeps decrypted at a
+/// time.
+struct Cache {
+    index: u64,
+    plaintext: Vec<u8>,
+    dirty: bool,
+}
+
+/// A random-access `Read + Write + Seek` view over an already-written
+/// stream's plaintext. See the module-level doc comment, including its
+/// "Security" section before writing to the same offsets more than once.
+pub struct EncryptedFile<S, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    s: S,
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    layout: ChunkLayout,
+    pos: u64,
+    cache: Option<Cache>,
+    /// Indices of chunks this instance has already resealed. See the
+    /// module-level "Security" section: resealing the same index twice
+    /// reuses its nonce, so [`EncryptedFile::flush_cache`] consults
+    /// this before writing and refuses a second reseal instead of
+    /// silently repeating it.
+    rewritten: HashSet<u64>,
+}
+
+impl<S, A> EncryptedFile<S, A>
+where
+    S: Read + Write + Seek,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens an already-written stream for random access, starting at
+    /// plaintext offset 0.
+    ///
+    /// `key` and `s`'s header must match what sealed it: `s` is read
+    /// from its current position (normally the start of the file) to
+    /// decode the header, then seeked to its end to measure the
+    /// stream's total length. See the module-level doc comment for
+    /// which headers are rejected.
+    pub fn open(mut s: S, key: &Key<A>) -> io::Result<Self> {
+        let info = peek_header(&mut s)?;
+        if info.digest.is_some() || info.padded || info.derived_nonce || info.compressed {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Error::InvalidHeader,
+            ));
+        }
+        let end = s.seek(SeekFrom::End(0))?;
+        let body_len = end
+            .checked_sub(HEADER_LEN as u64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+        let layout = ChunkLayout::compute(body_len)?;
+        Ok(Self {
+            s,
+            aead: A::new(key),
+            nonce_prefix: info.nonce_prefix,
+            layout,
+            pos: 0,
+            cache: None,
+            rewritten: HashSet::new(),
+        })
+    }
+
+    /// This stream's total plaintext length, fixed as of
+    /// [`EncryptedFile::open`]. See the module-level doc comment's
+    /// "Limitations" section: nothing in this type can make this
+    /// number grow.
+    pub fn len(&self) -> u64 {
+        self.layout.total_len
+    }
+
+    /// Whether this stream's plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.layout.total_len == 0
+    }
+
+    /// Flushes the cached chunk, if dirty, and returns the underlying
+    /// `S`. See the module-level doc comment: this, or an explicit
+    /// [`flush`](std::io::Write::flush), is the only thing that
+    /// guarantees the last write made it to `S`.
+    pub fn into_inner(mut self) -> io::Result<S> {
+        self.flush_cache()?;
+        Ok(self.s)
+    }
+
+    /// Reseals the cached chunk and writes it back to `s`, if it's
+    /// dirty. Leaves the chunk in the cache either way.
+    ///
+    /// Refuses with [`Error::ChunkAlreadyRewritten`] if this instance
+    /// has already resealed this index before: doing it again would
+    /// reuse that chunk's nonce. See the module-level "Security"
+    /// section.
+    fn flush_cache(&mut self) -> io::Result<()> {
+        let Some(cache) = &mut self.cache else {
+            return Ok(());
+        };
+        if !cache.dirty {
+            return Ok(());
+        }
+        if !self.rewritten.insert(cache.index) {
+            return Err(io::Error::other(Error::ChunkAlreadyRewritten {
+                chunk: cache.index,
+            }));
+        }
+        let nonce = nonce::build(
+            &self.nonce_prefix,
+            cache.index,
+            cache.index == self.layout.final_chunk_index,
+        );
+        let mut sealed = cache.plaintext.clone();
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, b"", &mut sealed)
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        sealed.extend_from_slice(&tag);
+        self.s
+            .seek(SeekFrom::Start(ChunkLayout::chunk_offset(cache.index)))?;
+        self.s.write_all(&sealed)?;
+        cache.dirty = false;
+        Ok(())
+    }
+
+    /// Makes sure chunk `index` is the one cached, flushing and
+    /// evicting whatever was cached before if it isn't.
+    fn load_chunk(&mut self, index: u64) -> io::Result<()> {
+        if matches!(&self.cache, Some(c) if c.index == index) {
+            return Ok(());
+        }
+        self.flush_cache()?;
+        let plaintext_len = self.layout.chunk_len(index);
+        let last = index == self.layout.final_chunk_index;
+        let offset = ChunkLayout::chunk_offset(index);
+        self.s.seek(SeekFrom::Start(offset))?;
+        let mut sealed = vec![0u8; plaintext_len + TAG_SIZE];
+        self.s.read_exact(&mut sealed)?;
+        let tag: aead::Tag<A> =
+            aead::generic_array::GenericArray::clone_from_slice(&sealed[plaintext_len..]);
+        let nonce = nonce::build(&self.nonce_prefix, index, last);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut sealed[..plaintext_len], &tag)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::AeadAt {
+                        chunk: index,
+                        offset,
+                    },
+                )
+            })?;
+        sealed.truncate(plaintext_len);
+        self.cache = Some(Cache {
+            index,
+            plaintext: sealed,
+            dirty: false,
+        });
+        Ok(())
+    }
+}
+
+impl<S, A> Read for EncryptedFile<S, A>
+where
+    S: Read + Write + Seek,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.layout.total_len {
+            return Ok(0);
+        }
+        let chunk_index = self.pos / CHUNK_SIZE as u64;
+        let offset_in_chunk = (self.pos % CHUNK_SIZE as u64) as usize;
+        self.load_chunk(chunk_index)?;
+        let cache = self.cache.as_ref().expect("just loaded above");
+        let n = buf.len().min(cache.plaintext.len() - offset_in_chunk);
+        buf[..n].copy_from_slice(&cache.plaintext[offset_in_chunk..offset_in_chunk + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<S, A> Write for EncryptedFile<S, A>
+where
+    S: Read + Write + Seek,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.layout.total_len {
+            return Ok(0);
+        }
+        let chunk_index = self.pos / CHUNK_SIZE as u64;
+        let offset_in_chunk = (self.pos % CHUNK_SIZE as u64) as usize;
+        self.load_chunk(chunk_index)?;
+        let cache = self.cache.as_mut().expect("just loaded above");
+        let n = buf.len().min(cache.plaintext.len() - offset_in_chunk);
+        cache.plaintext[offset_in_chunk..offset_in_chunk + n].copy_from_slice(&buf[..n]);
+        cache.dirty = true;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_cache()?;
+        self.s.flush()
+    }
+}
+
+impl<S, A> Seek for EncryptedFile<S, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(n) => (self.layout.total_len, n),
+            SeekFrom::Current(n) => (self.pos, n),
+        };
+        let new_pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        let new_pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.pos = new_pos;
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+use std::io;
+
+/// The error type returned by this crate's fallible operations.
+///
+/// `#[non_exhaustive]`: new variants (e.g. more positional-context
+/// variants alongside [`Error::AeadAt`]) may be added in a minor
+/// release. Match on [`Error::kind`] instead of this enum's variants
+/// directly if you need a `match` that won't need a new arm every time
+/// this type grows.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// Authenticated decryption failed: the ciphertext was tampered
+    /// with, the wrong key was used, or the stream was truncated.
+    Aead,
+    /// The stream's nonce counter would have overflowed, meaning the
+    /// stream is too long to encrypt safely under this construction.
+    NonceOverflow,
+    /// The header was shorter than expected or named an unsupported
+    /// version.
+    InvalidHeader,
+    /// A final chunk's digest footer did not match the digest computed
+    /// over the decrypted plaintext.
+    DigestMismatch,
+    /// A [`Keyring`](crate::Keyring) couldn't open a stream because its
+    /// header didn't carry a key ID, or named one the keyring doesn't
+    /// hold.
+    UnknownKeyId,
+    /// Deflate compression or decompression failed, e.g. because a
+    /// stream's compressed body was truncated or corrupt. See the
+    /// [`compression`](crate::compression) module.
+    Compression,
+    /// A [`Reader`](crate::Reader) opened with
+    /// [`Reader::with_strict_mode`](crate::Reader::with_strict_mode)
+    /// found bytes after the stream's final chunk, e.g. from two
+    /// streams concatenated together.
+    TrailingData,
+    /// A [`Reader`](crate::Reader) opened with
+    /// [`Reader::with_limits`](crate::Reader::with_limits) hit its
+    /// configured chunk-count or plaintext-length bound before reaching
+    /// the stream's final chunk.
+    StreamTooLarge,
+    /// Like [`Error::Aead`], but from [`Reader`](crate::Reader)'s own
+    /// chunk-decryption path, which already tracks the 0-based index of
+    /// the chunk being decrypted (`chunk`) and the approximate byte
+    /// offset into the ciphertext where that chunk's sealed bytes began
+    /// (`offset`). Reporting both lets an operator chasing corruption in
+    /// a terabyte-scale archive seek straight to the offending chunk
+    /// instead of re-decrypting the stream from the start to find it.
+    ///
+    /// The container-format readers in
+    /// [`age`](crate::age)/[`tink`](crate::tink)/[`reference`](crate::reference)/[`aead_stream`](crate::aead_stream)
+    /// still return the plain [`Error::Aead`]: they don't share
+    /// [`Reader`]'s chunk bookkeeping, and threading it through them is
+    /// a larger change than this request covers.
+    AeadAt { chunk: u64, offset: u64 },
+    /// A [`Reader`](crate::Reader) ran out of ciphertext partway through
+    /// chunk `chunk` (0-based, ciphertext beginning at approximately
+    /// byte `offset`): too few bytes remained to even hold that chunk's
+    /// authentication tag, so the stream was truncated or corrupted
+    /// inside this chunk rather than cleanly between chunks.
+    TruncatedChunk { chunk: u64, offset: u64 },
+    /// A [`DatagramOpener`](crate::DatagramOpener) datagram was
+    /// rejected by a [`ReplayWindow`](crate::ReplayWindow): its
+    /// sequence number had already been seen, or fell far enough
+    /// behind the highest one seen to be indistinguishable from a
+    /// replay. See the [`datagram`](crate::datagram) module.
+    Replayed,
+    /// An [`EncryptedFile`](crate::EncryptedFile) refused to reseal
+    /// chunk `chunk` a second time in the same open instance: doing so
+    /// would reuse that chunk's nonce against the copy already written
+    /// the first time, breaking confidentiality and authenticity for
+    /// the whole key. See [`EncryptedFile`](crate::EncryptedFile)'s
+    /// "Security" section.
+    ChunkAlreadyRewritten { chunk: u64 },
+    /// An IO error from the underlying reader/writer or a
+    /// [`KeyProvider`](crate::KeyProvider), carried losslessly instead
+    /// of flattened to [`io::ErrorKind::Other`](std::io::ErrorKind::Other).
+    ///
+    /// [`Writer::with_provider`](crate::Writer::with_provider) and
+    /// [`Reader::with_provider`](crate::Reader::with_provider) wrap a
+    /// `KeyProvider::Error` in this variant, via the crate-private
+    /// `provider_io_error` helper, before converting it to the
+    /// [`io::Error`](std::io::Error) their return type needs;
+    /// converting back through `From<Error> for io::Error` below
+    /// unwraps it again, so a provider backed by `io::Error` itself (a
+    /// network call, say) keeps its original
+    /// [`io::ErrorKind`](std::io::ErrorKind) and OS error code all the
+    /// way out, instead of arriving at the caller as an opaque `Other`.
+    Io(std::io::Error),
+}
+
+/// A coarse, stable classification of an [`Error`], returned by
+/// [`Error::kind`].
+///
+/// `Error` is `#[non_exhaustive]` and gains new variants over time (most
+/// recently [`Error::AeadAt`] and [`Error::TruncatedChunk`] alongside the
+/// [`Error::Aead`]/[`Error::InvalidHeader`] they refine); `ErrorKind`
+/// groups those refinements under the kind they share, so a `match` on
+/// it doesn't need a new arm every time `Error` grows a variant that's a
+/// more detailed version of one it already covers.
+///
+/// `ErrorKind` is itself `#[non_exhaustive]` for the same reason: a
+/// wholly new category of failure (not a refinement of an existing one)
+/// would need a new `ErrorKind` too.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorKind {
+    /// Authenticated decryption failed: wrong key, tampered ciphertext,
+    /// or a stream truncated mid-chunk. Covers [`Error::Aead`] and
+    /// [`Error::AeadAt`].
+    Aead,
+    /// The stream's nonce counter would have overflowed.
+    NonceOverflow,
+    /// The header was malformed, or the stream ended somewhere other
+    /// than inside a chunk's own ciphertext. Covers
+    /// [`Error::InvalidHeader`] and [`Error::TruncatedChunk`].
+    InvalidHeader,
+    /// A final chunk's digest footer didn't match.
+    DigestMismatch,
+    /// No keyring entry for the stream's key ID.
+    UnknownKeyId,
+    /// Deflate compression or decompression failed.
+    Compression,
+    /// Trailing data after the stream's final chunk.
+    TrailingData,
+    /// The stream exceeded a configured chunk-count or length limit.
+    StreamTooLarge,
+    /// An IO error from the underlying reader/writer or a
+    /// [`KeyProvider`](crate::KeyProvider).
+    Io,
+    /// A datagram was rejected by a replay window. Covers
+    /// [`Error::Replayed`].
+    Replayed,
+    /// An [`EncryptedFile`](crate::EncryptedFile) refused to reseal an
+    /// already-rewritten chunk a second time. Covers
+    /// [`Error::ChunkAlreadyRewritten`].
+    ChunkAlreadyRewritten,
+}
+
+impl Error {
+    /// Returns this error's coarse, match-stable [`ErrorKind`].
+    ///
+    /// Prefer matching on this over matching on `Error` directly when
+    /// you only care about the broad category of failure: `Error` is
+    /// `#[non_exhaustive]` and grows variants that refine an existing
+    /// `ErrorKind` (like [`Error::AeadAt`] refining
+    /// [`ErrorKind::Aead`]) without changing which `ErrorKind` they
+    /// report.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Aead | Self::AeadAt { .. } => ErrorKind::Aead,
+            Self::NonceOverflow => ErrorKind::NonceOverflow,
+            Self::InvalidHeader | Self::TruncatedChunk { .. } => ErrorKind::InvalidHeader,
+            Self::DigestMismatch => ErrorKind::DigestMismatch,
+            Self::UnknownKeyId => ErrorKind::UnknownKeyId,
+            Self::Compression => ErrorKind::Compression,
+            Self::TrailingData => ErrorKind::TrailingData,
+            Self::StreamTooLarge => ErrorKind::StreamTooLarge,
+            Self::Replayed => ErrorKind::Replayed,
+            Self::ChunkAlreadyRewritten { .. } => ErrorKind::ChunkAlreadyRewritten,
+            Self::Io(_) => ErrorKind::Io,
+        }
+    }
+
+    /// Whether simply retrying the operation that produced this error,
+    /// with nothing else changed, has a chance of succeeding.
+    ///
+    /// True only for an [`Error::Io`] whose underlying
+    /// [`io::ErrorKind`](std::io::ErrorKind) is itself transient
+    /// (`Interrupted` or `WouldBlock`); every other error reflects a
+    /// deterministic property of the ciphertext, key, or configuration
+    /// that retrying the same call won't change.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Io(e) if matches!(e.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock)
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Aead => write!(f, "authenticated decryption failed"),
+            Self::AeadAt { chunk, offset } => write!(
+                f,
+                "authenticated decryption failed at chunk {chunk} (ciphertext offset {offset})"
+            ),
+            Self::TruncatedChunk { chunk, offset } => write!(
+                f,
+                "stream truncated inside chunk {chunk} (ciphertext offset {offset})"
+            ),
+            Self::NonceOverflow => write!(f, "stream nonce counter overflowed"),
+            Self::InvalidHeader => write!(f, "invalid stream header"),
+            Self::DigestMismatch => write!(f, "digest footer did not match stream contents"),
+            Self::UnknownKeyId => write!(f, "no keyring entry for the stream's key ID"),
+            Self::Compression => write!(f, "deflate compression failed"),
+            Self::TrailingData => write!(f, "trailing data after the stream's final chunk"),
+            Self::StreamTooLarge => {
+                write!(f, "stream exceeded its configured chunk or length limit")
+            }
+            Self::Replayed => write!(f, "datagram rejected by the replay window"),
+            Self::ChunkAlreadyRewritten { chunk } => {
+                write!(f, "refused to reseal chunk {chunk} a second time")
+            }
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Mirrors the [`fmt::Display`] impl above, field for field, so a
+/// `defmt::error!("{}", err)` over RTT reads the same as a `println!`.
+///
+/// `Error::Io`'s `std::io::Error` doesn't implement `defmt::Format`
+/// itself, so it's logged through
+/// [`defmt::Display2Format`](defmt::Display2Format) instead of being
+/// matched on directly like every other variant.
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Aead => defmt::write!(f, "authenticated decryption failed"),
+            Self::AeadAt { chunk, offset } => defmt::write!(
+                f,
+                "authenticated decryption failed at chunk {} (ciphertext offset {})",
+                chunk,
+                offset
+            ),
+            Self::TruncatedChunk { chunk, offset } => defmt::write!(
+                f,
+                "stream truncated inside chunk {} (ciphertext offset {})",
+                chunk,
+                offset
+            ),
+            Self::NonceOverflow => defmt::write!(f, "stream nonce counter overflowed"),
+            Self::InvalidHeader => defmt::write!(f, "invalid stream header"),
+            Self::DigestMismatch => {
+                defmt::write!(f, "digest footer did not match stream contents")
+            }
+            Self::UnknownKeyId => defmt::write!(f, "no keyring entry for the stream's key ID"),
+            Self::Compression => defmt::write!(f, "deflate compression failed"),
+            Self::TrailingData => {
+                defmt::write!(f, "trailing data after the stream's final chunk")
+            }
+            Self::StreamTooLarge => {
+                defmt::write!(f, "stream exceeded its configured chunk or length limit")
+            }
+            Self::Replayed => defmt::write!(f, "datagram rejected by the replay window"),
+            Self::ChunkAlreadyRewritten { chunk } => {
+                defmt::write!(f, "refused to reseal chunk {} a second time", chunk)
+            }
+            Self::Io(e) => defmt::write!(f, "{}", defmt::Display2Format(e)),
+        }
+    }
+}
+
+impl From<aead::Error> for Error {
+    fn from(_: aead::Error) -> Self {
+        Self::Aead
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            // Unwrap rather than rewrap, so round-tripping an IO error
+            // through `Error` doesn't flatten its `ErrorKind` and OS
+            // error code to `Other` the way `io::Error::other` would.
+            Error::Io(e) => e,
+            other => std::io::Error::other(other),
+        }
+    }
+}
+
+/// Converts a [`KeyProvider`](crate::KeyProvider) error into an
+/// [`io::Error`](std::io::Error) for
+/// [`Writer::with_provider`](crate::Writer::with_provider) and
+/// [`Reader::with_provider`](crate::Reader::with_provider) to return,
+/// preserving the original [`io::ErrorKind`](std::io::ErrorKind) and
+/// OS error code when the provider's error type is itself an
+/// `io::Error`, instead of flattening it to
+/// [`io::ErrorKind::Other`](std::io::ErrorKind::Other) the way
+/// `io::Error::other` unconditionally would.
+pub(crate) fn provider_io_error<E>(e: E) -> std::io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let boxed: Box<dyn std::error::Error + Send + Sync + 'static> = Box::new(e);
+    match boxed.downcast::<std::io::Error>() {
+        Ok(io_err) => Error::Io(*io_err).into(),
+        Err(other) => std::io::Error::other(other),
+    }
+}
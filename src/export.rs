@@ -0,0 +1,54 @@
+//! Deriving application subkeys bound to a stream.
+//!
+//! A protocol built on top of this crate often needs more than the one
+//! key it hands to [`Writer::new`](crate::Writer::new): a MAC over
+//! sidecar metadata this crate never sees, say, or a key for encrypting
+//! filenames alongside the contents they belong to.
+//! [`Writer::export_key`](crate::Writer::export_key) and
+//! [`Reader::export_key`](crate::Reader::export_key) hand out such
+//! subkeys, derived via HKDF-SHA256 from the same `(key, nonce_prefix)`
+//! pair the stream is sealed under, so callers don't need to run their
+//! own KDF extract step against the shared key just to get a second,
+//! independent one.
+//!
+//! `context` is a fixed, protocol-specific label the caller chooses to
+//! tell one export apart from another: two calls with the same
+//! `context` against the same stream always return the same subkey, and
+//! different `context`s never collide, the same separation
+//! [`derive_session_key`](crate::derive_session_key)'s `info` parameter
+//! provides for Noise/TLS-exported secrets.
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::nonce::PREFIX_LEN;
+
+/// The length, in bytes, of a subkey returned by
+/// [`Writer::export_key`](crate::Writer::export_key)/[`Reader::export_key`](crate::Reader::export_key).
+pub const EXPORT_KEY_LEN: usize = 32;
+
+/// Runs the HKDF extract and expand steps behind
+/// [`Writer::export_key`](crate::Writer::export_key) and
+/// [`Reader::export_key`](crate::Reader::export_key).
+pub(crate) struct KeyExporter {
+    hk: Hkdf<Sha256>,
+}
+
+impl KeyExporter {
+    /// Runs the HKDF extract step over `key`, salted with
+    /// `nonce_prefix`.
+    pub(crate) fn new(key: &[u8], nonce_prefix: &[u8; PREFIX_LEN]) -> Self {
+        Self {
+            hk: Hkdf::<Sha256>::new(Some(nonce_prefix), key),
+        }
+    }
+
+    /// Expands the subkey for `context`.
+    pub(crate) fn export(&self, context: &[u8]) -> [u8; EXPORT_KEY_LEN] {
+        let mut out = [0u8; EXPORT_KEY_LEN];
+        self.hk
+            .expand(context, &mut out)
+            .expect("32 bytes is well within HKDF-SHA256's output size limit");
+        out
+    }
+}
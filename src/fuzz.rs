@@ -0,0 +1,60 @@
+//! `Arbitrary` implementations for structure-aware fuzzing, gated
+//! behind the `fuzz` feature so `arbitrary` doesn't become part of the
+//! crate's default dependency surface.
+//!
+//! Feeding raw bytes straight to [`Reader::new`](crate::Reader::new)
+//! wastes most of a fuzzer's budget on inputs that are rejected at the
+//! header before any interesting decoding logic runs. [`StreamDescription`]
+//! lets `cargo-fuzz` derive a well-formed ciphertext from raw fuzzer
+//! input instead, via [`StreamDescription::to_ciphertext`], so mutation
+//! pressure lands on chunk boundaries, padding, and comment handling
+//! rather than the header's magic bytes and length checks.
+//!
+//! [`Version`] and [`ReaderOpts`](crate::ReaderOpts) implement
+//! [`Arbitrary`] directly (see their definitions), so a target that
+//! wants to fuzz [`Reader::with_opts`](crate::Reader::with_opts)'s
+//! allowlist logic can derive those independently of
+//! [`StreamDescription`].
+
+use std::io::Write;
+
+use arbitrary::Arbitrary;
+use chacha20poly1305::ChaCha20Poly1305;
+
+use crate::Writer;
+
+/// A synthetic stream, cheap for `cargo-fuzz` to derive from raw input
+/// and turn into a valid ciphertext via [`StreamDescription::to_ciphertext`].
+///
+/// Always sealed with [`ChaCha20Poly1305`], the crate's default AEAD in
+/// its own examples and tests -- a fuzz target that cares about a
+/// different algorithm can build one by hand instead.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct StreamDescription {
+    /// The AEAD key, as raw bytes.
+    pub key: [u8; 32],
+    /// The stream's nonce prefix. Uniqueness isn't required here the
+    /// way it is in real use: fuzzing decryption doesn't depend on it.
+    pub nonce_prefix: [u8; 4],
+    /// The plaintext to seal.
+    pub plaintext: Vec<u8>,
+    /// Whether to pad the final chunk via [`Writer::with_padding`].
+    pub padded: bool,
+}
+
+impl StreamDescription {
+    /// Seals `self.plaintext` into a well-formed ciphertext, ready to
+    /// feed to [`Reader::new`](crate::Reader::new) or mutate further.
+    pub fn to_ciphertext(&self) -> Vec<u8> {
+        let key = self.key.into();
+        let mut w = if self.padded {
+            Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, self.nonce_prefix)
+        } else {
+            Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, self.nonce_prefix)
+        }
+        .expect("sealing to an in-memory Vec<u8> never fails");
+        w.write_all(&self.plaintext)
+            .expect("writing to an in-memory Vec<u8> never fails");
+        w.finish().expect("finishing an in-memory Vec<u8> never fails")
+    }
+}
@@ -0,0 +1,546 @@
+use std::io::{self, Read};
+
+use crate::digest::DigestAlgorithm;
+use crate::key_check::KEY_CHECK_LEN;
+use crate::keyring::{KeyId, KEY_ID_LEN};
+use crate::nonce::PREFIX_LEN;
+use crate::Error;
+
+/// The on-disk length of a [`Header`].
+pub const HEADER_LEN: usize = 3 + KEY_ID_LEN + KEY_CHECK_LEN + PREFIX_LEN;
+
+/// The length, in bytes, of [`MAGIC`].
+pub const MAGIC_LEN: usize = 4;
+
+/// A constant written immediately before the header of a
+/// [`Version::V3`] stream, letting [`sniff`] recognize this crate's
+/// format without a key -- [`Version::V1`] and [`Version::V2`] streams
+/// have no such prefix and so aren't distinguishable from arbitrary
+/// binary data this way. See [`Writer::with_magic`](crate::Writer::with_magic).
+pub(crate) const MAGIC: [u8; MAGIC_LEN] = *b"STRM";
+
+/// The length, in bytes, of a [`stream_id`].
+pub const STREAM_ID_LEN: usize = 32;
+
+/// Derives a stable, non-secret identifier for a stream from its
+/// `nonce_prefix` -- the one field of [`Header`] that's both unique per
+/// stream and present on every [`Writer`](crate::Writer) and
+/// [`Reader`](crate::Reader), whether or not the stream carries a key
+/// ID or digest.
+///
+/// Hashing the prefix instead of handing it back directly gives
+/// callers a fixed-width value they can use as a cache key or dedup
+/// token without also handing out the literal bytes the AEAD nonce is
+/// built from, and domain-separates it from anything else in this
+/// crate that might someday hash a raw nonce prefix for an unrelated
+/// purpose.
+pub(crate) fn stream_id(nonce_prefix: &[u8; PREFIX_LEN]) -> [u8; STREAM_ID_LEN] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"stream-rs stream id v1");
+    hasher.update(nonce_prefix);
+    *hasher.finalize().as_bytes()
+}
+
+/// Flag bit in the header's flags byte marking a stream as padded. See
+/// the [`padding`](crate::padding) module.
+const FLAG_PADDED: u8 = 1 << 0;
+
+/// Flag bit in the header's flags byte marking a stream as carrying a
+/// key ID. See the [`keyring`](crate::keyring) module.
+const FLAG_KEY_ID: u8 = 1 << 1;
+
+/// Flag bit in the header's flags byte marking a stream as using
+/// HKDF-derived per-chunk nonces instead of the default
+/// prefix-and-counter construction. See the [`derive`](crate::derive)
+/// module.
+const FLAG_DERIVED_NONCE: u8 = 1 << 2;
+
+/// Flag bit in the header's flags byte marking a stream's plaintext as
+/// Deflate-compressed before chunking. See the
+/// [`compression`](crate::compression) module.
+const FLAG_COMPRESSED: u8 = 1 << 3;
+
+/// Flag bit in the header's flags byte marking a stream as carrying a
+/// key-check value. See the [`key_check`](crate::key_check) module.
+const FLAG_KEY_CHECK: u8 = 1 << 4;
+
+/// The wire format version of a stream.
+///
+/// This is the first byte of every stream and lets a future version of
+/// this crate (or another implementation) reject streams it doesn't
+/// know how to decode.
+///
+/// The framing itself hasn't changed since `V1`:
+/// [`Reader::advance`](crate::Reader) marks a chunk as final by peeking
+/// one extra byte past it in [`Reader::top_up`](crate::Reader) -- if
+/// nothing more is on the wire, the chunk just decrypted was the last
+/// one, and that fact is baked into the chunk's own nonce (see the
+/// [`nonce`](crate::nonce) module) rather than requiring a second
+/// decryption attempt with a different nonce to find out. A proposal to
+/// add a version that encodes finality more "explicitly" to retire a
+/// retry-decrypt fallback was evaluated and doesn't apply here: this
+/// crate never implemented such a fallback, and the lookahead framing
+/// already gives every version an unambiguous, single-decrypt-per-chunk
+/// final-chunk condition, including for the empty-stream case (an empty
+/// stream is just a single final chunk with no plaintext).
+///
+/// [`V2`](Self::V2) exists for an unrelated reason: binding the chunk
+/// nonce derivation to parameters `V1` left unbound. See its own doc
+/// comment. So does [`V3`](Self::V3): prefixing the header with
+/// [`MAGIC`] so the format can be recognized without a key. So does
+/// [`V4`](Self::V4): a TLV extension area for header fields this crate
+/// doesn't know about yet. So does [`V5`](Self::V5): an encrypted
+/// metadata block for fields that shouldn't be cleartext in the first
+/// place. So does [`V6`](Self::V6): a cleartext comment authenticated
+/// as every chunk's associated data, rather than folded into the
+/// key-check value the way `V4`'s extension area is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum Version {
+    /// The original STREAM framing implemented by this crate.
+    V1,
+    /// Like `V1`, but [`Header::derived_nonce`] streams bind the wire
+    /// version, [`CHUNK_SIZE`](crate::CHUNK_SIZE), and the AEAD
+    /// algorithm into every chunk's HKDF context, not just the counter
+    /// and final-chunk flag -- closing a class of cross-parameter
+    /// confusion `V1`'s [`NonceDeriver`](crate::derive::NonceDeriver)
+    /// left open, where the same key and `nonce_prefix` reused across
+    /// two builds with different parameters (a different `CHUNK_SIZE`
+    /// feature flag, say) could derive colliding nonces. See
+    /// [`Writer::with_bound_nonces`](crate::Writer::with_bound_nonces).
+    V2,
+    /// Like `V1`, but the header is preceded on the wire by [`MAGIC`],
+    /// a fixed constant [`sniff`] looks for to recognize this crate's
+    /// format before a key is available to actually decrypt anything.
+    /// See [`Writer::with_magic`](crate::Writer::with_magic).
+    V3,
+    /// Like `V1`, but followed on the wire by a TLV area of
+    /// [`Extension`]s -- caller-defined `(tag, value)` pairs the fixed
+    /// [`Header`] fields have no room for. The area is "authenticated"
+    /// the same way every other header field is: folded into the
+    /// stream's key-check value, so tampering with an extension is
+    /// caught the same way a wrong key is, before any chunk is
+    /// decrypted. See [`Writer::with_extensions`](crate::Writer::with_extensions).
+    V4,
+    /// Like `V1`, but followed on the wire by an encrypted-and-authenticated
+    /// metadata block -- a caller's original filename, modification time,
+    /// and content type. Unlike `V4`'s extension area, this block is
+    /// never cleartext: it's sealed under its own one-time key, derived
+    /// from the stream's key and `nonce_prefix`, so a [`Reader`](crate::Reader)
+    /// without the key learns nothing about it, not even its presence
+    /// beyond the fact that the header names this version. See
+    /// [`Writer::with_metadata`](crate::Writer::with_metadata).
+    V5,
+    /// Like `V1`, but followed on the wire by a cleartext comment --
+    /// a short operator-supplied label such as `"2024 Q1 payroll
+    /// backup"` -- and with every chunk's AEAD call given the comment's
+    /// bytes as associated data, so tampering with it is caught the
+    /// instant the first chunk is decrypted, the same way tampering
+    /// with the ciphertext itself would be. Unlike `V4`'s extension
+    /// area, a `V6` comment doesn't rely on a key-check value to catch
+    /// tampering, so it still works on a stream with none. See
+    /// [`Writer::with_comment`](crate::Writer::with_comment).
+    V6,
+}
+
+impl Version {
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+            Self::V3 => 3,
+            Self::V4 => 4,
+            Self::V5 => 5,
+            Self::V6 => 6,
+        }
+    }
+
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            1 => Some(Self::V1),
+            2 => Some(Self::V2),
+            3 => Some(Self::V3),
+            4 => Some(Self::V4),
+            5 => Some(Self::V5),
+            6 => Some(Self::V6),
+            _ => None,
+        }
+    }
+
+    /// Every version this crate knows how to read, oldest first. Used by
+    /// [`ReaderOpts`](crate::ReaderOpts)'s default, which accepts
+    /// whatever [`Reader::new`](crate::Reader::new) would.
+    pub(crate) fn all() -> [Self; 6] {
+        [Self::V1, Self::V2, Self::V3, Self::V4, Self::V5, Self::V6]
+    }
+
+    /// The newest version this crate can write, i.e. the one
+    /// [`Writer::with_comment`](crate::Writer::with_comment) currently
+    /// produces. A deployment pinning its configuration to "whatever
+    /// this crate considers current" rather than a hardcoded variant can
+    /// use this instead, at the cost of that configuration's meaning
+    /// shifting on every upgrade that adds a version.
+    pub fn latest() -> Self {
+        Self::V6
+    }
+}
+
+/// Returned by [`Version`]'s [`FromStr`] impl when the string isn't one
+/// of `"v1"` through `"v6"` (case-insensitively).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionError(String);
+
+impl std::fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a valid stream version", self.0)
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+impl std::fmt::Display for Version {
+    /// Renders as `"v1"` through `"v6"`, the same strings
+    /// [`Version`]'s [`FromStr`] impl parses back.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.to_byte())
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = ParseVersionError;
+
+    /// Parses `"v1"` through `"v6"`, matched case-insensitively so
+    /// `"V1"` and `"v1"` both work in operator-facing configuration
+    /// (an environment variable or CLI flag, say).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let byte = s
+            .strip_prefix(['v', 'V'])
+            .and_then(|n| n.parse::<u8>().ok());
+        byte.and_then(Self::from_byte)
+            .ok_or_else(|| ParseVersionError(s.to_string()))
+    }
+}
+
+/// A single `(tag, value)` pair in a [`Version::V4`] stream's extension
+/// area.
+///
+/// `tag` namespaces the entry so unrelated callers don't collide over
+/// the same header; this crate doesn't reserve or interpret any tag
+/// values itself, it only carries them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Extension {
+    /// Identifies what `value` holds; caller-defined.
+    pub tag: u8,
+    /// The extension's payload. Limited to [`u16::MAX`] bytes, like the
+    /// extension area as a whole -- see [`encode_extensions`].
+    pub value: Vec<u8>,
+}
+
+/// The length, in bytes, of an [`Extension`]'s `tag` and length prefix,
+/// not counting its `value`.
+const EXTENSION_HEADER_LEN: usize = 1 + 2;
+
+/// Encodes `extensions` as the TLV area a [`Version::V4`] stream carries
+/// after its fixed-size [`Header`]: each entry is `tag` (1 byte), then
+/// `value`'s length (2 bytes, big-endian), then `value` itself.
+///
+/// Fails with [`Error::InvalidHeader`] if any single `value` or the
+/// encoded area as a whole would exceed [`u16::MAX`] bytes -- the same
+/// error a malformed or truncated header on the read side reports, since
+/// this is really the same failure mode (a header this crate can't
+/// represent on the wire) seen from the write side.
+pub(crate) fn encode_extensions(extensions: &[Extension]) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    for ext in extensions {
+        let len = u16::try_from(ext.value.len()).map_err(|_| Error::InvalidHeader)?;
+        buf.push(ext.tag);
+        buf.extend_from_slice(&len.to_be_bytes());
+        buf.extend_from_slice(&ext.value);
+    }
+    u16::try_from(buf.len()).map_err(|_| Error::InvalidHeader)?;
+    Ok(buf)
+}
+
+/// Decodes the TLV area [`encode_extensions`] produces, back into the
+/// list of [`Extension`]s it was built from. Also used by the
+/// [`metadata`](crate::metadata) module to decode a [`Version::V5`]
+/// stream's metadata block, once decrypted: it's encoded with the same
+/// TLV scheme as a [`Version::V4`] extension area, just sealed first.
+pub(crate) fn decode_extensions(buf: &[u8]) -> Result<Vec<Extension>, Error> {
+    let mut extensions = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let header = buf
+            .get(pos..pos + EXTENSION_HEADER_LEN)
+            .ok_or(Error::InvalidHeader)?;
+        let tag = header[0];
+        let len = usize::from(u16::from_be_bytes([header[1], header[2]]));
+        pos += EXTENSION_HEADER_LEN;
+        let value = buf
+            .get(pos..pos + len)
+            .ok_or(Error::InvalidHeader)?
+            .to_vec();
+        pos += len;
+        extensions.push(Extension { tag, value });
+    }
+    Ok(extensions)
+}
+
+/// The fixed-size header written at the start of every stream.
+///
+/// `extensions` isn't part of the fixed-size wire encoding
+/// [`Header::encode`]/[`Header::decode`] handle -- it's only populated
+/// for a [`Version::V4`] stream, whose TLV extension area [`read_header`]
+/// reads and decodes separately, after the fixed header proper. See
+/// [`Writer::with_extensions`](crate::Writer::with_extensions).
+///
+/// `sealed_metadata` is the same kind of out-of-band field, for
+/// [`Version::V5`]: [`read_header`] reads its raw, still-encrypted bytes
+/// without needing a key, leaving [`Reader::from_header_with_cbuf`](crate::Reader)
+/// to decrypt them once a key is available. Empty for every other
+/// version.
+///
+/// `comment` is the same kind of out-of-band field, for [`Version::V6`]:
+/// cleartext bytes [`read_header`] reads straight off the wire, with no
+/// decryption step at all -- only authenticated later, implicitly, by
+/// every chunk's AEAD call taking it as associated data. Empty for every
+/// other version.
+pub(crate) struct Header {
+    pub(crate) version: Version,
+    pub(crate) digest: Option<DigestAlgorithm>,
+    pub(crate) padded: bool,
+    pub(crate) key_id: Option<KeyId>,
+    pub(crate) derived_nonce: bool,
+    pub(crate) compressed: bool,
+    pub(crate) key_check: Option<[u8; KEY_CHECK_LEN]>,
+    pub(crate) nonce_prefix: [u8; PREFIX_LEN],
+    pub(crate) extensions: Vec<Extension>,
+    pub(crate) sealed_metadata: Vec<u8>,
+    pub(crate) comment: Vec<u8>,
+}
+
+impl Header {
+    pub(crate) fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut buf = [0u8; HEADER_LEN];
+        buf[0] = self.version.to_byte();
+        buf[1] = self.digest.map_or(0, DigestAlgorithm::to_byte);
+        let mut flags = if self.padded { FLAG_PADDED } else { 0 };
+        flags |= if self.key_id.is_some() {
+            FLAG_KEY_ID
+        } else {
+            0
+        };
+        flags |= if self.derived_nonce {
+            FLAG_DERIVED_NONCE
+        } else {
+            0
+        };
+        flags |= if self.compressed { FLAG_COMPRESSED } else { 0 };
+        flags |= if self.key_check.is_some() {
+            FLAG_KEY_CHECK
+        } else {
+            0
+        };
+        buf[2] = flags;
+        buf[3..3 + KEY_ID_LEN].copy_from_slice(&self.key_id.unwrap_or_default());
+        buf[3 + KEY_ID_LEN..3 + KEY_ID_LEN + KEY_CHECK_LEN]
+            .copy_from_slice(&self.key_check.unwrap_or_default());
+        buf[3 + KEY_ID_LEN + KEY_CHECK_LEN..].copy_from_slice(&self.nonce_prefix);
+        buf
+    }
+
+    pub(crate) fn decode(buf: &[u8; HEADER_LEN]) -> Result<Self, Error> {
+        let version = Version::from_byte(buf[0]).ok_or(Error::InvalidHeader)?;
+        let digest = DigestAlgorithm::from_byte(buf[1]).ok_or(Error::InvalidHeader)?;
+        let flags = buf[2];
+        let padded = flags & FLAG_PADDED != 0;
+        let key_id = if flags & FLAG_KEY_ID != 0 {
+            let mut id = KeyId::default();
+            id.copy_from_slice(&buf[3..3 + KEY_ID_LEN]);
+            Some(id)
+        } else {
+            None
+        };
+        let derived_nonce = flags & FLAG_DERIVED_NONCE != 0;
+        let compressed = flags & FLAG_COMPRESSED != 0;
+        let key_check = if flags & FLAG_KEY_CHECK != 0 {
+            let mut check = [0u8; KEY_CHECK_LEN];
+            check.copy_from_slice(&buf[3 + KEY_ID_LEN..3 + KEY_ID_LEN + KEY_CHECK_LEN]);
+            Some(check)
+        } else {
+            None
+        };
+        let mut nonce_prefix = [0u8; PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&buf[3 + KEY_ID_LEN + KEY_CHECK_LEN..]);
+        Ok(Self {
+            version,
+            digest,
+            padded,
+            key_id,
+            derived_nonce,
+            compressed,
+            key_check,
+            nonce_prefix,
+            extensions: Vec::new(),
+            sealed_metadata: Vec::new(),
+            comment: Vec::new(),
+        })
+    }
+}
+
+/// A stream's header fields, decoded without needing its key: every
+/// field here is cleartext on the wire, unlike the chunk plaintext the
+/// key protects.
+///
+/// Returned by [`peek_header`], mainly for tooling that inspects a
+/// stream's framing without possessing the key to decrypt it (the
+/// `stream inspect` CLI command, say).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HeaderInfo {
+    /// The stream's wire format version.
+    pub version: Version,
+    /// The digest algorithm authenticating the whole plaintext, if
+    /// any. See the [`digest`](crate::digest) module.
+    pub digest: Option<DigestAlgorithm>,
+    /// Whether the final chunk's plaintext is
+    /// [Padmé](crate::padding)-padded.
+    pub padded: bool,
+    /// The key ID naming which key sealed the stream, if any. See the
+    /// [`keyring`](crate::keyring) module.
+    pub key_id: Option<KeyId>,
+    /// Whether chunk nonces are HKDF-derived rather than built from
+    /// the prefix and counter directly. See the
+    /// [`derive`](crate::derive) module.
+    pub derived_nonce: bool,
+    /// Whether the plaintext was Deflate-compressed before chunking.
+    /// See the [`compression`](crate::compression) module.
+    pub compressed: bool,
+    /// Whether the header carries a key-check value a
+    /// [`Reader`](crate::Reader) can verify before decrypting any
+    /// chunk. See the [`key_check`](crate::key_check) module.
+    pub key_checked: bool,
+    /// The stream's random per-stream nonce prefix.
+    pub nonce_prefix: [u8; PREFIX_LEN],
+    /// The stream's [`Version::V4`] extension area, empty for every
+    /// earlier version. See
+    /// [`Writer::with_extensions`](crate::Writer::with_extensions).
+    pub extensions: Vec<Extension>,
+    /// Whether the header carries a [`Version::V5`] encrypted metadata
+    /// block. Unlike [`HeaderInfo::extensions`], the block's contents
+    /// aren't exposed here: they're sealed under a key
+    /// [`peek_header`]'s caller doesn't necessarily have. See
+    /// [`Reader::metadata`](crate::Reader::metadata).
+    pub metadata_sealed: bool,
+    /// The stream's [`Version::V6`] comment, empty for every earlier
+    /// version. Already readable here, without a key: unlike
+    /// [`HeaderInfo::metadata_sealed`], a `V6` comment is cleartext on
+    /// the wire, only authenticated (not hidden) by the stream's key.
+    /// See [`Writer::with_comment`](crate::Writer::with_comment).
+    pub comment: Vec<u8>,
+}
+
+impl HeaderInfo {
+    /// A stable, non-secret identifier for this stream, derived from
+    /// its `nonce_prefix`. Useful for logging, caching, or deduping
+    /// encrypted objects by the stream that produced them, without
+    /// needing the key to decrypt anything. See [`stream_id`]'s doc
+    /// comment for why it's a hash of the prefix rather than the
+    /// prefix itself.
+    pub fn stream_id(&self) -> [u8; STREAM_ID_LEN] {
+        stream_id(&self.nonce_prefix)
+    }
+}
+
+impl From<Header> for HeaderInfo {
+    fn from(h: Header) -> Self {
+        Self {
+            version: h.version,
+            digest: h.digest,
+            padded: h.padded,
+            key_id: h.key_id,
+            derived_nonce: h.derived_nonce,
+            compressed: h.compressed,
+            key_checked: h.key_check.is_some(),
+            nonce_prefix: h.nonce_prefix,
+            extensions: h.extensions,
+            metadata_sealed: !h.sealed_metadata.is_empty(),
+            comment: h.comment,
+        }
+    }
+}
+
+/// Reads and decodes a [`Header`] from `r`, transparently skipping past
+/// [`MAGIC`] if the stream carries it ([`Version::V3`]).
+///
+/// `r` can't simply be `read_exact`d for [`HEADER_LEN`] bytes and
+/// decoded: a [`Version::V3`] stream has [`MAGIC_LEN`] extra bytes in
+/// front of the header proper, and there's no way to tell whether
+/// they're present without reading them first. This reads the
+/// [`MAGIC_LEN`]-byte prefix every version's header has in common,
+/// either way, and then reads whatever's left to fill out a full
+/// header buffer: past the magic if it matched, or the rest of a
+/// magic-less header's fields if it didn't.
+pub(crate) fn read_header(r: &mut impl Read) -> io::Result<Header> {
+    let mut buf = [0u8; HEADER_LEN];
+    r.read_exact(&mut buf[..MAGIC_LEN])?;
+    if buf[..MAGIC_LEN] == MAGIC {
+        r.read_exact(&mut buf)?;
+    } else {
+        r.read_exact(&mut buf[MAGIC_LEN..])?;
+    }
+    let mut header =
+        Header::decode(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if header.version == Version::V4 {
+        let mut ext_len = [0u8; 2];
+        r.read_exact(&mut ext_len)?;
+        let mut ext_buf = vec![0u8; usize::from(u16::from_be_bytes(ext_len))];
+        r.read_exact(&mut ext_buf)?;
+        header.extensions = decode_extensions(&ext_buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+    if header.version == Version::V5 {
+        let mut meta_len = [0u8; 2];
+        r.read_exact(&mut meta_len)?;
+        let mut meta_buf = vec![0u8; usize::from(u16::from_be_bytes(meta_len))];
+        r.read_exact(&mut meta_buf)?;
+        header.sealed_metadata = meta_buf;
+    }
+    if header.version == Version::V6 {
+        let mut comment_len = [0u8; 2];
+        r.read_exact(&mut comment_len)?;
+        let mut comment_buf = vec![0u8; usize::from(u16::from_be_bytes(comment_len))];
+        r.read_exact(&mut comment_buf)?;
+        header.comment = comment_buf;
+    }
+    Ok(header)
+}
+
+/// Reads and decodes a stream's header from `r`, without needing its
+/// key: every header field is cleartext on the wire. Useful for
+/// debugging interop problems or otherwise inspecting a stream's
+/// framing before deciding how (or whether) to decrypt it.
+pub fn peek_header<R: Read>(mut r: R) -> io::Result<HeaderInfo> {
+    read_header(&mut r).map(HeaderInfo::from)
+}
+
+/// Recognizes this crate's wire format from its leading bytes, without
+/// needing a key -- only [`Version::V3`] streams can be recognized this
+/// way, since only they carry [`MAGIC`]; a [`Version::V1`] or
+/// [`Version::V2`] stream is indistinguishable from arbitrary binary
+/// data by this function (though [`peek_header`] can still open and
+/// decode one directly, once something else -- a file extension or
+/// content-type header, say -- has already identified it as this
+/// format).
+///
+/// Returns `None` if `buf` doesn't begin with [`MAGIC`], or is too
+/// short to tell.
+pub fn sniff(buf: &[u8]) -> Option<Version> {
+    if buf.len() < MAGIC_LEN + 1 || buf[..MAGIC_LEN] != MAGIC {
+        return None;
+    }
+    Version::from_byte(buf[MAGIC_LEN])
+}
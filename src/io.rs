@@ -0,0 +1,341 @@
+//! Small [`Read`]/[`Write`] wrappers for tracking byte counts and
+//! duplicating a stream to a second sink -- the bookkeeping an
+//! application built on [`Reader`](crate::Reader)/[`Writer`](crate::Writer)
+//! constantly needs (how much ciphertext did that request actually
+//! transfer? what's its digest?) but that this crate's own IO types
+//! have no reason to build in themselves.
+
+use std::io::{self, Read, Write};
+
+use zeroize::Zeroize;
+
+/// Wraps a [`Read`]er, counting every byte read through it.
+pub struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    /// Wraps `inner`, starting the count at zero.
+    pub fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// The number of bytes read through this reader so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Wraps a [`Write`]r, counting every byte written through it.
+pub struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    /// Wraps `inner`, starting the count at zero.
+    pub fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// The number of bytes written through this writer so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Unwraps this writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps a [`Read`]er, copying every byte read through it into a second
+/// [`Write`]r as it goes -- e.g. hashing ciphertext while it streams
+/// through [`Reader`](crate::Reader) instead of buffering it twice to
+/// hash it separately afterward.
+pub struct TeeReader<R, W> {
+    inner: R,
+    sink: W,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    /// Wraps `inner`, copying every byte subsequently read from it into
+    /// `sink`.
+    pub fn new(inner: R, sink: W) -> Self {
+        Self { inner, sink }
+    }
+
+    /// Unwraps this reader, returning the underlying reader and sink.
+    pub fn into_inner(self) -> (R, W) {
+        (self.inner, self.sink)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sink.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Wraps a [`Read`]er in a fixed-size, stack-allocated buffer of `N`
+/// bytes, so a source that only ever hands back a byte or two at a
+/// time -- a UART, an I2C peripheral, anything without its own
+/// buffering -- doesn't force one upstream refill call per byte read
+/// through it.
+///
+/// This isn't a `#![no_std]` type: it's generic over `N` via a const
+/// generic specifically so the buffer is `[u8; N]` rather than a `Vec`,
+/// but [`Read`]/[`Write`] and this crate as a whole have no
+/// `#![no_std]` attribute -- [`Reader`](crate::Reader) and
+/// [`Writer`](crate::Writer) are built on `std::io` throughout -- so it
+/// still depends on `std`. What it does buy a byte-at-a-time source is
+/// real: without it, [`Reader`](crate::Reader)'s ciphertext refill loop
+/// calls back into this crate's caller once per byte the source hands
+/// back; with it, a single refill call drains the source until the
+/// internal buffer is full (or the source is exhausted), so the loop
+/// above only sees one call per `N` bytes instead of one per byte.
+pub struct FixedBufReader<R, const N: usize> {
+    inner: R,
+    buf: [u8; N],
+    pos: usize,
+    filled: usize,
+}
+
+impl<R: Read, const N: usize> FixedBufReader<R, N> {
+    /// Wraps `inner` in an empty `N`-byte buffer.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: [0u8; N],
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    /// Unwraps this reader, returning the underlying reader. Any bytes
+    /// already read from `inner` into the internal buffer but not yet
+    /// consumed by a caller are discarded.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, const N: usize> Read for FixedBufReader<R, N> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.filled {
+            self.pos = 0;
+            self.filled = 0;
+            // Drain the source until the buffer is full or it's out of
+            // bytes to give, so a source that only ever returns a byte
+            // or two per call still fills the whole buffer in one call
+            // to this reader.
+            while self.filled < N {
+                let n = self.inner.read(&mut self.buf[self.filled..])?;
+                if n == 0 {
+                    break;
+                }
+                self.filled += n;
+            }
+            if self.filled == 0 {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.filled - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Wraps a [`Write`]r in a fixed-size, stack-allocated buffer of `N`
+/// bytes, coalescing small writes into one call to the underlying
+/// writer every `N` bytes instead of one call per write. See
+/// [`FixedBufReader`] for why this isn't a `#![no_std]` type despite
+/// the fixed-size buffer.
+pub struct FixedBufWriter<W, const N: usize> {
+    inner: W,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<W: Write, const N: usize> FixedBufWriter<W, N> {
+    /// Wraps `inner` in an empty `N`-byte buffer.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buf: [0u8; N],
+            len: 0,
+        }
+    }
+
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if self.len > 0 {
+            self.inner.write_all(&self.buf[..self.len])?;
+            self.len = 0;
+        }
+        Ok(())
+    }
+
+    /// Flushes any buffered bytes and unwraps this writer, returning
+    /// the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush_buf()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write, const N: usize> Write for FixedBufWriter<W, N> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len == 0 && buf.len() >= N {
+            return self.inner.write(buf);
+        }
+        let n = buf.len().min(N - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        if self.len == N {
+            self.flush_buf()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.inner.flush()
+    }
+}
+
+/// A fixed-capacity, zeroizing byte buffer of `N` bytes, implementing
+/// both [`Read`] and [`Write`].
+///
+/// This is a cleaned-up, public cousin of the buffer
+/// [`Reader`](crate::Reader) and [`Writer`](crate::Writer) already use
+/// internally to accumulate one chunk's plaintext before sealing it:
+/// enough custom adapters built around them have needed exactly this
+/// (a bounded scratch buffer that doesn't leave plaintext behind in
+/// memory once it's done with it) that it's worth exposing directly,
+/// rather than everyone reimplementing it. The crate's own internal
+/// buffer, `crate::buf::Buf`, stays private -- it's wired to
+/// [`CHUNK_SIZE`](crate::CHUNK_SIZE) and carries chunk-sealing-specific
+/// bookkeeping ([`Writer`](crate::Writer)'s digest footer) that has no
+/// business on a general-purpose type.
+///
+/// [`Write::write`] fills from wherever the last write left off and
+/// refuses anything past `N` (returning `Ok(0)`, the same way
+/// [`std::io::Cursor`] does when its backing slice is full);
+/// [`Read::read`] drains what's been written so far, in order. The
+/// backing storage is zeroized on [`FixedBuf::clear`] and on drop, so
+/// nothing written into it lingers in memory past its last use.
+pub struct FixedBuf<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+    pos: usize,
+}
+
+impl<const N: usize> FixedBuf<N> {
+    /// An empty buffer.
+    pub fn new() -> Self {
+        Self {
+            bytes: [0u8; N],
+            len: 0,
+            pos: 0,
+        }
+    }
+
+    /// This buffer's capacity, `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of bytes written into this buffer so far, including
+    /// any already consumed via [`Read`].
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this buffer has ever had anything written into it.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether this buffer has reached its capacity and can't accept
+    /// any more bytes via [`Write`].
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// The bytes written into this buffer so far, in order, including
+    /// any already consumed via [`Read`].
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Zeroizes the written bytes and resets this buffer to empty, as
+    /// if newly constructed.
+    pub fn clear(&mut self) {
+        self.bytes[..self.len].zeroize();
+        self.len = 0;
+        self.pos = 0;
+    }
+}
+
+impl<const N: usize> Default for FixedBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for FixedBuf<N> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(N - self.len);
+        self.bytes[self.len..self.len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<const N: usize> Read for FixedBuf<N> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.bytes[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<const N: usize> Drop for FixedBuf<N> {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
@@ -0,0 +1,121 @@
+//! Fail-fast wrong-key detection.
+//!
+//! Without this module, opening a stream under the wrong key fails the
+//! same way a tampered or truncated one does: [`Reader`](crate::Reader)
+//! has to decrypt the first chunk before AEAD authentication tells it
+//! anything is wrong, which for a multi-gigabyte object fetched over
+//! the network means paying to stream a whole [`CHUNK_SIZE`](crate::CHUNK_SIZE)
+//! worth of ciphertext just to learn the key was wrong all along.
+//!
+//! [`Writer::with_key_check`](crate::Writer::with_key_check) derives a
+//! [`KEY_CHECK_LEN`]-byte value from the key and the stream's
+//! `nonce_prefix` via HKDF-SHA256 and stores it in the header.
+//! [`Reader::new`](crate::Reader::new) (and every other `Reader`
+//! constructor) recomputes the same value from the key it was given and
+//! compares it against the header's, returning
+//! [`Error::Aead`](crate::Error::Aead) immediately on a mismatch instead
+//! of reading any ciphertext.
+//!
+//! The check value is cleartext on the wire, like every other header
+//! field, but reveals nothing about the key itself: it's a one-way
+//! HKDF output bound to a nonce_prefix that's unique per stream, so two
+//! streams sealed under the same key don't even produce the same check
+//! value.
+
+use aead::{AeadCore, Key, KeyInit};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::nonce::PREFIX_LEN;
+
+/// The length, in bytes, of a stream's key-check value.
+pub(crate) const KEY_CHECK_LEN: usize = 16;
+
+const INFO: &[u8] = b"stream key check v1";
+
+/// Derives the key-check value [`Writer::with_key_check`](crate::Writer::with_key_check)
+/// writes to the header and [`Reader::new`](crate::Reader::new) verifies
+/// against it.
+///
+/// `extra` folds additional bytes into the HKDF context alongside the
+/// key and `nonce_prefix`; every caller before
+/// [`Writer::with_extensions`](crate::Writer::with_extensions) passes
+/// `&[]`, which leaves this byte-for-byte identical to the original
+/// two-argument derivation. [`Version::V4`](crate::Version::V4) passes
+/// its encoded extension area instead, so tampering with the
+/// "authenticated" TLV area is caught by the same mismatch a wrong key
+/// or prefix would trigger, rather than needing a second authentication
+/// primitive. See the [`header`](crate::header) module.
+pub(crate) fn derive_key_check<A>(
+    key: &Key<A>,
+    nonce_prefix: &[u8; PREFIX_LEN],
+    extra: &[u8],
+) -> [u8; KEY_CHECK_LEN]
+where
+    A: AeadCore + KeyInit,
+{
+    let hk = Hkdf::<Sha256>::new(None, key.as_slice());
+    let mut info = Vec::with_capacity(INFO.len() + PREFIX_LEN + extra.len());
+    info.extend_from_slice(INFO);
+    info.extend_from_slice(nonce_prefix);
+    info.extend_from_slice(extra);
+
+    let mut check = [0u8; KEY_CHECK_LEN];
+    hk.expand(&info, &mut check)
+        .expect("16 bytes is well within HKDF-SHA256's output size limit");
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use aead::Key;
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    use super::derive_key_check;
+
+    #[test]
+    fn identical_key_and_prefix_derive_identical_check_values() {
+        let key = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+        assert_eq!(
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], &[]),
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], &[])
+        );
+    }
+
+    #[test]
+    fn different_keys_derive_different_check_values_for_the_same_prefix() {
+        let a = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+        let b = Key::<ChaCha20Poly1305>::from([0x11; 32]);
+        assert_ne!(
+            derive_key_check::<ChaCha20Poly1305>(&a, &[0x24; 4], &[]),
+            derive_key_check::<ChaCha20Poly1305>(&b, &[0x24; 4], &[])
+        );
+    }
+
+    #[test]
+    fn different_prefixes_derive_different_check_values_for_the_same_key() {
+        let key = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+        assert_ne!(
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], &[]),
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x11; 4], &[])
+        );
+    }
+
+    #[test]
+    fn different_extra_bytes_derive_different_check_values_for_the_same_key_and_prefix() {
+        let key = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+        assert_ne!(
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], b"one"),
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], b"two")
+        );
+    }
+
+    #[test]
+    fn empty_extra_bytes_match_the_original_two_argument_derivation() {
+        let key = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+        assert_eq!(
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], &[]),
+            derive_key_check::<ChaCha20Poly1305>(&key, &[0x24; 4], b"")
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Key IDs and trial decryption across a set of keys.
+//!
+//! A stream's header can carry a short [`KeyId`] naming which key
+//! sealed it. [`Keyring`] holds a set of `(KeyId, key)` pairs and opens
+//! a stream by reading its header, looking up the matching key, and
+//! handing off to [`Reader`](crate::Reader) — useful for fleets that
+//! rotate keys and need to decrypt streams sealed under any key still
+//! on file, without tracking out-of-band which key goes with which
+//! stream.
+
+use std::io::{self, Read};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadCore, AeadInPlace, Key, KeyInit};
+use zeroize::Zeroize;
+
+use crate::header::{Header, HEADER_LEN};
+use crate::reader::Reader;
+use crate::Error;
+
+/// The length, in bytes, of a [`KeyId`].
+pub(crate) const KEY_ID_LEN: usize = 8;
+
+/// A short, non-secret identifier for a key, embedded in a stream's
+/// header so a [`Keyring`] can pick the right key without out-of-band
+/// bookkeeping.
+///
+/// A key ID is not itself a security boundary: it's sent in the clear
+/// and only needs to be unique enough, within a keyring, to tell keys
+/// apart.
+pub type KeyId = [u8; KEY_ID_LEN];
+
+/// A set of keys, indexed by [`KeyId`], used to open a stream sealed
+/// under any one of them.
+pub struct Keyring<A>
+where
+    A: AeadCore + KeyInit,
+{
+    keys: Vec<(KeyId, Key<A>)>,
+}
+
+impl<A> Keyring<A>
+where
+    A: AeadCore + KeyInit,
+{
+    /// Creates an empty keyring.
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    /// Adds a key to the keyring under `key_id`.
+    ///
+    /// If `key_id` is already present, the new key takes its place.
+    pub fn add(&mut self, key_id: KeyId, key: Key<A>) -> &mut Self {
+        if let Some(slot) = self.keys.iter_mut().find(|(id, _)| *id == key_id) {
+            slot.1.zeroize();
+            slot.1 = key;
+        } else {
+            self.keys.push((key_id, key));
+        }
+        self
+    }
+}
+
+impl<A> Drop for Keyring<A>
+where
+    A: AeadCore + KeyInit,
+{
+    fn drop(&mut self) {
+        for (_, key) in &mut self.keys {
+            key.zeroize();
+        }
+    }
+}
+
+impl<A> Default for Keyring<A>
+where
+    A: AeadCore + KeyInit,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Keyring<A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a stream, selecting the key named by its header's key ID.
+    ///
+    /// Returns [`Error::UnknownKeyId`] if the stream's header doesn't
+    /// carry a key ID, or names one this keyring doesn't hold.
+    pub fn open<R: Read>(&self, mut r: R) -> io::Result<Reader<R, A>> {
+        let mut header_buf = [0u8; HEADER_LEN];
+        r.read_exact(&mut header_buf)?;
+        let header = Header::decode(&header_buf).map_err(io::Error::other)?;
+        let key_id = header
+            .key_id
+            .ok_or_else(|| io::Error::other(Error::UnknownKeyId))?;
+        let key = self
+            .keys
+            .iter()
+            .find(|(id, _)| *id == key_id)
+            .map(|(_, key)| key)
+            .ok_or_else(|| io::Error::other(Error::UnknownKeyId))?;
+        Reader::from_header(r, header, key)
+    }
+}
@@ -0,0 +1,361 @@
+//! A length-prefixed chunk framing variant of the main
+//! [`Writer`](crate::Writer)/[`Reader`](crate::Reader) pair, for callers
+//! whose [`Reader`](crate::Reader) equivalent doesn't already agree with
+//! the writer on [`CHUNK_SIZE`](crate::CHUNK_SIZE), or that want to walk
+//! a stream's chunk boundaries without holding the key at all.
+//!
+//! The main pair's chunks are all exactly [`CHUNK_SIZE`](crate::CHUNK_SIZE)
+//! except for the last one, so [`Reader`](crate::Reader) tells the last
+//! chunk apart from every other one by peeking a byte past where the
+//! current chunk ends rather than by reading anything in the chunk
+//! itself (see the [`Version`](crate::Version) doc comment). That works
+//! well when both sides already share `CHUNK_SIZE` out of band, but
+//! leaves no way for a reader that doesn't -- or for tooling with no key
+//! at all, just trying to split a file into its chunks -- to find chunk
+//! boundaries. [`LengthPrefixedWriter`] trades the lookahead trick for
+//! an explicit length on every chunk, authenticated as that chunk's
+//! associated data alongside a one-byte continuation/final flag, so a
+//! chunk's length and role are both tamper-evident without decrypting
+//! its plaintext: a structural parser can walk the whole stream by
+//! reading one length at a time and skipping that many bytes, the same
+//! way it would walk any other length-prefixed record format.
+//!
+//! This is deliberately simpler than [`MessageWriter`](crate::MessageWriter):
+//! there's no message-boundary tag, no rekeying, and no heartbeats, just
+//! continuation and final chunks. A caller that needs those is better
+//! served by [`message`](crate::message) directly; this module exists
+//! for the narrower case of wanting length-prefixed framing without
+//! everything else that comes with it.
+//!
+//! Because every chunk already carries its own length, nothing requires
+//! chunks to be the same size: [`LengthPrefixedWriter::flush_chunk`]
+//! seals whatever's currently buffered, however little that is, instead
+//! of waiting for [`MAX_CHUNK_LEN`] to fill up. Latency-sensitive
+//! callers -- flushing on a timer so a peer sees plaintext as soon as
+//! it's produced rather than once a chunk's worth has accumulated --
+//! need exactly that; the main [`Writer`](crate::Writer)'s fixed
+//! [`CHUNK_SIZE`](crate::CHUNK_SIZE) chunks have no equivalent.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::cdc::Chunker;
+use crate::nonce::PREFIX_LEN;
+use crate::Error;
+
+const TAG_SIZE: usize = 16;
+
+/// The length, in bytes, of a chunk's big-endian plaintext-length prefix.
+const LEN_PREFIX_LEN: usize = 4;
+
+/// The length, in bytes, of a chunk's continuation/final flag.
+const FLAG_LEN: usize = 1;
+
+/// The largest plaintext length a chunk's length prefix may declare.
+///
+/// There's no `CHUNK_SIZE` a [`LengthPrefixedReader`] needs to agree
+/// with its writer on, but an upper bound still keeps a corrupted (or
+/// hostile) length field from making a reader allocate an unbounded
+/// amount of memory before authentication has had a chance to fail it.
+const MAX_CHUNK_LEN: usize = 64 * 1024;
+
+/// What a chunk's authenticated flag byte says about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkFlag {
+    /// More chunks follow.
+    Continuation,
+    /// This chunk ends the stream.
+    Final,
+}
+
+impl ChunkFlag {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Continuation => 0,
+            Self::Final => 1,
+        }
+    }
+
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Self::Continuation),
+            1 => Some(Self::Final),
+            _ => None,
+        }
+    }
+}
+
+/// Builds the 96-bit nonce for chunk `counter` of a stream whose random
+/// prefix is `prefix`.
+///
+/// Unlike [`nonce::build`](crate::nonce::build), there's no final-chunk
+/// flag packed in here: a chunk's role is carried by [`ChunkFlag`]
+/// instead, authenticated as this chunk's associated data.
+fn build_nonce(prefix: &[u8; PREFIX_LEN], counter: u64) -> GenericArray<u8, U12> {
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+/// Builds the associated data authenticating `flag` and the declared
+/// plaintext length `len` together, so neither can be tampered with
+/// independently of the ciphertext they frame.
+fn build_aad(flag: ChunkFlag, len: usize) -> [u8; FLAG_LEN + LEN_PREFIX_LEN] {
+    let mut aad = [0u8; FLAG_LEN + LEN_PREFIX_LEN];
+    aad[0] = flag.to_byte();
+    aad[FLAG_LEN..].copy_from_slice(&(len as u32).to_be_bytes());
+    aad
+}
+
+/// Seals a plaintext as a sequence of length-prefixed,
+/// AEAD-authenticated chunks written to an underlying [`Write`]r.
+pub struct LengthPrefixedWriter<W, A> {
+    w: W,
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    counter: u64,
+    buf: Vec<u8>,
+    /// Cuts chunk boundaries from plaintext content instead of from
+    /// [`MAX_CHUNK_LEN`], when set. See
+    /// [`LengthPrefixedWriter::with_chunker`].
+    chunker: Option<Chunker>,
+}
+
+impl<W, A> LengthPrefixedWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Starts a new stream, writing its nonce prefix to `w` immediately.
+    ///
+    /// `nonce_prefix` must be unique for every stream encrypted under
+    /// `key`, the same requirement as [`Writer::new`](crate::Writer::new).
+    pub fn new(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Self::new_inner(w, key, nonce_prefix, None)
+    }
+
+    /// Like [`LengthPrefixedWriter::new`], but cuts each chunk where
+    /// `chunker` says to instead of whenever [`MAX_CHUNK_LEN`] bytes
+    /// have accumulated, so a downstream dedup system chunking the same
+    /// plaintext the same way sees this stream's chunk boundaries line
+    /// up with its own, even though the ciphertext itself doesn't
+    /// deduplicate (each chunk is still sealed under its own nonce).
+    ///
+    /// `chunker`'s own `max_size` still applies on top of this: a chunk
+    /// is cut at `max_size` bytes regardless of content, the same
+    /// backstop [`Chunker::push`] already enforces.
+    pub fn with_chunker(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        chunker: Chunker,
+    ) -> io::Result<Self> {
+        Self::new_inner(w, key, nonce_prefix, Some(chunker))
+    }
+
+    fn new_inner(
+        mut w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        chunker: Option<Chunker>,
+    ) -> io::Result<Self> {
+        w.write_all(&nonce_prefix)?;
+        Ok(Self {
+            w,
+            aead: A::new(key),
+            nonce_prefix,
+            counter: 0,
+            buf: Vec::with_capacity(MAX_CHUNK_LEN),
+            chunker,
+        })
+    }
+
+    /// Seals whatever plaintext is currently buffered as `flag` and
+    /// writes it out, advancing the nonce counter.
+    fn write_chunk(&mut self, flag: ChunkFlag) -> io::Result<()> {
+        let mut chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(MAX_CHUNK_LEN));
+        let nonce = build_nonce(&self.nonce_prefix, self.counter);
+        let aad = build_aad(flag, chunk.len());
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, &aad, &mut chunk)
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        self.w.write_all(&aad)?;
+        self.w.write_all(&chunk)?;
+        self.w.write_all(&tag)?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other(Error::NonceOverflow))?;
+        Ok(())
+    }
+
+    /// Seals whatever plaintext is currently buffered as a
+    /// [`ChunkFlag::Continuation`] chunk and writes it out immediately,
+    /// without waiting for [`MAX_CHUNK_LEN`]'s worth of data to
+    /// accumulate.
+    ///
+    /// Chunks are already variable-length on the wire -- each one
+    /// carries its own authenticated length -- so nothing here stops a
+    /// chunk from being shorter than `MAX_CHUNK_LEN`; what the automatic
+    /// flushing in [`write`](Write::write) doesn't give a caller is a
+    /// way to *choose* when that happens. This does: a caller on a
+    /// timer, say, flushing every 100ms so a peer sees plaintext as
+    /// soon as it's available instead of once `MAX_CHUNK_LEN` fills up,
+    /// calls this directly instead of waiting on `write` to do it.
+    ///
+    /// Safe to call with nothing buffered: that seals an empty
+    /// zero-length chunk, a valid (if wasteful) way to keep the
+    /// connection active without committing to any plaintext yet.
+    pub fn flush_chunk(&mut self) -> io::Result<()> {
+        self.write_chunk(ChunkFlag::Continuation)
+    }
+
+    /// Finishes the stream: seals any buffered plaintext as the final
+    /// chunk, then returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(ChunkFlag::Final)?;
+        Ok(self.w)
+    }
+}
+
+impl<W, A> Write for LengthPrefixedWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let total = data.len();
+        if self.chunker.is_none() {
+            // No chunker: the fast path, filling `buf` up to
+            // `MAX_CHUNK_LEN` in as few copies as possible.
+            let mut data = data;
+            while !data.is_empty() {
+                let room = MAX_CHUNK_LEN - self.buf.len();
+                let n = data.len().min(room);
+                self.buf.extend_from_slice(&data[..n]);
+                data = &data[n..];
+                if self.buf.len() == MAX_CHUNK_LEN {
+                    self.write_chunk(ChunkFlag::Continuation)?;
+                }
+            }
+            return Ok(total);
+        }
+        // A chunker is in play: it has to see every byte in order to
+        // roll its hash, so there's no bulk-copy fast path here.
+        for &byte in data {
+            self.buf.push(byte);
+            let cut = self
+                .chunker
+                .as_mut()
+                .expect("checked is_some above")
+                .push(byte);
+            if cut || self.buf.len() == MAX_CHUNK_LEN {
+                self.write_chunk(ChunkFlag::Continuation)?;
+            }
+        }
+        Ok(total)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Decrypts a chunk sequence written by [`LengthPrefixedWriter`].
+pub struct LengthPrefixedReader<R, A> {
+    r: R,
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    counter: u64,
+    pbuf: Vec<u8>,
+    ppos: usize,
+    done: bool,
+}
+
+impl<R, A> LengthPrefixedReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a stream, reading its nonce prefix from `r`.
+    pub fn new(mut r: R, key: &Key<A>) -> io::Result<Self> {
+        let mut nonce_prefix = [0u8; PREFIX_LEN];
+        r.read_exact(&mut nonce_prefix)?;
+        Ok(Self {
+            r,
+            aead: A::new(key),
+            nonce_prefix,
+            counter: 0,
+            pbuf: Vec::new(),
+            ppos: 0,
+            done: false,
+        })
+    }
+
+    /// Reads and decrypts exactly one chunk into `self.pbuf`.
+    ///
+    /// Called only once the previous chunk's plaintext has been fully
+    /// consumed and the stream hasn't already been marked done, so a
+    /// truncated stream -- one cut short before its final chunk -- is
+    /// surfaced as an I/O error from the underlying `read_exact` calls
+    /// below rather than mistaken for a clean end of stream.
+    fn advance(&mut self) -> io::Result<()> {
+        let mut aad = [0u8; FLAG_LEN + LEN_PREFIX_LEN];
+        self.r.read_exact(&mut aad)?;
+        let flag = ChunkFlag::from_byte(aad[0])
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+        let mut len_buf = [0u8; LEN_PREFIX_LEN];
+        len_buf.copy_from_slice(&aad[FLAG_LEN..]);
+        let chunk_len = u32::from_be_bytes(len_buf) as usize;
+        if chunk_len > MAX_CHUNK_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::InvalidHeader,
+            ));
+        }
+
+        let mut sealed = vec![0u8; chunk_len + TAG_SIZE];
+        self.r.read_exact(&mut sealed)?;
+
+        let nonce = build_nonce(&self.nonce_prefix, self.counter);
+        let mut plaintext = sealed[..chunk_len].to_vec();
+        let tag: aead::Tag<A> = GenericArray::clone_from_slice(&sealed[chunk_len..]);
+        self.aead
+            .decrypt_in_place_detached(&nonce, &aad, &mut plaintext, &tag)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::Aead))?;
+
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::NonceOverflow))?;
+        self.done = flag == ChunkFlag::Final;
+        self.pbuf = plaintext;
+        self.ppos = 0;
+        Ok(())
+    }
+}
+
+impl<R, A> Read for LengthPrefixedReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.ppos >= self.pbuf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.advance()?;
+        }
+        let avail = &self.pbuf[self.ppos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.ppos += n;
+        Ok(n)
+    }
+}
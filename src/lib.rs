@@ -0,0 +1,204 @@
+//! `stream` implements the STREAM construction for online, chunked
+//! authenticated encryption: a long plaintext is split into fixed-size
+//! chunks, each chunk is sealed with an AEAD under a nonce derived from
+//! a per-stream random prefix and a monotonically increasing counter,
+//! and the final chunk is marked so that truncation is detected.
+//!
+//! This is the same high-level construction used by `age` and
+//! `libsodium`'s `secretstream` API: it lets you encrypt data you can't
+//! (or don't want to) hold in memory all at once, while still getting
+//! the same authenticity guarantees as a single, whole-message AEAD
+//! call.
+//!
+//! ```
+//! use chacha20poly1305::ChaCha20Poly1305;
+//! use stream::{Reader, Writer};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let key = [0x42; 32].into();
+//! let nonce_prefix = [0x24; 4];
+//!
+//! let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, nonce_prefix)?;
+//! std::io::Write::write_all(&mut w, b"hello, world")?;
+//! let ciphertext = w.finish()?;
+//!
+//! let mut plaintext = Vec::new();
+//! let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key)?;
+//! std::io::Read::read_to_end(&mut r, &mut plaintext)?;
+//! assert_eq!(plaintext, b"hello, world");
+//! # Ok(())
+//! # }
+//! ```
+
+mod aead_stream;
+#[cfg(feature = "age")]
+mod age;
+#[cfg(feature = "armor")]
+mod armor;
+mod atomic_file;
+mod buf;
+mod cdc;
+mod chunk_layout;
+#[cfg(feature = "compression")]
+mod compression;
+mod convergent;
+mod datagram;
+mod derive;
+mod digest;
+mod encrypted_file;
+mod error;
+mod export;
+#[cfg(feature = "fuzz")]
+mod fuzz;
+mod header;
+#[cfg(feature = "pq_hybrid")]
+mod hybrid_recipient;
+mod io;
+mod key_check;
+mod keyring;
+mod length_prefixed;
+mod message;
+mod metadata;
+mod mmap;
+mod nonce;
+mod padding;
+mod patch;
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
+#[cfg(feature = "pool")]
+mod pool;
+mod provider;
+#[cfg(feature = "proptest")]
+#[path = "proptest_strategies.rs"]
+pub mod proptest;
+mod range_reader;
+mod reader;
+#[cfg(feature = "reference")]
+mod reference;
+mod rekey;
+mod resync;
+mod session_keys;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "tar")]
+mod tar_archive;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "threaded")]
+mod threaded;
+mod tink;
+#[cfg(feature = "vectors")]
+mod vectors;
+mod volume;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod writer;
+
+pub use aead_stream::{
+    AeadStreamReader, AeadStreamWriter, Endian, PREFIX_LEN as AEAD_STREAM_PREFIX_LEN,
+};
+#[cfg(feature = "age")]
+pub use age::{
+    AgeReader, AgeWriter, CHUNK_SIZE as AGE_CHUNK_SIZE, FILE_KEY_LEN, PAYLOAD_NONCE_LEN,
+};
+#[cfg(feature = "armor")]
+pub use armor::{
+    ArmorReader, ArmorWriter, BEGIN_MARKER, BEGIN_MESSAGE_MARKER, END_MARKER, END_MESSAGE_MARKER,
+};
+pub use atomic_file::{decrypt_file, encrypt_file};
+pub use cdc::Chunker;
+#[cfg(feature = "compression")]
+pub use compression::{CompressReader, CompressWriter};
+pub use convergent::convergent_nonce_prefix;
+pub use datagram::{
+    DatagramOpener, DatagramSealer, ReplayWindow, PREFIX_LEN as DATAGRAM_PREFIX_LEN,
+};
+pub use derive::AlgorithmId;
+pub use digest::DigestAlgorithm;
+pub use encrypted_file::EncryptedFile;
+pub use error::{Error, ErrorKind};
+pub use export::EXPORT_KEY_LEN;
+#[cfg(feature = "fuzz")]
+pub use fuzz::StreamDescription;
+pub use header::{
+    peek_header, sniff, Extension, HeaderInfo, ParseVersionError, Version, HEADER_LEN, MAGIC_LEN,
+    STREAM_ID_LEN,
+};
+#[cfg(feature = "pq_hybrid")]
+pub use hybrid_recipient::{unwrap_key, wrap_key, Identity, Recipient};
+pub use io::{CountingReader, CountingWriter, FixedBuf, FixedBufReader, FixedBufWriter, TeeReader};
+pub use keyring::{KeyId, Keyring};
+pub use length_prefixed::{LengthPrefixedReader, LengthPrefixedWriter};
+pub use message::{ChunkTag, MessageReader, MessageWriter};
+pub use metadata::Metadata;
+pub use mmap::MmapReader;
+pub use patch::{rewrite_chunk, rewrite_chunk_at};
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::{Pkcs11Error, Pkcs11Provider};
+#[cfg(feature = "pool")]
+pub use pool::BufferPool;
+pub use provider::KeyProvider;
+pub use range_reader::{read_exact_at, write_all_at, RangeReader, ReadAt, WriteAt};
+pub use reader::{Reader, ReaderOpts, RecoveredChunk};
+#[cfg(feature = "parallel")]
+pub use reference::par_encrypt;
+#[cfg(feature = "reference")]
+pub use reference::{decrypt as reference_decrypt, encrypt as reference_encrypt};
+pub use rekey::reencrypt;
+#[cfg(feature = "stats")]
+pub use rekey::reencrypt_with_stats;
+pub use resync::find_chunk_boundary;
+pub use session_keys::derive_session_key;
+#[cfg(feature = "stats")]
+pub use stats::Stats;
+#[cfg(feature = "tar")]
+pub use tar_archive::{open_tar, seal_tar};
+#[cfg(feature = "threaded")]
+pub use threaded::{ThreadedReader, ThreadedWriter};
+pub use tink::{TinkReader, TinkWriter, DEFAULT_SEGMENT_SIZE};
+#[cfg(feature = "vectors")]
+pub use vectors::{dump as dump_vectors, load as load_vectors, run as run_vector, Vector};
+pub use volume::{VolumeReader, VolumeWriter};
+#[cfg(feature = "wasm")]
+pub use wasm::{WasmDecryptor, WasmEncryptor};
+pub use writer::Writer;
+
+/// The result type used throughout this crate.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The size, in bytes, of a plaintext chunk before the final chunk of a
+/// stream.
+///
+/// Chunks are fixed-size so that the ciphertext framing never needs to
+/// record plaintext lengths: everything but the last chunk is exactly
+/// `CHUNK_SIZE` bytes of plaintext.
+///
+/// This is a compile-time constant, not a per-stream setting: a
+/// `Writer` and the `Reader` that opens its output must agree on
+/// `CHUNK_SIZE`, which this crate enforces by having both sides read
+/// it from the same constant rather than negotiating it over the wire.
+/// The `large_chunks` feature swaps it from 64 KiB to 8 MiB, trading a
+/// few things object-storage callers tend to accept gladly: fewer AEAD
+/// calls and less tag overhead per byte of plaintext (one 16-byte tag
+/// per 8 MiB instead of per 64 KiB), at the cost of
+/// [`Reader`](crate::Reader) needing to buffer a whole chunk of
+/// ciphertext and a whole chunk of plaintext before it can return any
+/// of it (see [`Reader::read`](crate::Reader) and
+/// [`Buf`](crate::buf::Buf)'s backing storage), which raises
+/// time-to-first-byte and peak memory for both ends relative to 64 KiB
+/// chunks. `large_chunks` pulls in `boxed` so that larger storage
+/// doesn't also make every `Writer`/`Reader` itself multiple megabytes
+/// wide on the stack.
+///
+/// The chunk counter packed into each nonce (see the
+/// [`nonce`](crate::nonce) module) is unaffected by chunk size: it's
+/// 56 bits wide regardless of `CHUNK_SIZE`, so switching to 8 MiB
+/// chunks only pushes the point at which a multi-exabyte stream would
+/// need to fail with [`Error::NonceOverflow`] further out, never
+/// closer.
+#[cfg(not(feature = "large_chunks"))]
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// See the `large_chunks`-disabled [`CHUNK_SIZE`] doc comment above.
+#[cfg(feature = "large_chunks")]
+pub const CHUNK_SIZE: usize = 8 * 1024 * 1024;
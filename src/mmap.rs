@@ -0,0 +1,231 @@
+//! A read-only, random-access view over an already-in-memory
+//! ciphertext, for callers holding a memory-mapped (or otherwise
+//! already-loaded) stream who don't want [`Reader`](crate::Reader)'s
+//! sequential, copy-through-a-buffer framing.
+//!
+//! [`Reader`] is built around an `R: Read`: every chunk arrives through
+//! a `read()` call into an internal buffer, even when the underlying
+//! `R` is already a `&[u8]` with every byte addressable up front. For
+//! an `mmap`ed file, that `read()` is just a `memcpy` with no real IO
+//! behind it, but it's still a copy [`MmapReader`] skips: it decrypts a
+//! chunk's plaintext directly out of `ciphertext`'s own bytes, and it
+//! can do so for any chunk index, not only the next one in sequence.
+//!
+//! Nothing here actually maps memory -- this crate doesn't take a
+//! dependency on `memmap2` or any other `mmap` crate just for this. The
+//! caller maps the file however they like and hands `MmapReader` the
+//! resulting `&[u8]`; everything below only needs byte-slice indexing.
+//!
+//! # Limitations
+//!
+//! Like [`rewrite_chunk`](crate::rewrite_chunk) and
+//! [`EncryptedFile`](crate::EncryptedFile), [`MmapReader::new`] only
+//! opens a stream written by [`Writer::new`](crate::Writer::new) (or an
+//! equivalent plain constructor): a header with padding, a digest
+//! footer, derived nonces, or compression set is rejected, since none
+//! of those keep every non-final chunk at a fixed, computable offset
+//! the way the plain prefix-and-counter construction does. A
+//! [`Version::V4`](crate::Version::V4) header is rejected for the same
+//! reason: its TLV extension area sits between the fixed header and the
+//! first chunk, shifting every chunk offset [`ChunkLayout`] doesn't know
+//! to account for. So is a [`Version::V5`](crate::Version::V5) header,
+//! whose encrypted metadata block sits in that same spot, and a
+//! [`Version::V6`](crate::Version::V6) header, whose cleartext comment
+//! does too.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::buf::TAG_SIZE;
+use crate::chunk_layout::ChunkLayout;
+use crate::header::{Header, HEADER_LEN};
+use crate::key_check::derive_key_check;
+use crate::nonce::{self, PREFIX_LEN};
+use crate::{Error, CHUNK_SIZE};
+
+/// The one chunk of plaintext [`MmapReader`]'s [`Read`] impl keeps
+/// decrypted at a time, so repeated small reads within one chunk don't
+/// each pay for their own AEAD call.
+struct Cache {
+    index: u64,
+    plaintext: Vec<u8>,
+}
+
+/// A read-only, random-access view over an in-memory ciphertext's
+/// plaintext. See the module-level doc comment.
+pub struct MmapReader<'a, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    ciphertext: &'a [u8],
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    layout: ChunkLayout,
+    pos: u64,
+    cache: Option<Cache>,
+}
+
+impl<'a, A> MmapReader<'a, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens an in-memory ciphertext for random-access reading, at
+    /// plaintext offset 0.
+    ///
+    /// See the module-level doc comment for which headers are
+    /// rejected.
+    pub fn new(ciphertext: &'a [u8], key: &Key<A>) -> io::Result<Self> {
+        let header_bytes: &[u8; HEADER_LEN] = ciphertext
+            .get(..HEADER_LEN)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+        let header = Header::decode(header_bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if header.digest.is_some()
+            || header.padded
+            || header.derived_nonce
+            || header.compressed
+            || header.version == crate::Version::V4
+            || header.version == crate::Version::V5
+            || header.version == crate::Version::V6
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Error::InvalidHeader,
+            ));
+        }
+        if let Some(key_check) = header.key_check {
+            if derive_key_check::<A>(key, &header.nonce_prefix, &[]) != key_check {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, Error::Aead));
+            }
+        }
+        let body_len = (ciphertext.len() - HEADER_LEN) as u64;
+        let layout = ChunkLayout::compute(body_len)?;
+        Ok(Self {
+            ciphertext,
+            aead: A::new(key),
+            nonce_prefix: header.nonce_prefix,
+            layout,
+            pos: 0,
+            cache: None,
+        })
+    }
+
+    /// This stream's total plaintext length.
+    pub fn len(&self) -> u64 {
+        self.layout.total_len
+    }
+
+    /// Whether this stream's plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.layout.total_len == 0
+    }
+
+    /// The number of chunks (final chunk included) this stream is
+    /// sealed as.
+    pub fn chunk_count(&self) -> u64 {
+        self.layout.final_chunk_index + 1
+    }
+
+    /// Decrypts chunk `index` directly out of the mapped `ciphertext`,
+    /// with no intermediate copy of the sealed bytes: the AEAD call
+    /// reads them straight out of `ciphertext` and writes plaintext
+    /// into the `Vec` this returns.
+    ///
+    /// `index` must be `< self.chunk_count()`.
+    pub fn read_chunk(&self, index: u64) -> io::Result<Vec<u8>> {
+        if index > self.layout.final_chunk_index {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk index out of range",
+            ));
+        }
+        let plaintext_len = self.layout.chunk_len(index);
+        let offset = ChunkLayout::chunk_offset(index) as usize;
+        let sealed = self
+            .ciphertext
+            .get(offset..offset + plaintext_len + TAG_SIZE)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    Error::TruncatedChunk {
+                        chunk: index,
+                        offset: offset as u64,
+                    },
+                )
+            })?;
+        let mut plaintext = sealed[..plaintext_len].to_vec();
+        let tag: aead::Tag<A> =
+            aead::generic_array::GenericArray::clone_from_slice(&sealed[plaintext_len..]);
+        let nonce = nonce::build(
+            &self.nonce_prefix,
+            index,
+            index == self.layout.final_chunk_index,
+        );
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut plaintext, &tag)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::AeadAt {
+                        chunk: index,
+                        offset: offset as u64,
+                    },
+                )
+            })?;
+        Ok(plaintext)
+    }
+}
+
+impl<A> Read for MmapReader<'_, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.layout.total_len {
+            return Ok(0);
+        }
+        let chunk_index = self.pos / CHUNK_SIZE as u64;
+        let offset_in_chunk = (self.pos % CHUNK_SIZE as u64) as usize;
+        if !matches!(&self.cache, Some(c) if c.index == chunk_index) {
+            let plaintext = self.read_chunk(chunk_index)?;
+            self.cache = Some(Cache {
+                index: chunk_index,
+                plaintext,
+            });
+        }
+        let cache = self.cache.as_ref().expect("just loaded above");
+        let n = buf.len().min(cache.plaintext.len() - offset_in_chunk);
+        buf[..n].copy_from_slice(&cache.plaintext[offset_in_chunk..offset_in_chunk + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<A> Seek for MmapReader<'_, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => (n, 0),
+            SeekFrom::End(n) => (self.layout.total_len, n),
+            SeekFrom::Current(n) => (self.pos, n),
+        };
+        let new_pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        let new_pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.pos = new_pos;
+        Ok(self.pos)
+    }
+}
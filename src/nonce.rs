@@ -0,0 +1,77 @@
+//! Nonce construction for the STREAM construction.
+//!
+//! Each chunk's nonce is built from a per-stream random prefix (stored
+//! in the header), a big-endian chunk counter, and a one-byte flag
+//! marking the final chunk of the stream. Reusing a `(prefix, counter)`
+//! pair under the same key would be catastrophic for AEAD security, so
+//! the prefix must be generated fresh for every stream and the counter
+//! must never wrap.
+//!
+//! This layout is fixed at 96 bits and isn't generic over the AEAD's
+//! nonce size: every public constructor in this crate is bounded by
+//! `AeadInPlace<NonceSize = U12, ...>`, so an AEAD with a different
+//! nonce size is rejected by the type checker before any of the
+//! splitting below runs. The asserts further down exist to keep the
+//! three pieces of *this* 96-bit nonce honest with each other (and
+//! with [`PREFIX_LEN`]'s minimum entropy) as this module evolves, not
+//! to validate `A::NonceSize` itself.
+//!
+//! Ascon-128a was evaluated for first-class support and doesn't fit
+//! here: the `ascon-aead` crate's `AsconAead128` uses a 128-bit nonce
+//! (`NonceSize = U16`), not the 96 bits this module splits, and beyond
+//! that it's built on `aead` 0.6's `AeadInOut` rather than the `aead`
+//! 0.5 `AeadInPlace` trait this crate's `Writer`/`Reader` are bound to.
+//! Supporting it would mean either a second, incompatible major
+//! version of `aead` in the dependency tree or a hand-rolled Ascon
+//! permutation outside the `aead` trait ecosystem entirely -- both
+//! bigger changes than a type alias, so it's left out for now.
+//!
+//! The AEGIS family runs into the same two walls. The `aegis` crate's
+//! `Aegis128L` takes a 128-bit nonce and `Aegis256` a 256-bit nonce,
+//! both wider than the 96 bits this module builds, and its optional
+//! RustCrypto-trait impls are against `aead` 0.6, not the 0.5 this
+//! crate depends on. Its large nonces are part of the appeal for
+//! high-throughput use (room for a random nonce with no counter
+//! reuse risk across restarts), but that's exactly what doesn't fit a
+//! fixed 96-bit prefix-and-counter split.
+
+use aead::generic_array::typenum::U12;
+use aead::generic_array::GenericArray;
+
+/// The length, in bytes, of the random per-stream nonce prefix.
+///
+/// Must be large enough that per-stream prefixes don't collide within
+/// realistic usage volumes; 4 bytes (32 bits) is the floor below which
+/// the birthday bound becomes uncomfortably small.
+pub(crate) const PREFIX_LEN: usize = 4;
+
+/// The length, in bytes, of the big-endian chunk counter packed into
+/// the nonce.
+const COUNTER_LEN: usize = 7;
+
+/// The length, in bytes, of the final-chunk flag packed into the nonce.
+const FLAG_LEN: usize = 1;
+
+/// The minimum acceptable entropy, in bytes, for [`PREFIX_LEN`]. See
+/// its doc comment.
+const MIN_PREFIX_ENTROPY: usize = 4;
+
+const _: () = assert!(
+    PREFIX_LEN >= MIN_PREFIX_ENTROPY,
+    "nonce prefix is too short to provide meaningful entropy"
+);
+const _: () = assert!(
+    PREFIX_LEN + COUNTER_LEN + FLAG_LEN == 12,
+    "prefix, counter, and final-chunk flag must exactly fill a 96-bit nonce"
+);
+
+/// Builds a 96-bit AEAD nonce for chunk `counter` of a stream whose
+/// random prefix is `prefix`. `last` must be `true` only for the final
+/// chunk of the stream.
+pub(crate) fn build(prefix: &[u8; PREFIX_LEN], counter: u64, last: bool) -> GenericArray<u8, U12> {
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..PREFIX_LEN + COUNTER_LEN].copy_from_slice(&counter.to_be_bytes()[1..]);
+    nonce[PREFIX_LEN + COUNTER_LEN] = last as u8;
+    nonce
+}
@@ -0,0 +1,56 @@
+//! Padmé padding.
+//!
+//! Padmé (Beck & Barnett, "Padmé: Grow with the Flow") rounds a length
+//! up to the nearest value whose low bits are all zero, where the
+//! number of zeroed bits grows with the length being padded. This
+//! leaks only `O(log log n)` bits about the true length, compared to
+//! `O(log n)` bits leaked by an unpadded length.
+
+/// The length, in bytes, of the authenticated true-length footer
+/// appended after a padded final chunk's plaintext (and any pad bytes).
+pub(crate) const LENGTH_FOOTER_LEN: usize = 8;
+
+/// Rounds `len` up to its Padmé target length.
+pub(crate) fn padme_target(len: u64) -> u64 {
+    if len < 2 {
+        return len;
+    }
+    let e = len.ilog2() as u64;
+    if e == 0 {
+        return len;
+    }
+    let s = e.ilog2() as u64 + 1;
+    let last_bits = e - s;
+    let bit_mask = (1u64 << last_bits) - 1;
+    (len + bit_mask) & !bit_mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::padme_target;
+
+    #[test]
+    fn never_shrinks() {
+        for len in 0..10_000u64 {
+            assert!(padme_target(len) >= len);
+        }
+    }
+
+    #[test]
+    fn idempotent() {
+        for len in 0..10_000u64 {
+            let padded = padme_target(len);
+            assert_eq!(padme_target(padded), padded);
+        }
+    }
+
+    #[test]
+    fn monotonic() {
+        let mut prev = padme_target(0);
+        for len in 1..10_000u64 {
+            let padded = padme_target(len);
+            assert!(padded >= prev);
+            prev = padded;
+        }
+    }
+}
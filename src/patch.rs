@@ -0,0 +1,186 @@
+//! In-place rewriting of a single chunk, for seekable outputs.
+//!
+//! [`Writer`](crate::Writer) is append-only: once a chunk is sealed and
+//! written, nothing in this crate lets a caller go back and change it
+//! without re-encrypting everything from that point on. That's the
+//! right default for a `W: Write` that might be a socket or a pipe, but
+//! it's wasteful for a large ciphertext sitting in a regular file or
+//! anything else that's also [`Seek`]: patching one chunk's worth of
+//! plaintext shouldn't require rewriting every chunk after it just to
+//! keep their positions unchanged, when those positions were never
+//! going to move in the first place.
+//!
+//! [`rewrite_chunk`] takes advantage of that: every non-final chunk a
+//! plain [`Writer::new`](crate::Writer::new) stream writes is exactly
+//! [`CHUNK_SIZE`] bytes of plaintext at a fixed, computable offset, so
+//! replacing one with different plaintext of the same length -- sealed
+//! under a fresh tag, but the same nonce the original chunk used --
+//! doesn't touch any byte outside that chunk.
+//!
+//! # Limitations
+//!
+//! This only covers chunk `chunk_index` when it's a non-final chunk of
+//! a stream written with [`Writer::new`](crate::Writer::new) (or an
+//! equivalent plain constructor using the default prefix-and-counter
+//! nonce, not [`Writer::with_derived_nonces`](crate::Writer::with_derived_nonces)):
+//!
+//! - The final chunk has no fixed length -- it holds whatever plaintext
+//!   was left over, plus any padding or digest footer -- so it has no
+//!   fixed offset to rewrite in place.
+//! - A stream written with
+//!   [`Writer::with_digest`](crate::Writer::with_digest) has an
+//!   authenticated digest of the *entire* plaintext in its final chunk;
+//!   rewriting any other chunk invalidates that digest, and this
+//!   function has no way to recompute or re-seal the final chunk to fix
+//!   it up. Don't use this on a digested stream.
+//! - A stream written with
+//!   [`Writer::with_derived_nonces`](crate::Writer::with_derived_nonces)
+//!   derives its chunk nonces from a [`NonceDeriver`](crate::derive),
+//!   not the plain prefix-and-counter construction `rewrite_chunk`
+//!   rebuilds here. Don't use this on such a stream either.
+//!
+//! None of the above is checked: `rewrite_chunk` takes `key` and
+//! `nonce_prefix` directly, the same way
+//! [`Writer::new`](crate::Writer::new) does, and has no way to tell
+//! from those alone which constructor originally sealed the stream.
+//! Getting it wrong doesn't corrupt the other chunks, but it will make
+//! chunk `chunk_index` fail to authenticate when read back.
+//!
+//! # Safety
+//!
+//! [`rewrite_chunk`] and [`rewrite_chunk_at`] are `unsafe`: they reseal
+//! `chunk_index` under exactly the nonce
+//! [`Writer::new`](crate::Writer::new) used for it the first time --
+//! `nonce::build(&nonce_prefix, chunk_index, false)` -- and *the very
+//! first call already reuses that nonce against the original chunk*,
+//! not just against a later second rewrite. That's nonce reuse the
+//! moment any copy of the original ciphertext (a backup, a filesystem
+//! snapshot, a network capture, the previous version of the file
+//! itself if the caller kept one) is retained or becomes observable
+//! again, and for the AEADs this crate supports (ChaCha20Poly1305,
+//! AES-GCM) it's not a soft failure: anyone who can see two ciphertexts
+//! sealed under the same key and nonce can recover the keystream for
+//! that nonce and use it to forge or decrypt *other* chunks sealed
+//! under the same key, not just this one. This is the same failure
+//! mode as reusing a one-time pad.
+//!
+//! This crate's fixed 96-bit prefix-and-counter nonce layout (see
+//! [`crate::nonce`]) has no spare bits to mix a fresh value into per
+//! rewrite, so there's no way for these functions to make a rewrite
+//! safe on their own -- the caller has to guarantee it instead. See
+//! each function's own `# Safety` section for exactly what to
+//! guarantee.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::buf::TAG_SIZE;
+use crate::header::HEADER_LEN;
+use crate::nonce::{self, PREFIX_LEN};
+use crate::range_reader::{write_all_at, WriteAt};
+use crate::{Error, CHUNK_SIZE};
+
+/// The ciphertext offset a non-final chunk `chunk_index` begins at, and
+/// its freshly-sealed bytes -- the part [`rewrite_chunk`] and
+/// [`rewrite_chunk_at`] share, before they part ways on how to deliver
+/// those bytes to the destination.
+fn seal_chunk<A>(
+    key: &Key<A>,
+    nonce_prefix: [u8; PREFIX_LEN],
+    chunk_index: u64,
+    plaintext: &[u8],
+) -> io::Result<(u64, Vec<u8>)>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    if plaintext.len() != CHUNK_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            Error::InvalidHeader,
+        ));
+    }
+    let offset = HEADER_LEN as u64 + chunk_index * (CHUNK_SIZE + TAG_SIZE) as u64;
+
+    let nonce = nonce::build(&nonce_prefix, chunk_index, false);
+    let mut sealed = plaintext.to_vec();
+    let tag = A::new(key)
+        .encrypt_in_place_detached(&nonce, b"", &mut sealed)
+        .map_err(|_| io::Error::other(Error::Aead))?;
+    sealed.extend_from_slice(&tag);
+
+    Ok((offset, sealed))
+}
+
+/// Overwrites non-final chunk `chunk_index` of an already-written
+/// stream with `plaintext`, sealing it under a fresh tag in place.
+///
+/// `plaintext` must be exactly [`CHUNK_SIZE`] bytes, the only length a
+/// non-final chunk is ever sealed at; anything else is rejected rather
+/// than risk writing a chunk whose length doesn't match what the rest
+/// of the stream expects at that offset. `key` and `nonce_prefix` must
+/// match what the stream was originally sealed with. See the
+/// module-level doc comment for which streams this does and doesn't
+/// apply to.
+///
+/// # Safety
+///
+/// Rewrites `chunk_index` under the same nonce it was already sealed
+/// with, so the caller must guarantee, for the lifetime of `key`:
+///
+/// - No copy of `s`'s ciphertext from before this call -- an earlier
+///   version of the file, a backup, a filesystem snapshot, a network
+///   capture -- is ever retained or becomes observable again once this
+///   call returns.
+/// - `chunk_index` is never rewritten again (by this function, by
+///   [`rewrite_chunk_at`], or by any other means) while that guarantee
+///   still needs to hold.
+///
+/// Violating either one reuses a nonce and breaks confidentiality and
+/// authenticity for the entire key, not just this chunk. See the
+/// module-level "Safety" section for why nothing here can check this
+/// for you.
+pub unsafe fn rewrite_chunk<S, A>(
+    s: &mut S,
+    key: &Key<A>,
+    nonce_prefix: [u8; PREFIX_LEN],
+    chunk_index: u64,
+    plaintext: &[u8],
+) -> io::Result<()>
+where
+    S: Write + Seek,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let (offset, sealed) = seal_chunk::<A>(key, nonce_prefix, chunk_index, plaintext)?;
+    s.seek(SeekFrom::Start(offset))?;
+    s.write_all(&sealed)?;
+    Ok(())
+}
+
+/// Like [`rewrite_chunk`], but through [`WriteAt`] instead of
+/// [`Write`] + [`Seek`], so patching several chunks of the same stream
+/// doesn't have to serialize through one shared cursor -- concurrent
+/// callers each seal their own chunk and issue one [`WriteAt::write_at`]
+/// covering only that chunk, the same way [`RangeReader`](crate::RangeReader)
+/// reads one through [`ReadAt`](crate::ReadAt) instead of a shared
+/// [`Seek`] position.
+///
+/// # Safety
+///
+/// Same nonce-reuse hazard, and the same caller obligations, as
+/// [`rewrite_chunk`] -- see its "Safety" section before using this.
+pub unsafe fn rewrite_chunk_at<S, A>(
+    s: &S,
+    key: &Key<A>,
+    nonce_prefix: [u8; PREFIX_LEN],
+    chunk_index: u64,
+    plaintext: &[u8],
+) -> io::Result<()>
+where
+    S: WriteAt,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let (offset, sealed) = seal_chunk::<A>(key, nonce_prefix, chunk_index, plaintext)?;
+    write_all_at(s, offset, &sealed)
+}
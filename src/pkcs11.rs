@@ -0,0 +1,157 @@
+//! PKCS#11 / HSM-backed [`KeyProvider`], gated behind the `pkcs11`
+//! feature.
+//!
+//! [`Pkcs11Provider`] keeps a stream's long-term wrapping key on a
+//! PKCS#11 token (a hardware security module, a smart card, or a
+//! software module like SoftHSM2): [`KeyProvider::wrap`] and
+//! [`KeyProvider::unwrap_dek`] move a per-stream data-encryption key in
+//! and out of the token via `C_WrapKey`/`C_UnwrapKey`, so the wrapping
+//! key itself never has to be marked extractable. The unwrapped DEK is
+//! still briefly materialized in process memory, since chunk encryption
+//! is done in software by this crate's (non-HSM) AEAD implementations.
+//!
+//! [`KeyProvider::resolve`] is also provided, for tokens or deployments
+//! that accept extractable session keys; it looks up a key object by
+//! label and reads its raw value back with `C_GetAttributeValue`. This
+//! only succeeds if the object's `CKA_EXTRACTABLE` attribute is true.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use aead::{AeadCore, Key, KeyInit};
+use cryptoki::error::Error as CryptokiError;
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, ObjectClass, ObjectHandle};
+use cryptoki::session::Session;
+
+use crate::keyring::KeyId;
+use crate::provider::KeyProvider;
+
+/// Errors returned by [`Pkcs11Provider`].
+#[derive(Debug)]
+pub enum Pkcs11Error {
+    /// The underlying PKCS#11 call failed.
+    Pkcs11(CryptokiError),
+    /// No object on the token carries the given key ID as its label.
+    KeyNotFound,
+    /// An unwrapped or resolved key didn't have the length `A` expects.
+    UnexpectedKeyLength,
+}
+
+impl fmt::Display for Pkcs11Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pkcs11(e) => write!(f, "PKCS#11 error: {e}"),
+            Self::KeyNotFound => write!(f, "no token object with the given key ID"),
+            Self::UnexpectedKeyLength => write!(f, "key from token had an unexpected length"),
+        }
+    }
+}
+
+impl std::error::Error for Pkcs11Error {}
+
+impl From<CryptokiError> for Pkcs11Error {
+    fn from(e: CryptokiError) -> Self {
+        Self::Pkcs11(e)
+    }
+}
+
+/// A [`KeyProvider`] backed by a PKCS#11 token.
+///
+/// Each [`KeyId`] names a key object on the token, found by matching
+/// `CKA_LABEL` against the key ID's bytes.
+pub struct Pkcs11Provider<A> {
+    session: Session,
+    wrap_mechanism: Mechanism<'static>,
+    _marker: PhantomData<A>,
+}
+
+impl<A> Pkcs11Provider<A> {
+    /// Wraps an already-open, already-logged-in PKCS#11 `session`.
+    /// `wrap_mechanism` is used for [`KeyProvider::wrap`] and
+    /// [`KeyProvider::unwrap_dek`]; `Mechanism::AesKeyWrap` is a
+    /// reasonable default for most tokens.
+    pub fn new(session: Session, wrap_mechanism: Mechanism<'static>) -> Self {
+        Self {
+            session,
+            wrap_mechanism,
+            _marker: PhantomData,
+        }
+    }
+
+    fn find_key(&self, key_id: KeyId) -> Result<ObjectHandle, Pkcs11Error> {
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::Label(key_id.to_vec()),
+        ];
+        self.session
+            .find_objects(&template)?
+            .into_iter()
+            .next()
+            .ok_or(Pkcs11Error::KeyNotFound)
+    }
+}
+
+impl<A> KeyProvider<A> for Pkcs11Provider<A>
+where
+    A: AeadCore + KeyInit,
+{
+    type Error = Pkcs11Error;
+
+    fn resolve(&self, key_id: KeyId) -> Result<Key<A>, Self::Error> {
+        let handle = self.find_key(key_id)?;
+        let attrs = self
+            .session
+            .get_attributes(handle, &[AttributeType::Value])?;
+        let value = attrs
+            .into_iter()
+            .find_map(|attr| match attr {
+                Attribute::Value(v) => Some(v),
+                _ => None,
+            })
+            .ok_or(Pkcs11Error::KeyNotFound)?;
+        if value.len() != Key::<A>::default().len() {
+            return Err(Pkcs11Error::UnexpectedKeyLength);
+        }
+        Ok(Key::<A>::clone_from_slice(&value))
+    }
+
+    fn wrap(&self, key_id: KeyId, dek: &Key<A>) -> Result<Vec<u8>, Self::Error> {
+        let wrapping_key = self.find_key(key_id)?;
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::Value(dek.to_vec()),
+            Attribute::Extractable(false),
+            Attribute::Sensitive(true),
+        ];
+        let dek_handle = self.session.create_object(&template)?;
+        Ok(self
+            .session
+            .wrap_key(&self.wrap_mechanism, wrapping_key, dek_handle)?)
+    }
+
+    fn unwrap_dek(&self, key_id: KeyId, wrapped: &[u8]) -> Result<Key<A>, Self::Error> {
+        let wrapping_key = self.find_key(key_id)?;
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::Extractable(true),
+        ];
+        let dek_handle =
+            self.session
+                .unwrap_key(&self.wrap_mechanism, wrapping_key, wrapped, &template)?;
+        let attrs = self
+            .session
+            .get_attributes(dek_handle, &[AttributeType::Value])?;
+        let value = attrs
+            .into_iter()
+            .find_map(|attr| match attr {
+                Attribute::Value(v) => Some(v),
+                _ => None,
+            })
+            .ok_or(Pkcs11Error::KeyNotFound)?;
+        if value.len() != Key::<A>::default().len() {
+            return Err(Pkcs11Error::UnexpectedKeyLength);
+        }
+        Ok(Key::<A>::clone_from_slice(&value))
+    }
+}
@@ -0,0 +1,63 @@
+//! A pool of reusable ciphertext buffers, gated behind the `pool`
+//! feature, for services that open many short-lived streams and don't
+//! want every [`Reader`](crate::Reader) to allocate its own
+//! chunk-sized `cbuf` from scratch.
+//!
+//! [`BufferPool`] is a cheap-to-clone handle around a shared free list.
+//! [`Reader::with_pool`](crate::Reader::with_pool) checks a buffer out
+//! of it when the stream is opened and returns it to the pool when the
+//! `Reader` is dropped, so a service cycling through many short streams
+//! reuses the same handful of buffers instead of allocating (and
+//! zeroing, since `Vec::with_capacity` doesn't but growth along the way
+//! might) a new one per stream.
+//!
+//! [`Writer`](crate::Writer) has no equivalent constructor: its chunk
+//! buffer is inline (part of the `Writer` itself) unless the `boxed`
+//! feature is enabled, so there's normally nothing to pool on the write
+//! side. Pooling `Writer`'s `boxed` storage is a natural follow-up, not
+//! implemented here.
+
+use std::sync::{Arc, Mutex};
+
+/// A pool of reusable `Vec<u8>` buffers, shared (cheaply, via a clone)
+/// across however many streams check one out at a time.
+#[derive(Clone)]
+pub struct BufferPool {
+    free: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl BufferPool {
+    /// Creates an empty pool. Buffers are allocated lazily, the first
+    /// time a check-out finds nothing free to reuse.
+    pub fn new() -> Self {
+        Self {
+            free: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Checks a buffer with at least `cap` bytes of capacity out of the
+    /// pool, allocating a new one if none is free.
+    pub(crate) fn take(&self, cap: usize) -> Vec<u8> {
+        let mut free = self.free.lock().unwrap();
+        match free.iter().position(|buf| buf.capacity() >= cap) {
+            Some(i) => {
+                let mut buf = free.swap_remove(i);
+                buf.clear();
+                buf
+            }
+            None => Vec::with_capacity(cap),
+        }
+    }
+
+    /// Returns `buf` to the pool for a future [`BufferPool::take`] to
+    /// reuse.
+    pub(crate) fn put(&self, buf: Vec<u8>) {
+        self.free.lock().unwrap().push(buf);
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
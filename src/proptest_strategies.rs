@@ -0,0 +1,152 @@
+//! Property-testing strategies encoding this crate's own invariants,
+//! gated behind the `proptest` feature so `proptest` doesn't become
+//! part of the crate's default dependency surface. Exposed at the
+//! crate root as [`crate::proptest`], so a downstream property test
+//! imports from `stream::proptest` the way it would from
+//! `proptest::prelude`.
+//!
+//! [`plaintext_len`] and [`plaintext`] bias toward the lengths most
+//! likely to expose an off-by-one in chunk framing: one byte short of
+//! [`CHUNK_SIZE`], exactly `CHUNK_SIZE`, and one byte over. [`Mutation`]
+//! encodes the tamperings [`Reader`](crate::Reader) is expected to
+//! reject with an authentication failure rather than wrong plaintext.
+
+use proptest::prelude::*;
+
+use crate::buf::TAG_SIZE;
+use crate::chunk_layout::ChunkLayout;
+use crate::{CHUNK_SIZE, HEADER_LEN};
+
+/// A random 32-byte AEAD key.
+pub fn key() -> impl Strategy<Value = [u8; 32]> {
+    any::<[u8; 32]>()
+}
+
+/// A random nonce prefix.
+pub fn nonce_prefix() -> impl Strategy<Value = [u8; 4]> {
+    any::<[u8; 4]>()
+}
+
+/// Capped independent of [`CHUNK_SIZE`], which is 8 MiB under the
+/// `large_chunks` feature: without this cap, every boundary point below
+/// would generate tens of megabytes of plaintext per case, which is
+/// what actually makes `cargo test --all-features` slow, not the number
+/// of cases. Twice the default (`large_chunks`-disabled) `CHUNK_SIZE`,
+/// so this is a no-op unless `large_chunks` is enabled.
+const MAX_TEST_LEN: usize = 2 * 64 * 1024;
+
+/// Plaintext lengths straddling a chunk boundary -- one byte short of
+/// [`CHUNK_SIZE`], exactly `CHUNK_SIZE`, one byte over, the same again
+/// for two chunks, the empty stream, and a uniformly random length up
+/// to two chunks for everything those fixed points miss. Every length
+/// above is clamped to [`MAX_TEST_LEN`] first, so under `large_chunks`
+/// these collapse toward that cap instead of actually reaching the
+/// 8 MiB chunk boundary -- still exercises the same code paths at a
+/// length practical to generate, just not the exact boundary offset.
+pub fn plaintext_len() -> impl Strategy<Value = usize> {
+    let boundary = |n: usize| Just(n.min(MAX_TEST_LEN));
+    prop_oneof![
+        Just(0),
+        Just(1),
+        boundary(CHUNK_SIZE - 1),
+        boundary(CHUNK_SIZE),
+        boundary(CHUNK_SIZE + 1),
+        boundary(2 * CHUNK_SIZE - 1),
+        boundary(2 * CHUNK_SIZE),
+        boundary(2 * CHUNK_SIZE + 1),
+        0..=(2 * CHUNK_SIZE + 1).min(MAX_TEST_LEN),
+    ]
+}
+
+/// Plaintext of a length drawn from [`plaintext_len`].
+pub fn plaintext() -> impl Strategy<Value = Vec<u8>> {
+    plaintext_len().prop_flat_map(|len| prop::collection::vec(any::<u8>(), len))
+}
+
+/// A tampering to apply to a sealed ciphertext via [`Mutation::apply`],
+/// for testing that [`Reader`](crate::Reader) rejects it rather than
+/// silently producing wrong plaintext.
+#[derive(Debug, Clone, Copy)]
+pub enum Mutation {
+    /// Drops everything from byte `at` onward.
+    Truncate {
+        /// Clamped to the ciphertext's length by [`Mutation::apply`].
+        at: usize,
+    },
+    /// Flips one bit of byte `at`.
+    FlipBit {
+        /// Reduced modulo the ciphertext's length by [`Mutation::apply`].
+        at: usize,
+        /// Reduced modulo 8 by [`Mutation::apply`].
+        bit: u8,
+    },
+    /// Swaps two whole chunks' ciphertext (tag included).
+    SwapChunks {
+        /// Reduced modulo the stream's chunk count by [`Mutation::apply`].
+        a: u64,
+        /// Reduced modulo the stream's chunk count by [`Mutation::apply`].
+        b: u64,
+    },
+}
+
+impl Mutation {
+    /// Applies the mutation to `ciphertext` in place. Every field is
+    /// clamped or reduced to something in range, so a [`Mutation`] this
+    /// module's [`mutation`] strategy produces is applicable to any
+    /// ciphertext, no matter what plaintext length generated it --
+    /// including a [`Mutation::SwapChunks`] with nothing to swap or a
+    /// ciphertext too short to have a body at all, which leave
+    /// `ciphertext` untouched rather than panicking.
+    pub fn apply(&self, ciphertext: &mut Vec<u8>) {
+        match *self {
+            Mutation::Truncate { at } => {
+                ciphertext.truncate(at.min(ciphertext.len()));
+            }
+            Mutation::FlipBit { at, bit } => {
+                if ciphertext.is_empty() {
+                    return;
+                }
+                let at = at % ciphertext.len();
+                ciphertext[at] ^= 1 << (bit % 8);
+            }
+            Mutation::SwapChunks { a, b } => Self::swap_chunks(ciphertext, a, b),
+        }
+    }
+
+    fn swap_chunks(ciphertext: &mut [u8], a: u64, b: u64) {
+        if ciphertext.len() <= HEADER_LEN {
+            return;
+        }
+        let Ok(layout) = ChunkLayout::compute((ciphertext.len() - HEADER_LEN) as u64) else {
+            return;
+        };
+        let chunk_count = layout.final_chunk_index + 1;
+        let (a, b) = (a % chunk_count, b % chunk_count);
+        if a == b {
+            return;
+        }
+        // Only chunks of equal sealed length can be swapped in place
+        // without shifting every byte after them; every chunk but the
+        // final one shares that length, so a swap involving the final
+        // chunk is skipped unless it happens to match anyway.
+        let a_len = layout.chunk_len(a) + TAG_SIZE;
+        let b_len = layout.chunk_len(b) + TAG_SIZE;
+        if a_len != b_len {
+            return;
+        }
+        let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+        let lo_off = ChunkLayout::chunk_offset(lo) as usize;
+        let hi_off = ChunkLayout::chunk_offset(hi) as usize;
+        let (left, right) = ciphertext.split_at_mut(hi_off);
+        left[lo_off..lo_off + a_len].swap_with_slice(&mut right[..a_len]);
+    }
+}
+
+/// A strategy generating [`Mutation`]s.
+pub fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        any::<usize>().prop_map(|at| Mutation::Truncate { at }),
+        (any::<usize>(), any::<u8>()).prop_map(|(at, bit)| Mutation::FlipBit { at, bit }),
+        (any::<u64>(), any::<u64>()).prop_map(|(a, b)| Mutation::SwapChunks { a, b }),
+    ]
+}
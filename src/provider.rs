@@ -0,0 +1,48 @@
+//! External key management.
+//!
+//! [`KeyProvider`] lets a key live behind an external system — AWS KMS,
+//! GCP KMS, HashiCorp Vault, a company-internal key service — instead
+//! of in the caller's process memory. [`Writer::with_provider`] and
+//! [`Reader::with_provider`] accept any implementation and resolve the
+//! key to use by [`KeyId`](crate::KeyId) instead of requiring the
+//! caller to hold it directly.
+//!
+//! [`Writer::with_provider`]: crate::Writer::with_provider
+//! [`Reader::with_provider`]: crate::Reader::with_provider
+
+use std::error::Error as StdError;
+
+use aead::{AeadCore, Key, KeyInit};
+
+use crate::keyring::KeyId;
+
+/// A source of keys, identified by [`KeyId`], backed by an external key
+/// management system.
+///
+/// Implementations are free to cache resolved keys, make network calls,
+/// or fail transiently; [`Writer`](crate::Writer) and
+/// [`Reader`](crate::Reader) surface `Self::Error` as an
+/// [`io::Error`](std::io::Error), preserving its original
+/// [`io::ErrorKind`](std::io::ErrorKind) and OS error code when
+/// `Self::Error` is itself an `io::Error` (e.g. from a provider backed
+/// by a network call) instead of flattening it to
+/// [`io::ErrorKind::Other`](std::io::ErrorKind::Other).
+pub trait KeyProvider<A>
+where
+    A: AeadCore + KeyInit,
+{
+    /// The error type returned by this provider's operations.
+    type Error: StdError + Send + Sync + 'static;
+
+    /// Resolves `key_id` to the key it names.
+    fn resolve(&self, key_id: KeyId) -> Result<Key<A>, Self::Error>;
+
+    /// Wraps `dek` under `key_id`, for storing a per-stream data
+    /// encryption key alongside ciphertext the provider's own key never
+    /// touches directly.
+    fn wrap(&self, key_id: KeyId, dek: &Key<A>) -> Result<Vec<u8>, Self::Error>;
+
+    /// Unwraps a DEK previously sealed by [`KeyProvider::wrap`] under
+    /// `key_id`.
+    fn unwrap_dek(&self, key_id: KeyId, wrapped: &[u8]) -> Result<Key<A>, Self::Error>;
+}
@@ -0,0 +1,265 @@
+//! Positional-read decryption for sources like S3/GCS objects, where
+//! fetching one byte range costs a round trip and fetching the whole
+//! object doesn't scale: [`RangeReader`] decrypts a chunk by issuing
+//! exactly one [`ReadAt::read_at`] covering only that chunk's
+//! ciphertext, instead of reading (and discarding) everything before
+//! it the way [`Reader`](crate::Reader) would.
+//!
+//! This plays the same role as [`MmapReader`](crate::MmapReader) does
+//! for an already-addressable `&[u8]`: both use
+//! [`ChunkLayout`](crate::chunk_layout) to locate a chunk without
+//! walking the stream from the start, then decrypt it in one AEAD
+//! call. The only difference is how the sealed bytes are fetched -- a
+//! slice index for [`MmapReader`], one [`ReadAt::read_at`] call (an
+//! S3 `GetObject` with a `Range` header, say) for [`RangeReader`].
+//!
+//! # `ReadAt`
+//!
+//! [`ReadAt`] is this crate's own minimal positional-read trait,
+//! rather than a dependency on an existing one (`positioned-io`, say):
+//! it's exactly the one method [`RangeReader`] needs, so callers
+//! implement it directly against whatever fetches their object. A
+//! network-backed `read_at` can do anything a local file's `pread`
+//! can't -- retry, block on a request, return a short read for
+//! reasons that have nothing to do with reaching the source's end --
+//! which is also why `RangeReader` goes through [`read_exact_at`]
+//! rather than assuming one `read_at` call fills `buf`.
+//!
+//! # Limitations
+//!
+//! - Like [`MmapReader`], this only opens a stream written by
+//!   [`Writer::new`](crate::Writer::new) (or an equivalent plain
+//!   constructor): see [`RangeReader::open`].
+//! - There's no [`Read`](std::io::Read) impl: a positional source
+//!   rarely has a natural "current position" worth caching against,
+//!   and the point of ranged decryption is usually to fetch one
+//!   specific chunk (a video segment, a record at a known offset),
+//!   not to stream the whole object -- callers that do want the
+//!   latter are better served by [`Reader`](crate::Reader) over
+//!   whatever `Read` their object client already exposes.
+
+use std::io;
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::buf::TAG_SIZE;
+use crate::chunk_layout::ChunkLayout;
+use crate::header::{Header, HEADER_LEN};
+use crate::key_check::derive_key_check;
+use crate::nonce::{self, PREFIX_LEN};
+use crate::Error;
+
+/// A source that can be read from an arbitrary byte offset without
+/// disturbing any other position -- an S3/GCS object fetched via range
+/// GETs, a local file via `pread`, or anything else that can answer
+/// "give me up to `buf.len()` bytes starting at `offset`".
+///
+/// Unlike [`Read`](std::io::Read), `read_at` takes `&self`, not `&mut
+/// self`: positional reads share no mutable cursor state, so the same
+/// `S` can serve overlapping or out-of-order requests (as
+/// [`RangeReader::read_chunk`] does) without synchronization.
+pub trait ReadAt {
+    /// Reads into `buf`, starting at `offset`, and returns the number
+    /// of bytes read -- which may be less than `buf.len()` (a short
+    /// read, the same as [`Read::read`](std::io::Read::read)), but
+    /// `0` only at or past the source's end.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+/// Calls [`ReadAt::read_at`] in a loop until `buf` is completely
+/// filled, the same contract as
+/// [`Read::read_exact`](std::io::Read::read_exact).
+pub fn read_exact_at(s: &impl ReadAt, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match s.read_at(offset, buf)? {
+            0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "failed to fill whole buffer",
+                ))
+            }
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// [`ReadAt`]'s write-side counterpart: a destination that can be
+/// written at an arbitrary byte offset without disturbing any other
+/// position, letting [`crate::patch::rewrite_chunk_at`] patch a chunk
+/// through the same interface [`RangeReader`] reads one through,
+/// instead of serializing every access through a single shared [`Seek`]
+/// cursor the way [`crate::patch::rewrite_chunk`] does.
+///
+/// [`Seek`]: std::io::Seek
+pub trait WriteAt {
+    /// Writes `buf` starting at `offset`, returning the number of bytes
+    /// written -- which may be less than `buf.len()` (a short write,
+    /// the same as [`Write::write`](std::io::Write::write)).
+    fn write_at(&self, offset: u64, buf: &[u8]) -> io::Result<usize>;
+}
+
+/// Calls [`WriteAt::write_at`] in a loop until every byte of `buf` has
+/// been written, the same contract as
+/// [`Write::write_all`](std::io::Write::write_all).
+pub fn write_all_at(s: &impl WriteAt, mut offset: u64, mut buf: &[u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match s.write_at(offset, buf)? {
+            0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+impl ReadAt for std::fs::File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+}
+
+#[cfg(windows)]
+impl ReadAt for std::fs::File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+}
+
+#[cfg(unix)]
+impl WriteAt for std::fs::File {
+    fn write_at(&self, offset: u64, buf: &[u8]) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::write_at(self, buf, offset)
+    }
+}
+
+#[cfg(windows)]
+impl WriteAt for std::fs::File {
+    fn write_at(&self, offset: u64, buf: &[u8]) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_write(self, buf, offset)
+    }
+}
+
+/// A decryptor over a positional-read source, fetching exactly the
+/// byte range each requested chunk needs. See the module-level doc
+/// comment.
+pub struct RangeReader<S, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    s: S,
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    layout: ChunkLayout,
+}
+
+impl<S, A> RangeReader<S, A>
+where
+    S: ReadAt,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a stream for ranged decryption.
+    ///
+    /// `len` is the source's total length (an S3 object's
+    /// `Content-Length`, say): `RangeReader` has no way to discover it
+    /// itself through [`ReadAt`] alone.
+    ///
+    /// See the module-level doc comment for which headers are
+    /// rejected.
+    pub fn open(s: S, key: &Key<A>, len: u64) -> io::Result<Self> {
+        let mut header_bytes = [0u8; HEADER_LEN];
+        read_exact_at(&s, 0, &mut header_bytes)?;
+        let header = Header::decode(&header_bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if header.digest.is_some()
+            || header.padded
+            || header.derived_nonce
+            || header.compressed
+            || header.version == crate::Version::V4
+            || header.version == crate::Version::V5
+            || header.version == crate::Version::V6
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Error::InvalidHeader,
+            ));
+        }
+        if let Some(key_check) = header.key_check {
+            if derive_key_check::<A>(key, &header.nonce_prefix, &[]) != key_check {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, Error::Aead));
+            }
+        }
+        let body_len = len
+            .checked_sub(HEADER_LEN as u64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+        let layout = ChunkLayout::compute(body_len)?;
+        Ok(Self {
+            s,
+            aead: A::new(key),
+            nonce_prefix: header.nonce_prefix,
+            layout,
+        })
+    }
+
+    /// This stream's total plaintext length.
+    pub fn len(&self) -> u64 {
+        self.layout.total_len
+    }
+
+    /// Whether this stream's plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.layout.total_len == 0
+    }
+
+    /// The number of chunks (final chunk included) this stream is
+    /// sealed as.
+    pub fn chunk_count(&self) -> u64 {
+        self.layout.final_chunk_index + 1
+    }
+
+    /// Fetches and decrypts chunk `index`, issuing exactly one
+    /// [`ReadAt::read_at`] range covering that chunk's ciphertext.
+    ///
+    /// `index` must be `< self.chunk_count()`.
+    pub fn read_chunk(&self, index: u64) -> io::Result<Vec<u8>> {
+        if index > self.layout.final_chunk_index {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk index out of range",
+            ));
+        }
+        let plaintext_len = self.layout.chunk_len(index);
+        let offset = ChunkLayout::chunk_offset(index);
+        let mut sealed = vec![0u8; plaintext_len + TAG_SIZE];
+        read_exact_at(&self.s, offset, &mut sealed)?;
+        let tag: aead::Tag<A> =
+            aead::generic_array::GenericArray::clone_from_slice(&sealed[plaintext_len..]);
+        sealed.truncate(plaintext_len);
+        let last = index == self.layout.final_chunk_index;
+        let nonce = nonce::build(&self.nonce_prefix, index, last);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut sealed, &tag)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::AeadAt {
+                        chunk: index,
+                        offset,
+                    },
+                )
+            })?;
+        Ok(sealed)
+    }
+}
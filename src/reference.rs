@@ -0,0 +1,186 @@
+//! A deliberately simple, allocation-heavy reference implementation of
+//! the STREAM construction, gated behind the `reference` feature and
+//! kept independent from [`Writer`](crate::Writer)/[`Reader`](crate::Reader)
+//! so it can serve as an oracle in differential fuzzing and property
+//! tests: if the two implementations ever disagree, one of them has a
+//! bug.
+//!
+//! This module trades away everything the streaming API cares about --
+//! incremental buffering, bounded memory, zero-copy decryption -- for
+//! straightforward code that holds the whole plaintext or ciphertext in
+//! memory at once. It only implements the base framing: no digest
+//! footer, padding, key ID, or HKDF-derived nonces, since those are
+//! separable concerns layered on top of the same core construction.
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::buf::TAG_SIZE;
+use crate::header::{Header, HEADER_LEN};
+use crate::nonce::{self, PREFIX_LEN};
+use crate::{Error, Result, Version, CHUNK_SIZE};
+
+/// Seals `plaintext` as a complete stream ciphertext in one pass: a
+/// header followed by one AEAD-sealed chunk per [`CHUNK_SIZE`] bytes of
+/// plaintext, with an empty final chunk if `plaintext` is empty or ends
+/// exactly on a chunk boundary.
+pub fn encrypt<A>(plaintext: &[u8], key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> Vec<u8>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let header = Header {
+        version: Version::V1,
+        digest: None,
+        padded: false,
+        key_id: None,
+        derived_nonce: false,
+        compressed: false,
+        key_check: None,
+        nonce_prefix,
+        extensions: Vec::new(),
+        sealed_metadata: Vec::new(),
+        comment: Vec::new(),
+    };
+    let aead = A::new(key);
+    let mut out = header.encode().to_vec();
+
+    let mut chunks: Vec<&[u8]> = plaintext.chunks(CHUNK_SIZE).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let last_index = chunks.len() - 1;
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let last = i == last_index;
+        let nonce = nonce::build(&nonce_prefix, i as u64, last);
+        let mut sealed = chunk.to_vec();
+        let tag = aead
+            .encrypt_in_place_detached(&nonce, b"", &mut sealed)
+            .expect("sealing a correctly-sized chunk cannot fail");
+        sealed.extend_from_slice(&tag);
+        out.extend_from_slice(&sealed);
+    }
+    out
+}
+
+/// Like [`encrypt`], but seals each chunk on a [`rayon`] thread pool
+/// instead of one at a time, then stitches the results back together
+/// in order.
+///
+/// This is safe because each chunk's nonce only depends on its own
+/// index (see the [`nonce`](crate::nonce) module), so chunks can be
+/// sealed in any order, or concurrently, without affecting the result.
+/// Worthwhile for large plaintexts on multi-core machines; for small
+/// ones the thread pool overhead will outweigh the gains, so prefer
+/// [`encrypt`] unless `plaintext` is at least a few chunks long.
+#[cfg(feature = "parallel")]
+pub fn par_encrypt<A>(plaintext: &[u8], key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> Vec<u8>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Sync,
+{
+    use rayon::prelude::*;
+
+    let header = Header {
+        version: Version::V1,
+        digest: None,
+        padded: false,
+        key_id: None,
+        derived_nonce: false,
+        compressed: false,
+        key_check: None,
+        nonce_prefix,
+        extensions: Vec::new(),
+        sealed_metadata: Vec::new(),
+        comment: Vec::new(),
+    };
+    let aead = A::new(key);
+
+    let mut chunks: Vec<&[u8]> = plaintext.chunks(CHUNK_SIZE).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let last_index = chunks.len() - 1;
+
+    let sealed_chunks: Vec<Vec<u8>> = chunks
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let last = i == last_index;
+            let nonce = nonce::build(&nonce_prefix, i as u64, last);
+            let mut sealed = chunk.to_vec();
+            let tag = aead
+                .encrypt_in_place_detached(&nonce, b"", &mut sealed)
+                .expect("sealing a correctly-sized chunk cannot fail");
+            sealed.extend_from_slice(&tag);
+            sealed
+        })
+        .collect();
+
+    let mut out = header.encode().to_vec();
+    for sealed in sealed_chunks {
+        out.extend_from_slice(&sealed);
+    }
+    out
+}
+
+/// A parallel counterpart to [`decrypt`] -- opening disjoint chunk
+/// ranges of a ciphertext on multiple threads and reassembling them --
+/// was considered alongside [`par_encrypt`], but it needs a seek index:
+/// a footer (or side table) recording each chunk's byte offset, so a
+/// thread can jump straight to chunk `i` without decrypting everything
+/// before it. No such index exists anywhere in this crate. The only
+/// footers [`Header`] and the rest of this crate know how to produce
+/// are the digest footer (`digest.rs`) and the padding footer
+/// (`padding.rs`), and [`Reader`](crate::Reader) only implements
+/// sequential [`std::io::Read`], with no `Seek` bound or chunk-offset
+/// bookkeeping to build one on top of. Decryption also can't be
+/// parallelized the way [`par_encrypt`] parallelizes sealing: opening
+/// chunk `i` needs its ciphertext's byte offset, which today is only
+/// knowable by walking every preceding chunk in order. Adding a seek
+/// index would be a wire-format change worth its own request, not a
+/// rider on this one.
+///
+/// Opens a stream ciphertext produced by [`encrypt`] (or by
+/// [`Writer::new`](crate::Writer::new)), returning the decrypted
+/// plaintext.
+pub fn decrypt<A>(ciphertext: &[u8], key: &Key<A>) -> Result<Vec<u8>>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    if ciphertext.len() < HEADER_LEN {
+        return Err(Error::InvalidHeader);
+    }
+    let mut header_buf = [0u8; HEADER_LEN];
+    header_buf.copy_from_slice(&ciphertext[..HEADER_LEN]);
+    let header = Header::decode(&header_buf)?;
+
+    let aead = A::new(key);
+    let body = &ciphertext[HEADER_LEN..];
+    let chunk_len = CHUNK_SIZE + TAG_SIZE;
+    let mut plaintext = Vec::new();
+    let mut counter = 0u64;
+    let mut pos = 0;
+    loop {
+        let remaining = &body[pos..];
+        let last = remaining.len() <= chunk_len;
+        let this_len = remaining.len().min(chunk_len);
+        if this_len < TAG_SIZE {
+            return Err(Error::InvalidHeader);
+        }
+        let sealed_len = this_len - TAG_SIZE;
+
+        let nonce = nonce::build(&header.nonce_prefix, counter, last);
+        let mut chunk = remaining[..sealed_len].to_vec();
+        let tag: aead::Tag<A> =
+            aead::generic_array::GenericArray::clone_from_slice(&remaining[sealed_len..this_len]);
+        aead.decrypt_in_place_detached(&nonce, b"", &mut chunk, &tag)
+            .map_err(|_| Error::Aead)?;
+        plaintext.extend_from_slice(&chunk);
+
+        pos += this_len;
+        counter = counter.checked_add(1).ok_or(Error::NonceOverflow)?;
+        if last {
+            break;
+        }
+    }
+    Ok(plaintext)
+}
@@ -0,0 +1,127 @@
+//! Streaming re-encryption: rewrapping a ciphertext under a new key (and
+//! nonce prefix) without ever holding more than one chunk of plaintext
+//! in memory.
+//!
+//! [`reencrypt`] decrypts one chunk at a time from a [`Reader`] and
+//! immediately seals it into a [`Writer`] under the new key, reusing a
+//! single chunk-sized buffer for the whole stream. Useful for key
+//! rotation jobs over archives too large to decrypt to a temporary
+//! buffer and re-encrypt in a second pass.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::nonce::PREFIX_LEN;
+use crate::{Reader, Writer, CHUNK_SIZE};
+
+/// Decrypts `src` under `old_key` and re-encrypts the result into `dst`
+/// under `new_key` and `new_nonce_prefix`, chunk by chunk.
+///
+/// `new_nonce_prefix` must be unique for every stream encrypted under
+/// `new_key`, the same as for [`Writer::new`]. Returns `dst` once the
+/// whole of `src` has been rewrapped.
+pub fn reencrypt<R, W, A>(
+    src: R,
+    old_key: &Key<A>,
+    dst: W,
+    new_key: &Key<A>,
+    new_nonce_prefix: [u8; PREFIX_LEN],
+) -> io::Result<W>
+where
+    R: Read,
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let (_reader, writer) =
+        reencrypt_inner::<R, W, A>(src, old_key, dst, new_key, new_nonce_prefix)?;
+    writer.finish()
+}
+
+/// Like [`reencrypt`], but also returns [`Stats`](crate::stats::Stats)
+/// for the rewrap: `chunks`/`bytes_in` are the source stream's, `bytes_out`
+/// and `auth_failures` are carried over from decrypting it, and `rekeys`
+/// is set to 1. See the [`stats`](crate::stats) module.
+#[cfg(feature = "stats")]
+pub fn reencrypt_with_stats<R, W, A>(
+    src: R,
+    old_key: &Key<A>,
+    dst: W,
+    new_key: &Key<A>,
+    new_nonce_prefix: [u8; PREFIX_LEN],
+) -> io::Result<(W, crate::stats::Stats)>
+where
+    R: Read,
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let (reader, writer) =
+        reencrypt_inner::<R, W, A>(src, old_key, dst, new_key, new_nonce_prefix)?;
+    let reader_stats = reader.stats();
+    let writer_stats = writer.stats();
+    let dst = writer.finish()?;
+    Ok((
+        dst,
+        crate::stats::Stats {
+            chunks: writer_stats.chunks,
+            bytes_in: reader_stats.bytes_in,
+            bytes_out: writer_stats.bytes_out,
+            auth_failures: reader_stats.auth_failures,
+            rekeys: 1,
+        },
+    ))
+}
+
+/// The shared body of [`reencrypt`] and [`reencrypt_with_stats`]: reads
+/// `src` through a freshly opened [`Reader`] and writes the plaintext
+/// straight into a freshly opened [`Writer`], chunk by chunk, leaving
+/// both open so either caller can pull whatever it needs (a finished
+/// `W`, or also a [`Stats`](crate::stats::Stats) snapshot) out of them.
+fn reencrypt_inner<R, W, A>(
+    src: R,
+    old_key: &Key<A>,
+    dst: W,
+    new_key: &Key<A>,
+    new_nonce_prefix: [u8; PREFIX_LEN],
+) -> io::Result<(Reader<R, A>, Writer<W, A>)>
+where
+    R: Read,
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    #[cfg(feature = "tracing")]
+    tracing::info!("starting stream re-encryption");
+    let mut reader = Reader::<R, A>::new(src, old_key)?;
+    let mut writer = Writer::<W, A>::new(dst, new_key, new_nonce_prefix)?;
+    // Heap-allocated rather than a `[0u8; CHUNK_SIZE]` stack array: with
+    // the `large_chunks` feature enabled, `CHUNK_SIZE` is multiple
+    // megabytes, comfortably past the stack a thread is normally given.
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    #[cfg(feature = "tracing")]
+    let mut total = 0u64;
+    loop {
+        #[cfg_attr(not(feature = "tracing"), allow(clippy::map_identity))]
+        let n = reader.read(&mut buf).map_err(|e| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(total, error = %e, "re-encryption failed reading source stream");
+            e
+        })?;
+        if n == 0 {
+            break;
+        }
+        #[cfg_attr(not(feature = "tracing"), allow(clippy::map_identity))]
+        writer.write_all(&buf[..n]).map_err(|e| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(total, error = %e, "re-encryption failed writing destination stream");
+            e
+        })?;
+        #[cfg(feature = "tracing")]
+        {
+            total += n as u64;
+        }
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!(total, "finished stream re-encryption");
+    Ok((reader, writer))
+}
@@ -0,0 +1,82 @@
+//! Forward scanning for the next chunk boundary after byte-alignment
+//! loss, e.g. a bad disk sector that drops or duplicates bytes rather
+//! than merely flipping a few.
+//!
+//! This crate's chunk framing -- here, in [`aead_stream`](crate::aead_stream),
+//! in [`tink`](crate::tink), and in [`age`](crate::age) alike -- is
+//! fixed-size rather than length-prefixed: nothing on the wire records
+//! how long a chunk is, so there's no length field for corruption to
+//! desync. A chunk whose ciphertext bytes are merely flipped still sits
+//! at its expected offset, which is exactly why
+//! [`Reader::with_recovery_mode`] can substitute a gap marker for it and
+//! keep going without searching for anything. But if the damage itself
+//! changes the byte count -- a dropped run of bytes, a duplicated one --
+//! every chunk after the damage lands at the wrong offset, and no amount
+//! of retrying the expected offset will authenticate. [`find_chunk_boundary`]
+//! is for that case: it brute-force tries successive offsets until one
+//! produces a chunk that authenticates.
+//!
+//! [`Reader::with_recovery_mode`]: crate::Reader::with_recovery_mode
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::generic_array::GenericArray;
+use aead::AeadInPlace;
+
+use crate::buf::TAG_SIZE;
+
+/// Searches `ciphertext[start..]` for the next byte offset at which a
+/// chunk no longer than `chunk_ciphertext_len` bytes decrypts and
+/// authenticates under `aead` and `nonce`, trying every offset in turn
+/// until one verifies or `max_scan` candidate offsets have been tried.
+///
+/// This is a brute-force search -- one AEAD call per candidate offset --
+/// so `max_scan` bounds the work a very damaged (or adversarial) file
+/// can force; callers recovering from a known-bounded loss (e.g. "at
+/// most one 4 KiB sector") should pass a `max_scan` sized accordingly
+/// rather than scanning an entire archive byte by byte.
+///
+/// `nonce` must be built for the specific chunk (counter and
+/// final-chunk flag) the caller expects to find next; since this
+/// crate's nonces never depend on ciphertext position, the same nonce
+/// is reused for every candidate offset tried. `aad` is the associated
+/// data every candidate is checked against -- the stream's
+/// [`Version::V6`](crate::Version::V6) comment, or empty for every
+/// other version.
+///
+/// Returns the offset (relative to the start of `ciphertext`, not
+/// `start`) of the first chunk boundary found, or `None` if none
+/// verified within the scan window.
+pub fn find_chunk_boundary<A>(
+    ciphertext: &[u8],
+    start: usize,
+    chunk_ciphertext_len: usize,
+    aead: &A,
+    nonce: &GenericArray<u8, U12>,
+    aad: &[u8],
+    max_scan: usize,
+) -> Option<usize>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    let scan_end = ciphertext.len().min(start.saturating_add(max_scan));
+    let mut candidate = Vec::new();
+    for offset in start..scan_end {
+        let window_len = chunk_ciphertext_len.min(ciphertext.len() - offset);
+        if window_len < TAG_SIZE {
+            break;
+        }
+        let plaintext_len = window_len - TAG_SIZE;
+        candidate.clear();
+        candidate.extend_from_slice(&ciphertext[offset..offset + plaintext_len]);
+        let tag: aead::Tag<A> = GenericArray::clone_from_slice(
+            &ciphertext[offset + plaintext_len..offset + window_len],
+        );
+        if aead
+            .decrypt_in_place_detached(nonce, aad, &mut candidate, &tag)
+            .is_ok()
+        {
+            return Some(offset);
+        }
+    }
+    None
+}
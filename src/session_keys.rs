@@ -0,0 +1,94 @@
+//! Deriving a stream key and `nonce_prefix` from a secret a Noise or TLS
+//! session already exported, so this crate can serve as the record
+//! layer for the traffic that follows an interactive handshake instead
+//! of only ever being handed a pre-shared key.
+//!
+//! Noise's `Split()` and TLS 1.3's exporter interface both hand a
+//! finished handshake's secrets to the application as opaque bytes;
+//! neither one is an AEAD key plus a [`Writer::new`](crate::Writer::new)
+//! `nonce_prefix` by itself. [`derive_session_key`] bridges the two with
+//! a single HKDF-SHA256 expansion, the same way
+//! [`convergent_nonce_prefix`](crate::convergent_nonce_prefix) derives a
+//! prefix from a key and plaintext instead of drawing one from an RNG:
+//! here the *key* itself is derived too, from whatever secret the
+//! handshake exported.
+//!
+//! A duplex channel built on one exported secret needs two independent
+//! keys, one per direction -- without that, both peers would seal
+//! chunks under the same key and the same `(nonce_prefix, counter)`
+//! space, and a chunk counter that's supposed to be unique per stream
+//! would instead be shared by two streams sealing different data. This
+//! module doesn't hardcode a notion of "client" or "server" to tell the
+//! directions apart; instead `info` is whatever label the two peers
+//! already agree on out of band (a Noise handshake hash plus a
+//! direction tag, or a TLS exporter label), and the caller is
+//! responsible for using a different `info` for each direction. Calling
+//! [`derive_session_key`] twice with the same `exported_secret` and two
+//! distinct `info` values gives each direction its own key and prefix;
+//! calling it with the same `info` on both ends of one direction gives
+//! both peers the matching pair without either one transmitting it.
+
+use aead::{AeadCore, Key, KeyInit};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::nonce::PREFIX_LEN;
+
+/// Derives an AEAD key and `nonce_prefix` from `exported_secret` (a
+/// Noise or TLS session's exporter output) and `info` (a label the two
+/// peers agree on, made direction-specific by the caller -- see the
+/// module-level doc comment), via HKDF-SHA256.
+///
+/// Pass the result straight to [`Writer::new`](crate::Writer::new) or
+/// [`Reader::new`](crate::Reader::new): the peer deriving the matching
+/// side of the channel gets the same key and prefix from the same
+/// `exported_secret` and `info`, so neither needs to travel over the
+/// wire.
+pub fn derive_session_key<A>(exported_secret: &[u8], info: &[u8]) -> (Key<A>, [u8; PREFIX_LEN])
+where
+    A: AeadCore + KeyInit,
+{
+    let hk = Hkdf::<Sha256>::new(None, exported_secret);
+    let mut key = Key::<A>::default();
+    let mut prefix = [0u8; PREFIX_LEN];
+
+    let mut okm = vec![0u8; key.len() + PREFIX_LEN];
+    hk.expand(info, &mut okm)
+        .expect("a key plus a nonce prefix is well within HKDF-SHA256's output size limit");
+    let (key_bytes, prefix_bytes) = okm.split_at(key.len());
+    key.copy_from_slice(key_bytes);
+    prefix.copy_from_slice(prefix_bytes);
+
+    (key, prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    use super::derive_session_key;
+
+    #[test]
+    fn identical_inputs_derive_identical_keys_and_prefixes() {
+        let secret = [0x42u8; 32];
+        let a = derive_session_key::<ChaCha20Poly1305>(&secret, b"c2s");
+        let b = derive_session_key::<ChaCha20Poly1305>(&secret, b"c2s");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_info_derives_different_keys_and_prefixes() {
+        let secret = [0x42u8; 32];
+        let c2s = derive_session_key::<ChaCha20Poly1305>(&secret, b"client to server");
+        let s2c = derive_session_key::<ChaCha20Poly1305>(&secret, b"server to client");
+        assert_ne!(c2s, s2c);
+    }
+
+    #[test]
+    fn different_exported_secrets_derive_different_keys_and_prefixes() {
+        let info = b"client to server";
+        let a = derive_session_key::<ChaCha20Poly1305>(&[0x42u8; 32], info);
+        let b = derive_session_key::<ChaCha20Poly1305>(&[0x24u8; 32], info);
+        assert_ne!(a, b);
+    }
+}
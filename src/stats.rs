@@ -0,0 +1,39 @@
+//! Cheap accounting counters for [`Reader`](crate::Reader) and
+//! [`Writer`](crate::Writer), gated behind the `stats` feature so
+//! streams that don't care about them don't pay for the extra
+//! bookkeeping.
+//!
+//! Call [`Writer::stats`](crate::Writer::stats) or
+//! [`Reader::stats`](crate::Reader::stats) for a snapshot and feed its
+//! fields into whatever metrics backend a long-running service already
+//! reports through -- one Prometheus counter per field, say.
+
+/// A snapshot of the chunk- and byte-level counters a stream has
+/// accumulated so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Chunks sealed (by a [`Writer`](crate::Writer)) or decrypted (by
+    /// a [`Reader`](crate::Reader)) so far.
+    pub chunks: u64,
+    /// Bytes read from the caller or the wire: plaintext handed to a
+    /// `Writer` through [`Write`](std::io::Write), or ciphertext a
+    /// `Reader` has consumed from its underlying reader (header
+    /// excluded).
+    pub bytes_in: u64,
+    /// Bytes handed onward: ciphertext a `Writer` has written to its
+    /// underlying writer (header excluded), or plaintext a `Reader` has
+    /// returned to its caller.
+    pub bytes_out: u64,
+    /// Chunk authentication failures seen so far. Always 0 for a
+    /// `Writer`, which never decrypts; for a `Reader`, rarely more than
+    /// 1 in practice, since most callers stop reading once a chunk
+    /// fails to authenticate.
+    pub auth_failures: u64,
+    /// Streams rewrapped under a new key via
+    /// [`reencrypt_with_stats`](crate::rekey::reencrypt_with_stats).
+    /// Always 0 from [`Reader::stats`](crate::Reader::stats) and
+    /// [`Writer::stats`](crate::Writer::stats) directly: rekeying is an
+    /// operation on a `Reader`/`Writer` pair, not a property either one
+    /// tracks about itself.
+    pub rekeys: u64,
+}
@@ -0,0 +1,219 @@
+//! Fault-injection IO wrappers, gated behind the `testing` feature so
+//! these debugging aids don't ship in the default build. Each wraps an
+//! inner reader or writer and deterministically misbehaves the way
+//! real IO does under load, so an application built on this crate can
+//! test its own error handling without hand-rolling the same shims
+//! its test suite already does for [`Reader`](crate::Reader) and
+//! [`Writer`](crate::Writer) themselves.
+
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Wraps a [`Read`]er, returning at most `max_read` bytes per call and
+/// failing every `interrupt_every`th call with
+/// [`ErrorKind::Interrupted`], exercising the same short-read-and-retry
+/// loop a flaky socket or pipe would.
+pub struct FlakyReader<R> {
+    inner: R,
+    max_read: usize,
+    interrupt_every: usize,
+    calls: usize,
+}
+
+impl<R: Read> FlakyReader<R> {
+    /// A `max_read` of `0` means no cap; an `interrupt_every` of `0`
+    /// disables interrupts entirely.
+    pub fn new(inner: R, max_read: usize, interrupt_every: usize) -> Self {
+        Self {
+            inner,
+            max_read,
+            interrupt_every,
+            calls: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for FlakyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.calls += 1;
+        if self.interrupt_every != 0 && self.calls.is_multiple_of(self.interrupt_every) {
+            return Err(io::Error::from(ErrorKind::Interrupted));
+        }
+        let cap = if self.max_read == 0 {
+            buf.len()
+        } else {
+            buf.len().min(self.max_read)
+        };
+        self.inner.read(&mut buf[..cap])
+    }
+}
+
+/// Wraps a [`Write`]r, accepting at most `max_write` bytes per call no
+/// matter how much the caller offers, exercising the same
+/// partial-write handling a full pipe or throttled socket would.
+pub struct ShortWriter<W> {
+    inner: W,
+    max_write: usize,
+}
+
+impl<W: Write> ShortWriter<W> {
+    /// A `max_write` of `0` means no cap.
+    pub fn new(inner: W, max_write: usize) -> Self {
+        Self { inner, max_write }
+    }
+
+    /// Unwraps this writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for ShortWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let cap = if self.max_write == 0 {
+            buf.len()
+        } else {
+            buf.len().min(self.max_write)
+        };
+        self.inner.write(&buf[..cap])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps a [`Write`]r, failing every `block_every`th call with
+/// [`ErrorKind::WouldBlock`] instead of writing anything, exercising
+/// the retry loop a non-blocking socket's caller has to run.
+pub struct FlakyWriter<W> {
+    inner: W,
+    block_every: usize,
+    calls: usize,
+}
+
+impl<W: Write> FlakyWriter<W> {
+    /// A `block_every` of `0` disables blocking entirely.
+    pub fn new(inner: W, block_every: usize) -> Self {
+        Self {
+            inner,
+            block_every,
+            calls: 0,
+        }
+    }
+
+    /// Unwraps this writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for FlakyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        if self.block_every != 0 && self.calls.is_multiple_of(self.block_every) {
+            return Err(io::Error::from(ErrorKind::WouldBlock));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps a [`Write`]r, forwarding the first call up to `skip` times
+/// (for e.g. a header write a caller needs to land in full before the
+/// interesting part of a test begins), then on the very next call
+/// accepts only up to `partial` bytes -- a genuine short write, not a
+/// failure -- and for the `blocks` calls after *that* fails with
+/// [`ErrorKind::WouldBlock`] before any more bytes reach `inner`. Every
+/// call after that passes straight through. Unlike [`FlakyWriter`],
+/// which fails periodically for as long as it's used, this fails a
+/// fixed number of times starting at a call number the caller picks up
+/// front, so a test can pin down precisely which write partially lands
+/// before the [`WouldBlock`](ErrorKind::WouldBlock)s hit -- including
+/// more than one in a row, to cover a caller that resumes a stalled
+/// write only to have the resumed write itself block again -- instead
+/// of racing a recurring fault against whatever the buffering happens
+/// to do.
+pub struct StallingWriter<W> {
+    inner: W,
+    skip: usize,
+    partial: usize,
+    blocks: usize,
+    calls: usize,
+}
+
+impl<W: Write> StallingWriter<W> {
+    pub fn new(inner: W, skip: usize, partial: usize, blocks: usize) -> Self {
+        Self {
+            inner,
+            skip,
+            partial,
+            blocks,
+            calls: 0,
+        }
+    }
+
+    /// Unwraps this writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for StallingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        if self.calls <= self.skip {
+            return self.inner.write(buf);
+        }
+        match self.calls - self.skip {
+            1 => {
+                let cap = buf.len().min(self.partial);
+                self.inner.write(&buf[..cap])
+            }
+            n if n <= 1 + self.blocks => Err(io::Error::from(ErrorKind::WouldBlock)),
+            _ => self.inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps a [`Read`]er, flipping one bit every `corrupt_every` bytes
+/// read, for testing that a caller surfaces the resulting AEAD failure
+/// from [`Reader`](crate::Reader) instead of trusting corrupted
+/// plaintext.
+pub struct CorruptingReader<R> {
+    inner: R,
+    corrupt_every: usize,
+    bytes_read: usize,
+}
+
+impl<R: Read> CorruptingReader<R> {
+    /// A `corrupt_every` of `0` disables corruption entirely.
+    pub fn new(inner: R, corrupt_every: usize) -> Self {
+        Self {
+            inner,
+            corrupt_every,
+            bytes_read: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for CorruptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if self.corrupt_every != 0 {
+            for byte in &mut buf[..n] {
+                self.bytes_read += 1;
+                if self.bytes_read.is_multiple_of(self.corrupt_every) {
+                    *byte ^= 0x01;
+                }
+            }
+        }
+        Ok(n)
+    }
+}
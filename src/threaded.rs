@@ -0,0 +1,266 @@
+//! Background-thread pipelined [`Writer`] and [`Reader`] wrappers,
+//! gated behind the `threaded` feature, for callers bottlenecked on a
+//! single core alternating between memcpy, AEAD operations, and IO
+//! syscalls.
+//!
+//! [`ThreadedWriter`] hands each call's bytes off to a worker thread
+//! over a bounded channel; the worker owns the real [`Writer`] and does
+//! the sealing and the underlying writes, so the caller's thread is
+//! free to prepare (or produce) the next chunk of plaintext while the
+//! worker is still busy with the last one. [`ThreadedReader`] is the
+//! mirror image: its worker owns the real [`Reader`], reads and
+//! decrypts ahead of the caller, and hands finished plaintext chunks
+//! back over a bounded channel, hiding the underlying storage's
+//! latency behind whatever the caller is doing with each chunk. In
+//! both cases the channel's bound caps how far the two threads can get
+//! out of step, the same way a bounded pipe caps how much unread data
+//! a fast producer can pile up.
+//!
+//! A stream sealed by [`ThreadedWriter`] is byte-for-byte identical to
+//! one sealed by a plain [`Writer`], and [`ThreadedReader`] opens
+//! anything a plain [`Reader`] can; pipelining is purely a
+//! caller-side, single-process performance choice, not a wire-format
+//! one.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+
+use crate::nonce::PREFIX_LEN;
+use crate::{Reader, Writer, CHUNK_SIZE};
+
+/// How many chunks the caller may get ahead of (for a [`ThreadedWriter`])
+/// or behind (for a [`ThreadedReader`]) the worker thread before the
+/// bounded channel between them blocks.
+const CHANNEL_DEPTH: usize = 2;
+
+/// Seals a plaintext as a sequence of AEAD-authenticated chunks on a
+/// background thread, overlapping the caller's IO (or plaintext
+/// production) with the worker's sealing and writes.
+///
+/// `A` carries the same bound as [`Writer`], plus `Send + 'static` so
+/// it can be moved onto the worker thread; `W` needs the same for the
+/// same reason.
+pub struct ThreadedWriter<W, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    W: Write + Send + 'static,
+{
+    /// `None` once [`ThreadedWriter::finish`] has signalled the worker
+    /// that no more chunks are coming.
+    tx: Option<SyncSender<Vec<u8>>>,
+    /// `None` once the worker thread has been joined.
+    handle: Option<JoinHandle<io::Result<W>>>,
+    /// Set by the worker as soon as a write fails, so `write` can report
+    /// it on the caller's next call instead of only at `finish`.
+    error: Arc<Mutex<Option<io::Error>>>,
+    _aead: PhantomData<A>,
+}
+
+impl<W, A> ThreadedWriter<W, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    W: Write + Send + 'static,
+{
+    /// Starts a new stream, writing its header to `w` immediately from
+    /// the calling thread, then spawning the worker that will seal and
+    /// write every chunk after it.
+    ///
+    /// See [`Writer::new`] for `nonce_prefix`'s requirements.
+    pub fn new(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        let mut writer = Writer::<W, A>::new(w, key, nonce_prefix)?;
+        let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(CHANNEL_DEPTH);
+        let error = Arc::new(Mutex::new(None));
+        let worker_error = Arc::clone(&error);
+        let handle = thread::spawn(move || -> io::Result<W> {
+            while let Ok(chunk) = rx.recv() {
+                if let Err(e) = writer.write_all(&chunk) {
+                    *worker_error.lock().unwrap() = Some(io::Error::new(e.kind(), e.to_string()));
+                    return Err(e);
+                }
+            }
+            writer.finish()
+        });
+        Ok(Self {
+            tx: Some(tx),
+            handle: Some(handle),
+            error,
+            _aead: PhantomData,
+        })
+    }
+
+    /// Signals the worker that no more plaintext is coming, waits for it
+    /// to seal and write the final chunk, then returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.tx.take();
+        self.handle
+            .take()
+            .expect("finish called once")
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("background writer thread panicked")))
+    }
+}
+
+impl<W, A> Write for ThreadedWriter<W, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    W: Write + Send + 'static,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Some(e) = self.error.lock().unwrap().take() {
+            return Err(e);
+        }
+        let tx = self.tx.as_ref().expect("write called after finish");
+        match tx.send(data.to_vec()) {
+            Ok(()) => Ok(data.len()),
+            Err(_) => Err(self.error.lock().unwrap().take().unwrap_or_else(|| {
+                io::Error::other("background writer thread exited unexpectedly")
+            })),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Nothing buffered on the caller's side to flush; the worker
+        // flushes the underlying writer itself whenever it completes a
+        // chunk.
+        Ok(())
+    }
+}
+
+impl<W, A> Drop for ThreadedWriter<W, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    W: Write + Send + 'static,
+{
+    fn drop(&mut self) {
+        // Dropping `tx` (if `finish` wasn't called) unblocks the
+        // worker's `recv` loop so the thread can exit instead of
+        // leaking; any in-flight error or the finished writer itself is
+        // simply discarded, since there's no `self` left to return it
+        // to.
+        self.tx.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Decrypts a stream's chunks on a background thread, read-ahead of the
+/// caller, overlapping the worker's decryption (and the underlying
+/// reader's IO) with whatever the caller does with each chunk.
+///
+/// `A` and `R` carry the same `Send + 'static` bounds as
+/// [`ThreadedWriter`]'s, for the same reason.
+pub struct ThreadedReader<R, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    R: Read + Send + 'static,
+{
+    /// `None` once the worker has reported EOF or an error and that
+    /// outcome has already been returned from `read`.
+    rx: Option<Receiver<io::Result<Vec<u8>>>>,
+    handle: Option<JoinHandle<()>>,
+    /// Plaintext from the most recently received chunk, not yet fully
+    /// returned to the caller.
+    buf: Vec<u8>,
+    pos: usize,
+    _aead: PhantomData<A>,
+    _reader: PhantomData<R>,
+}
+
+impl<R, A> ThreadedReader<R, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    R: Read + Send + 'static,
+{
+    /// Opens a stream, reading and validating its header from `r` on
+    /// the calling thread, then spawning the worker that will read
+    /// ahead and decrypt every chunk after it.
+    pub fn new(r: R, key: &Key<A>) -> io::Result<Self> {
+        let mut reader = Reader::<R, A>::new(r, key)?;
+        let (tx, rx) = mpsc::sync_channel::<io::Result<Vec<u8>>>(CHANNEL_DEPTH);
+        let handle = thread::spawn(move || {
+            let mut chunk = vec![0u8; CHUNK_SIZE];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.send(Ok(chunk[..n].to_vec())).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            rx: Some(rx),
+            handle: Some(handle),
+            buf: Vec::new(),
+            pos: 0,
+            _aead: PhantomData,
+            _reader: PhantomData,
+        })
+    }
+}
+
+impl<R, A> Read for ThreadedReader<R, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    R: Read + Send + 'static,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.buf.len() {
+            let Some(rx) = &self.rx else {
+                return Ok(0);
+            };
+            match rx.recv() {
+                Ok(Ok(chunk)) => {
+                    self.buf = chunk;
+                    self.pos = 0;
+                }
+                Ok(Err(e)) => {
+                    self.rx = None;
+                    return Err(e);
+                }
+                Err(_) => {
+                    // Worker exited after reporting EOF; nothing left.
+                    self.rx = None;
+                    self.buf.clear();
+                    self.pos = 0;
+                    return Ok(0);
+                }
+            }
+        }
+        let avail = &self.buf[self.pos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<R, A> Drop for ThreadedReader<R, A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit + Send + 'static,
+    R: Read + Send + 'static,
+{
+    fn drop(&mut self) {
+        // Dropping `rx` unblocks the worker's next `send` so it can
+        // exit instead of leaking, even if the caller stopped reading
+        // before EOF.
+        self.rx.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
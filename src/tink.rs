@@ -0,0 +1,314 @@
+//! Compatibility with Google Tink's HKDF-based streaming AEAD framing
+//! (`AesGcmHkdfStreaming` and its ChaCha20-Poly1305 counterpart).
+//!
+//! Tink's streaming format differs from this crate's native framing in
+//! a few ways:
+//!
+//! - The header carries a random salt (as long as the AEAD's key)
+//!   instead of a key ID, followed by a 7-byte nonce prefix.
+//! - Every segment's key is freshly derived via HKDF-SHA256 from the
+//!   stream's input keying material, salted by that header salt and
+//!   bound to caller-supplied associated data (rather than the AEAD
+//!   key being used directly, and rather than per-chunk associated
+//!   data).
+//! - Segments are fixed-size on the wire, including the header: the
+//!   first segment's plaintext capacity is shrunk by the header's
+//!   length so that every segment after it lands on the same
+//!   `segment_size` boundary.
+//! - The per-segment nonce is a 7-byte prefix, a 4-byte big-endian
+//!   segment counter, and a 1-byte final-segment flag, rather than this
+//!   crate's 4/7/1 split.
+//!
+//! This module implements that framing closely enough to round-trip
+//! with itself and to match Tink's documented format, but it hasn't
+//! been checked against Tink's own cross-language test vectors.
+//! Treat it as best-effort interop rather than a conformance guarantee.
+
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, Key, KeyInit};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::Error;
+
+/// Tink's default streaming segment size, in bytes (plaintext plus the
+/// 16-byte authentication tag).
+pub const DEFAULT_SEGMENT_SIZE: usize = 4096;
+
+/// The length, in bytes, of the random per-ciphertext nonce prefix.
+const NONCE_PREFIX_LEN: usize = 7;
+
+/// The length, in bytes, of the big-endian segment counter packed into
+/// the nonce.
+const COUNTER_LEN: usize = 4;
+
+/// The length, in bytes, of an AEAD authentication tag.
+const TAG_SIZE: usize = 16;
+
+const _: () = assert!(NONCE_PREFIX_LEN + COUNTER_LEN + 1 == 12);
+
+/// Derives the per-ciphertext segment key: `HKDF-Expand(HKDF-Extract(salt,
+/// ikm), aad)`, truncated to `A`'s key length.
+fn derive_key<A: KeyInit>(ikm: &[u8], salt: &[u8], aad: &[u8]) -> io::Result<Key<A>> {
+    let hk = Hkdf::<Sha256>::new(Some(salt), ikm);
+    let mut key = Key::<A>::default();
+    hk.expand(aad, &mut key)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::InvalidHeader))?;
+    Ok(key)
+}
+
+/// Builds the 96-bit nonce for segment `counter` of a ciphertext whose
+/// random prefix is `prefix`. `last` must be `true` only for the final
+/// segment.
+fn segment_nonce(
+    prefix: &[u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    last: bool,
+) -> GenericArray<u8, U12> {
+    let mut nonce = GenericArray::<u8, U12>::default();
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_PREFIX_LEN + COUNTER_LEN].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_PREFIX_LEN + COUNTER_LEN] = last as u8;
+    nonce
+}
+
+/// Seals a plaintext into Tink's HKDF streaming AEAD format.
+pub struct TinkWriter<W, A> {
+    w: W,
+    aead: A,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    first_seg_cap: usize,
+    seg_cap: usize,
+    first: bool,
+    buf: Vec<u8>,
+}
+
+impl<W, A> TinkWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Starts a new Tink-compatible ciphertext, writing its header to
+    /// `w` immediately.
+    ///
+    /// `salt` must be as long as `A`'s key and, like `nonce_prefix`,
+    /// must be fresh for every ciphertext sealed under `ikm`; both
+    /// should come from a cryptographically secure RNG. `aad`
+    /// associates the ciphertext with caller-chosen context (e.g. a
+    /// file name); the same bytes must be passed to
+    /// [`TinkReader::new`] to open it.
+    pub fn new(
+        mut w: W,
+        ikm: &[u8],
+        salt: &[u8],
+        nonce_prefix: [u8; NONCE_PREFIX_LEN],
+        aad: &[u8],
+        segment_size: usize,
+    ) -> io::Result<Self> {
+        let key = derive_key::<A>(ikm, salt, aad)?;
+        let header_len = 1 + salt.len() + NONCE_PREFIX_LEN;
+        if header_len > u8::MAX as usize || segment_size <= header_len + TAG_SIZE {
+            return Err(io::Error::other(Error::InvalidHeader));
+        }
+        let mut header = Vec::with_capacity(header_len);
+        header.push(header_len as u8);
+        header.extend_from_slice(salt);
+        header.extend_from_slice(&nonce_prefix);
+        w.write_all(&header)?;
+        Ok(Self {
+            w,
+            aead: A::new(&key),
+            nonce_prefix,
+            counter: 0,
+            first_seg_cap: segment_size - header_len - TAG_SIZE,
+            seg_cap: segment_size - TAG_SIZE,
+            first: true,
+            buf: Vec::with_capacity(segment_size),
+        })
+    }
+
+    fn cap(&self) -> usize {
+        if self.first {
+            self.first_seg_cap
+        } else {
+            self.seg_cap
+        }
+    }
+
+    fn flush_segment(&mut self, last: bool) -> io::Result<()> {
+        let nonce = segment_nonce(&self.nonce_prefix, self.counter, last);
+        let mut segment = std::mem::take(&mut self.buf);
+        let tag = self
+            .aead
+            .encrypt_in_place_detached(&nonce, b"", &mut segment)
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        segment.extend_from_slice(&tag);
+        self.w.write_all(&segment)?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::other(Error::NonceOverflow))?;
+        self.first = false;
+        Ok(())
+    }
+
+    /// Finishes the ciphertext: seals any buffered plaintext as the
+    /// final segment, then returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_segment(true)?;
+        Ok(self.w)
+    }
+}
+
+impl<W, A> Write for TinkWriter<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let total = data.len();
+        let mut data = data;
+        while !data.is_empty() {
+            let room = self.cap() - self.buf.len();
+            let n = data.len().min(room);
+            self.buf.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.buf.len() == self.cap() {
+                self.flush_segment(false)?;
+            }
+        }
+        Ok(total)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Opens a ciphertext sealed by [`TinkWriter`].
+pub struct TinkReader<R, A> {
+    r: R,
+    aead: A,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    first_seg_cap: usize,
+    seg_cap: usize,
+    first: bool,
+    cbuf: Vec<u8>,
+    pbuf: Vec<u8>,
+    ppos: usize,
+    done: bool,
+}
+
+impl<R, A> TinkReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Opens a ciphertext, reading and validating its header from `r`.
+    ///
+    /// `aad` must match the associated data passed to
+    /// [`TinkWriter::new`].
+    pub fn new(mut r: R, ikm: &[u8], aad: &[u8], segment_size: usize) -> io::Result<Self> {
+        let mut len_byte = [0u8; 1];
+        r.read_exact(&mut len_byte)?;
+        let header_len = len_byte[0] as usize;
+        let key_len = Key::<A>::default().len();
+        if header_len != 1 + key_len + NONCE_PREFIX_LEN || segment_size <= header_len + TAG_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::InvalidHeader,
+            ));
+        }
+        let mut rest = vec![0u8; header_len - 1];
+        r.read_exact(&mut rest)?;
+        let (salt, prefix) = rest.split_at(key_len);
+        let key = derive_key::<A>(ikm, salt, aad)?;
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(prefix);
+        Ok(Self {
+            r,
+            aead: A::new(&key),
+            nonce_prefix,
+            counter: 0,
+            first_seg_cap: segment_size - header_len - TAG_SIZE,
+            seg_cap: segment_size - TAG_SIZE,
+            first: true,
+            cbuf: Vec::new(),
+            pbuf: Vec::new(),
+            ppos: 0,
+            done: false,
+        })
+    }
+
+    fn advance(&mut self) -> io::Result<()> {
+        let cap = if self.first {
+            self.first_seg_cap
+        } else {
+            self.seg_cap
+        };
+        let target = cap + TAG_SIZE;
+        let mut chunk = [0u8; 4096];
+        while self.cbuf.len() < target + 1 {
+            let want = (target + 1 - self.cbuf.len()).min(chunk.len());
+            let n = self.r.read(&mut chunk[..want])?;
+            if n == 0 {
+                break;
+            }
+            self.cbuf.extend_from_slice(&chunk[..n]);
+        }
+        if self.cbuf.is_empty() {
+            self.done = true;
+            self.pbuf.clear();
+            return Ok(());
+        }
+        let last = self.cbuf.len() <= target;
+        let seg_len = self.cbuf.len().min(target);
+        if seg_len < TAG_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::InvalidHeader,
+            ));
+        }
+        let plaintext_len = seg_len - TAG_SIZE;
+
+        let nonce = segment_nonce(&self.nonce_prefix, self.counter, last);
+        let mut segment = self.cbuf[..seg_len].to_vec();
+        let tag: aead::Tag<A> = GenericArray::clone_from_slice(&segment[plaintext_len..]);
+        self.aead
+            .decrypt_in_place_detached(&nonce, b"", &mut segment[..plaintext_len], &tag)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, Error::Aead))?;
+        segment.truncate(plaintext_len);
+        self.cbuf.drain(..seg_len);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, Error::NonceOverflow))?;
+        self.first = false;
+        self.done = last;
+        self.pbuf = segment;
+        self.ppos = 0;
+        Ok(())
+    }
+}
+
+impl<R, A> Read for TinkReader<R, A>
+where
+    R: Read,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.ppos >= self.pbuf.len() && !self.done {
+            self.advance()?;
+        }
+        let avail = &self.pbuf[self.ppos..];
+        let n = avail.len().min(out.len());
+        out[..n].copy_from_slice(&avail[..n]);
+        self.ppos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,98 @@
+//! A loader and runner for Wycheproof-style JSON test vectors, gated
+//! behind the `vectors` feature so `serde`/`serde_json` don't become
+//! part of the crate's default dependency surface.
+//!
+//! A vector file is a plain JSON array of [`Vector`]s. A `valid` vector
+//! must decrypt through [`Reader`](crate::Reader) to exactly
+//! `plaintext`; an invalid one -- truncated, bit-flipped, or with
+//! reordered chunks -- must be rejected rather than silently producing
+//! wrong plaintext. This lets downstream users and auditors extend
+//! negative testing by adding JSON fixtures instead of hand-writing
+//! ciphertext bytes in Rust.
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadInPlace, Key, KeyInit};
+use serde::{Deserialize, Serialize};
+
+use crate::Reader;
+
+/// A single test case: a key, a ciphertext, and the expected outcome of
+/// decrypting it with [`Reader`](crate::Reader).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vector {
+    /// A short human-readable description, e.g. `"truncated final
+    /// chunk"` or `"bit flip in chunk 2's tag"`.
+    pub name: String,
+    /// Hex-encoded AEAD key.
+    #[serde(with = "hex")]
+    pub key: Vec<u8>,
+    /// Hex-encoded stream ciphertext, header included.
+    #[serde(with = "hex")]
+    pub ciphertext: Vec<u8>,
+    /// Whether `ciphertext` is expected to decrypt successfully.
+    pub valid: bool,
+    /// The expected plaintext when `valid` is `true`; ignored otherwise.
+    #[serde(with = "hex", default)]
+    pub plaintext: Vec<u8>,
+}
+
+/// Parses a JSON array of [`Vector`]s.
+pub fn load(json: &str) -> serde_json::Result<Vec<Vector>> {
+    serde_json::from_str(json)
+}
+
+/// Serializes `vectors` as a pretty-printed JSON array, for tools that
+/// generate vector files rather than just consuming them.
+pub fn dump(vectors: &[Vector]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(vectors)
+}
+
+/// Runs `vector` against [`Reader`](crate::Reader) under AEAD `A` and
+/// reports whether the outcome matched its `valid`/`plaintext`
+/// expectation.
+pub fn run<A>(vector: &Vector) -> bool
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let key = Key::<A>::from_slice(&vector.key);
+    let outcome = Reader::<_, A>::new(&vector.ciphertext[..], key).and_then(|mut r| {
+        let mut plaintext = Vec::new();
+        std::io::Read::read_to_end(&mut r, &mut plaintext)?;
+        Ok(plaintext)
+    });
+    match outcome {
+        Ok(plaintext) => vector.valid && plaintext == vector.plaintext,
+        Err(_) => !vector.valid,
+    }
+}
+
+/// Hex encoding for vector fields, so test-vector files stay plain
+/// ASCII JSON instead of embedding raw binary or base64.
+mod hex {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut out = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            out.push_str(&format!("{b:02x}"));
+        }
+        s.serialize_str(&out)
+    }
+
+    pub(super) fn deserialize<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(d)?;
+        if s.len() % 2 != 0 {
+            return Err(serde::de::Error::custom("odd-length hex string"));
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
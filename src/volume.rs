@@ -0,0 +1,221 @@
+//! Splitting a stream's raw ciphertext bytes across fixed-size
+//! volumes, and stitching a sequence of volumes back into one
+//! continuous byte stream to decrypt -- for media with a hard size
+//! limit (optical discs, removable storage) or uploads that are
+//! naturally chunked (a multipart upload's part size, say).
+//!
+//! Unlike most of this crate, [`VolumeWriter`]/[`VolumeReader`] know
+//! nothing about AEAD chunks, nonces, or headers: a stream's
+//! ciphertext is just bytes to them, split at whatever byte count
+//! hits `volume_size`, with no attempt to align a volume boundary with
+//! a STREAM chunk boundary (or the header). Feed a [`VolumeWriter`] to
+//! [`Writer::new`](crate::Writer::new) as its `W`, and a
+//! [`VolumeReader`] to [`Reader::new`](crate::Reader::new) as its `R`,
+//! and the split is invisible to everything above it -- the
+//! concatenation of every volume is byte-for-byte the same ciphertext
+//! a single, unsplit [`Writer`](crate::Writer) would have produced.
+//!
+//! Both types are generic over how a volume is actually opened, so the
+//! same code works whether volumes are local files named by index,
+//! objects uploaded one part at a time, or anything else: callers
+//! supply a factory closure rather than a path template.
+
+use std::io::{self, Read, Write};
+
+/// Splits everything written to it across a sequence of volumes opened
+/// on demand from `open_volume`, starting a new one as soon as the
+/// current one reaches `volume_size` bytes.
+///
+/// `open_volume` is called with the 0-based index of the volume to
+/// open next; the first call (for index `0`) happens in
+/// [`VolumeWriter::new`].
+pub struct VolumeWriter<W, F> {
+    open_volume: F,
+    current: W,
+    volume_size: u64,
+    written_in_current: u64,
+    index: u64,
+}
+
+impl<W, F> VolumeWriter<W, F>
+where
+    W: Write,
+    F: FnMut(u64) -> io::Result<W>,
+{
+    /// Opens the first volume (index `0`) via `open_volume` and starts
+    /// splitting subsequent writes every `volume_size` bytes.
+    ///
+    /// `volume_size` must be nonzero.
+    pub fn new(volume_size: u64, mut open_volume: F) -> io::Result<Self> {
+        if volume_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "volume_size must be nonzero",
+            ));
+        }
+        let current = open_volume(0)?;
+        Ok(Self {
+            open_volume,
+            current,
+            volume_size,
+            written_in_current: 0,
+            index: 0,
+        })
+    }
+}
+
+impl<W, F> Write for VolumeWriter<W, F>
+where
+    W: Write,
+    F: FnMut(u64) -> io::Result<W>,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.written_in_current >= self.volume_size {
+            self.index += 1;
+            self.current = (self.open_volume)(self.index)?;
+            self.written_in_current = 0;
+        }
+        let remaining = (self.volume_size - self.written_in_current) as usize;
+        let n = self.current.write(&buf[..buf.len().min(remaining)])?;
+        self.written_in_current += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.flush()
+    }
+}
+
+/// Reads continuously across a sequence of volumes opened on demand
+/// from `open_volume`, advancing to the next one as soon as the
+/// current one returns EOF.
+///
+/// `open_volume` is called with the 0-based index of the volume to
+/// open next, and returns `Ok(None)` once there's no volume at that
+/// index -- which [`VolumeReader::read`] then reports as its own EOF.
+pub struct VolumeReader<R, F> {
+    open_volume: F,
+    current: Option<R>,
+    index: u64,
+}
+
+impl<R, F> VolumeReader<R, F>
+where
+    R: Read,
+    F: FnMut(u64) -> io::Result<Option<R>>,
+{
+    /// Opens the first volume (index `0`) via `open_volume`, or starts
+    /// already at EOF if there isn't one.
+    pub fn new(mut open_volume: F) -> io::Result<Self> {
+        let current = open_volume(0)?;
+        Ok(Self {
+            open_volume,
+            current,
+            index: 0,
+        })
+    }
+}
+
+impl<R, F> Read for VolumeReader<R, F>
+where
+    R: Read,
+    F: FnMut(u64) -> io::Result<Option<R>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(current) = &mut self.current else {
+                return Ok(0);
+            };
+            let n = current.read(buf)?;
+            if n > 0 {
+                return Ok(n);
+            }
+            self.index += 1;
+            self.current = (self.open_volume)(self.index)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{VolumeReader, VolumeWriter};
+
+    #[test]
+    fn splits_writes_across_volumes_of_the_given_size() {
+        let volumes = RefCell::new(Vec::<Vec<u8>>::new());
+        let mut w = VolumeWriter::new(4, |index| {
+            assert_eq!(index, volumes.borrow().len() as u64);
+            volumes.borrow_mut().push(Vec::new());
+            Ok(VolumeHandle(index, &volumes))
+        })
+        .unwrap();
+        std::io::Write::write_all(&mut w, b"hello, world").unwrap();
+
+        let volumes = volumes.into_inner();
+        assert_eq!(
+            volumes,
+            vec![b"hell".to_vec(), b"o, w".to_vec(), b"orld".to_vec()]
+        );
+    }
+
+    #[test]
+    fn a_write_that_would_overflow_a_volume_is_split_across_two_write_calls() {
+        let volumes = RefCell::new(Vec::<Vec<u8>>::new());
+        let mut w = VolumeWriter::new(3, |index| {
+            volumes.borrow_mut().push(Vec::new());
+            Ok(VolumeHandle(index, &volumes))
+        })
+        .unwrap();
+        // A single six-byte write into 3-byte volumes must come back
+        // as a short write, the same as any other `Write::write` that
+        // can't take the whole buffer in one call.
+        let n = std::io::Write::write(&mut w, b"abcdef").unwrap();
+        assert_eq!(n, 3);
+        let n = std::io::Write::write(&mut w, b"def").unwrap();
+        assert_eq!(n, 3);
+
+        let volumes = volumes.into_inner();
+        assert_eq!(volumes, vec![b"abc".to_vec(), b"def".to_vec()]);
+    }
+
+    #[test]
+    fn stitches_volumes_back_into_one_continuous_read() {
+        let volumes = [b"hell".to_vec(), b"o, w".to_vec(), b"orld".to_vec()];
+        let mut r = VolumeReader::new(|index: u64| Ok(volumes.get(index as usize).map(|v| &v[..])))
+            .unwrap();
+
+        let mut out = Vec::new();
+        std::io::Read::read_to_end(&mut r, &mut out).unwrap();
+        assert_eq!(out, b"hello, world");
+    }
+
+    #[test]
+    fn stitching_an_empty_volume_sequence_reads_as_eof() {
+        let mut r = VolumeReader::new(|_: u64| Ok(None::<&[u8]>)).unwrap();
+        let mut out = Vec::new();
+        std::io::Read::read_to_end(&mut r, &mut out).unwrap();
+        assert_eq!(out, b"");
+    }
+
+    /// A `Write` that appends every byte written to it into the
+    /// `index`th `Vec` of a shared `RefCell<Vec<Vec<u8>>>`, standing in
+    /// for one already-open volume (a file, an in-progress upload)
+    /// without needing a real one for these tests.
+    struct VolumeHandle<'a>(u64, &'a RefCell<Vec<Vec<u8>>>);
+
+    impl std::io::Write for VolumeHandle<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.1.borrow_mut()[self.0 as usize].extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
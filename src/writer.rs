@@ -0,0 +1,968 @@
+use std::io::{self, Read, Write};
+
+use aead::generic_array::typenum::{U12, U16};
+use aead::{AeadCore, AeadInPlace, Key, KeyInit};
+
+use crate::buf::{Buf, TAG_SIZE};
+use crate::derive::{AlgorithmId, NonceDeriver};
+use crate::digest::{DigestAlgorithm, Hasher};
+use crate::export::KeyExporter;
+use crate::header::{encode_extensions, Extension, Header, Version};
+use crate::key_check::derive_key_check;
+use crate::keyring::KeyId;
+use crate::metadata::{self, Metadata};
+use crate::nonce::{self, PREFIX_LEN};
+use crate::padding::{self, LENGTH_FOOTER_LEN};
+use crate::provider::KeyProvider;
+use crate::{Error, CHUNK_SIZE};
+
+/// Seals a plaintext as a sequence of fixed-size, AEAD-authenticated
+/// chunks written to an underlying [`Write`]r.
+///
+/// `A` is the AEAD algorithm used to seal each chunk; it must use a
+/// 96-bit nonce and a 128-bit tag, which covers the common choices
+/// (`ChaCha20Poly1305`, `Aes256Gcm`, ...).
+pub struct Writer<W, A>
+where
+    A: AeadCore,
+{
+    w: W,
+    aead: A,
+    nonce_prefix: [u8; PREFIX_LEN],
+    counter: u64,
+    buf: Buf,
+    /// The plaintext capacity of a non-final chunk; equal to `buf`'s
+    /// capacity, cached here so the chunk-aligned fast path in
+    /// [`Writer::write`] doesn't need to go through `Buf` to learn it.
+    chunk_cap: usize,
+    digest: Option<(DigestAlgorithm, Hasher)>,
+    padded: bool,
+    /// Set when chunk nonces are HKDF-derived instead of built by
+    /// concatenating the prefix with the counter. See
+    /// [`Writer::with_derived_nonces`].
+    nonce_deriver: Option<NonceDeriver>,
+    exporter: KeyExporter,
+    /// The header's [`Version::V6`] comment, given to every chunk's AEAD
+    /// call as associated data. Empty for every other version, which is
+    /// equivalent to the empty associated data every earlier version
+    /// always used. See [`Writer::with_comment`].
+    comment: Vec<u8>,
+    total_written: u64,
+    /// Ciphertext bytes written so far (header excluded), tracked only
+    /// to answer [`Writer::stats`].
+    #[cfg(feature = "stats")]
+    bytes_out: u64,
+    /// How many bytes of the current chunk's already-sealed ciphertext
+    /// have made it out to `w`, when [`Writer::flush_chunk`] had to
+    /// return early mid-write (a non-blocking `w` reporting
+    /// [`io::ErrorKind::WouldBlock`], say). `None` means there's no
+    /// flush in progress: either nothing is sealed yet, or the last
+    /// flush ran to completion. Kept separate from `buf`'s own length
+    /// because `buf`'s storage holds ciphertext, not plaintext, for as
+    /// long as this is `Some` -- `flush_chunk` needs to tell those two
+    /// states apart to know whether it's safe to seal again. Threaded
+    /// into [`seal_and_write`] behind the `embedded` feature so that
+    /// path resumes the same way instead of re-sealing already-sealed
+    /// bytes under a reused nonce.
+    flush_progress: Option<usize>,
+}
+
+impl<W, A> Writer<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    /// Starts a new stream, writing its header to `w` immediately.
+    ///
+    /// `nonce_prefix` must be unique for every stream encrypted under
+    /// `key`; reusing a `(key, nonce_prefix)` pair breaks the security
+    /// of the underlying AEAD. Callers that don't already have a source
+    /// of unique prefixes should fill `nonce_prefix` with bytes from a
+    /// cryptographically secure RNG.
+    pub fn new(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but precedes the header on the wire with
+    /// [`MAGIC`](crate::header::MAGIC), letting [`sniff`](crate::sniff)
+    /// recognize the stream as this crate's format before a key is
+    /// available to actually decrypt anything -- a plain [`Writer::new`]
+    /// stream has no such prefix and isn't distinguishable from
+    /// arbitrary binary data that way.
+    pub fn with_magic(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V3,
+            None,
+            false,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but generates `nonce_prefix` itself from
+    /// the OS RNG via [`getrandom`], instead of requiring the caller to
+    /// supply one.
+    ///
+    /// [`Writer::new`]'s `nonce_prefix` parameter exists for callers
+    /// with their own source of per-stream uniqueness (a counter
+    /// persisted alongside the key, say); everyone else is left to
+    /// reach for *some* RNG, and nothing stops that from being a
+    /// non-cryptographic one, which silently breaks this construction's
+    /// security. This constructor removes that footgun from the common
+    /// case by going straight to the OS RNG.
+    #[cfg(feature = "getrandom")]
+    pub fn new_default(w: W, key: &Key<A>) -> io::Result<Self> {
+        let mut nonce_prefix = [0u8; PREFIX_LEN];
+        getrandom::getrandom(&mut nonce_prefix).map_err(|e| io::Error::other(e.to_string()))?;
+        Self::new(w, key, nonce_prefix)
+    }
+
+    /// Like [`Writer::new`], but also appends an authenticated digest
+    /// of the whole plaintext as a footer after the last chunk's
+    /// plaintext once the stream is finished. See the
+    /// [`digest`](crate::digest) module for details.
+    ///
+    /// Chunks are shrunk by the digest's length so that the footer
+    /// always fits in the final chunk without growing it past
+    /// [`CHUNK_SIZE`]; [`Reader`](crate::Reader) learns this from the
+    /// header and adjusts automatically.
+    pub fn with_digest(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        digest: Option<DigestAlgorithm>,
+    ) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            digest,
+            false,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but pads the final chunk's plaintext with
+    /// zero bytes up to its [Padmé](crate::padding) target length
+    /// before sealing it, so the ciphertext length leaks only
+    /// `O(log log n)` bits about the true plaintext length. The true
+    /// length is recorded as an authenticated footer so
+    /// [`Reader`](crate::Reader) can strip the padding back off.
+    ///
+    /// Padding is confined to the final chunk: if the ideal Padmé
+    /// padding doesn't fit in the room left in that chunk, as little as
+    /// possible of it is dropped rather than spilling into another
+    /// chunk.
+    pub fn with_padding(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            true,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but derives each chunk's nonce from the
+    /// key and random prefix via HKDF-SHA256, instead of building it by
+    /// concatenating the prefix with the chunk counter. See the
+    /// [`derive`](crate::derive) module.
+    ///
+    /// This is mainly useful for AEADs with a 96-bit nonce (the only
+    /// size this crate supports): HKDF spreads the key and prefix
+    /// across every output bit instead of leaving the counter and
+    /// final-chunk flag in fixed, predictable slots.
+    pub fn with_derived_nonces(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+    ) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            None,
+            Some(NonceDeriver::new(key, &nonce_prefix)),
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::with_derived_nonces`], but also binds each chunk's
+    /// derived nonce to the wire version, [`CHUNK_SIZE`], and `A`'s
+    /// [`AlgorithmId`](crate::AlgorithmId), closing a class of
+    /// cross-parameter confusion [`Writer::with_derived_nonces`] leaves
+    /// open: reusing the same `(key, nonce_prefix)` pair across two
+    /// builds that disagree on `CHUNK_SIZE` (the `large_chunks` feature,
+    /// say) or on `A` itself can otherwise derive colliding nonces.
+    ///
+    /// Only [`Reader::with_bound_nonces`](crate::Reader::with_bound_nonces)
+    /// can open the resulting stream; [`Reader::new`] rejects it, since
+    /// reconstructing the same binding needs `A: AlgorithmId`, a bound
+    /// its generic `A` doesn't carry.
+    pub fn with_bound_nonces(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self>
+    where
+        A: AlgorithmId,
+    {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V2,
+            None,
+            false,
+            None,
+            Some(NonceDeriver::new_bound::<A>(key, &nonce_prefix)),
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but embeds `key_id` in the header so a
+    /// [`Keyring`](crate::Keyring) holding many keys can identify which
+    /// one to use for decryption. See the [`keyring`](crate::keyring)
+    /// module.
+    pub fn with_key_id(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        key_id: KeyId,
+    ) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            Some(key_id),
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but also writes a key-check value to the
+    /// header, derived from `key` and `nonce_prefix`, so the
+    /// [`Reader`](crate::Reader) that opens this stream can report the
+    /// wrong key immediately instead of only finding out once the
+    /// first chunk fails to authenticate. See the
+    /// [`key_check`](crate::key_check) module.
+    pub fn with_key_check(w: W, key: &Key<A>, nonce_prefix: [u8; PREFIX_LEN]) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            None,
+            None,
+            false,
+            true,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but follows the header on the wire with a
+    /// TLV area encoding `extensions`, for header fields this crate
+    /// doesn't know about -- a caller-defined content type or
+    /// application version, say.
+    ///
+    /// The area is always authenticated: a key-check value covering
+    /// `extensions` as well as `key` and `nonce_prefix` is derived and
+    /// written to the header, so [`Reader::new`](crate::Reader::new)
+    /// rejects any stream whose extension bytes were tampered with,
+    /// even though a generic `Reader::new` caller has no way to ask
+    /// what the extensions actually mean. See the
+    /// [`key_check`](crate::key_check) module and
+    /// [`Extension`](crate::header::Extension).
+    pub fn with_extensions(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        extensions: &[Extension],
+    ) -> io::Result<Self> {
+        let ext_bytes = encode_extensions(extensions).map_err(io::Error::other)?;
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V4,
+            None,
+            false,
+            None,
+            None,
+            false,
+            true,
+            &ext_bytes,
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but follows the header on the wire with an
+    /// encrypted-and-authenticated block carrying `metadata` -- a
+    /// caller's original filename, modification time, and content type.
+    ///
+    /// Unlike [`Writer::with_extensions`]'s cleartext TLV area, this
+    /// block is sealed under its own one-time key, HKDF-derived from
+    /// `key` and `nonce_prefix`, so [`Reader::new`](crate::Reader::new)
+    /// can open the stream normally without ever seeing it: only a
+    /// [`Reader::metadata`](crate::Reader::metadata) call, which needs
+    /// the same `key`, can recover it. See the
+    /// [`metadata`](crate::metadata) module.
+    pub fn with_metadata(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        metadata: &Metadata,
+    ) -> io::Result<Self> {
+        let sealed = metadata::seal::<A>(metadata, key.as_slice(), &nonce_prefix)
+            .map_err(io::Error::other)?;
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V5,
+            None,
+            false,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &sealed,
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but follows the header on the wire with a
+    /// cleartext `comment` -- a short operator-supplied label, e.g.
+    /// `"2024 Q1 payroll backup"` -- and gives `comment`'s bytes to
+    /// every chunk's AEAD call as associated data.
+    ///
+    /// Unlike [`Writer::with_extensions`]'s TLV area, which relies on a
+    /// separate key-check value to catch tampering, a `V6` comment is
+    /// authenticated by every chunk in the stream: flip a single bit in
+    /// it and [`Reader::new`](crate::Reader::new) fails to decrypt the
+    /// very first chunk, the same as if the ciphertext itself had been
+    /// tampered with. `comment` is limited to [`u16::MAX`] bytes, the
+    /// same bound [`Writer::with_extensions`]'s TLV area has.
+    pub fn with_comment(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        comment: &[u8],
+    ) -> io::Result<Self> {
+        u16::try_from(comment.len()).map_err(io::Error::other)?;
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V6,
+            None,
+            false,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            comment,
+        )
+    }
+
+    /// Like [`Writer::with_key_id`], but resolves the key from
+    /// `provider` instead of requiring the caller to hold it directly.
+    /// See the [`provider`](crate::provider) module.
+    pub fn with_provider<P>(
+        w: W,
+        provider: &P,
+        key_id: KeyId,
+        nonce_prefix: [u8; PREFIX_LEN],
+    ) -> io::Result<Self>
+    where
+        P: KeyProvider<A>,
+    {
+        let key = provider
+            .resolve(key_id)
+            .map_err(crate::error::provider_io_error)?;
+        Self::new_inner(
+            w,
+            &key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            Some(key_id),
+            None,
+            false,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    /// Like [`Writer::new`], but marks the header as carrying
+    /// Deflate-compressed plaintext. Only used internally by
+    /// [`CompressWriter`](crate::CompressWriter), which is responsible
+    /// for actually compressing the bytes it passes through; this
+    /// constructor just records the flag.
+    #[cfg(feature = "compression")]
+    pub(crate) fn new_compressed(
+        w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+    ) -> io::Result<Self> {
+        Self::new_inner(
+            w,
+            key,
+            nonce_prefix,
+            Version::V1,
+            None,
+            false,
+            None,
+            None,
+            true,
+            false,
+            &[],
+            &[],
+            &[],
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_inner(
+        mut w: W,
+        key: &Key<A>,
+        nonce_prefix: [u8; PREFIX_LEN],
+        version: Version,
+        digest: Option<DigestAlgorithm>,
+        padded: bool,
+        key_id: Option<KeyId>,
+        nonce_deriver: Option<NonceDeriver>,
+        compressed: bool,
+        key_check: bool,
+        extra_key_check_info: &[u8],
+        sealed_metadata: &[u8],
+        comment: &[u8],
+    ) -> io::Result<Self> {
+        let key_check =
+            key_check.then(|| derive_key_check::<A>(key, &nonce_prefix, extra_key_check_info));
+        let derived_nonce = nonce_deriver.is_some();
+        let header = Header {
+            version,
+            digest,
+            padded,
+            key_id,
+            derived_nonce,
+            compressed,
+            key_check,
+            nonce_prefix,
+            extensions: Vec::new(),
+            sealed_metadata: Vec::new(),
+            comment: Vec::new(),
+        };
+        if matches!(version, Version::V3) {
+            w.write_all(&crate::header::MAGIC)?;
+        }
+        w.write_all(&header.encode())?;
+        if matches!(version, Version::V4) {
+            let ext_len = u16::try_from(extra_key_check_info.len())
+                .expect("Writer::with_extensions already bounded this to u16::MAX");
+            w.write_all(&ext_len.to_be_bytes())?;
+            w.write_all(extra_key_check_info)?;
+        }
+        if matches!(version, Version::V5) {
+            let meta_len = u16::try_from(sealed_metadata.len())
+                .expect("Writer::with_metadata already bounded this to u16::MAX");
+            w.write_all(&meta_len.to_be_bytes())?;
+            w.write_all(sealed_metadata)?;
+        }
+        if matches!(version, Version::V6) {
+            let comment_len = u16::try_from(comment.len())
+                .expect("Writer::with_comment already bounded this to u16::MAX");
+            w.write_all(&comment_len.to_be_bytes())?;
+            w.write_all(comment)?;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            key_id = ?key_id,
+            digest = ?digest,
+            padded,
+            derived_nonce,
+            compressed,
+            key_checked = key_check.is_some(),
+            "wrote stream header"
+        );
+        let footer_reserve = digest.map_or(0, DigestAlgorithm::digest_len)
+            + if padded { LENGTH_FOOTER_LEN } else { 0 };
+        let chunk_cap = CHUNK_SIZE - footer_reserve;
+        let exporter = KeyExporter::new(key.as_slice(), &nonce_prefix);
+        Ok(Self {
+            w,
+            aead: A::new(key),
+            nonce_prefix,
+            counter: 0,
+            buf: Buf::new(chunk_cap),
+            chunk_cap,
+            digest: digest.map(|d| (d, d.hasher())),
+            padded,
+            nonce_deriver,
+            exporter,
+            comment: comment.to_vec(),
+            total_written: 0,
+            #[cfg(feature = "stats")]
+            bytes_out: 0,
+            flush_progress: None,
+        })
+    }
+
+    /// A stable, non-secret identifier for this stream, derived from
+    /// its `nonce_prefix`. See
+    /// [`HeaderInfo::stream_id`](crate::HeaderInfo::stream_id)'s doc
+    /// comment; [`Reader::stream_id`](crate::Reader::stream_id) returns
+    /// the same value once the stream is reopened for reading.
+    pub fn stream_id(&self) -> [u8; crate::header::STREAM_ID_LEN] {
+        crate::header::stream_id(&self.nonce_prefix)
+    }
+
+    /// Pulls plaintext from `r` and writes it into this stream until
+    /// `r` reaches EOF, or (if `limit` is `Some`) until `limit` bytes
+    /// have been pulled, whichever comes first. Returns the number of
+    /// bytes pulled and written.
+    ///
+    /// Reads happen through a single internal buffer sized to this
+    /// stream's chunk capacity, reused for the whole call, so "encrypt
+    /// this whole file/socket" doesn't require the caller to write its
+    /// own copy loop or size a buffer of its own.
+    pub fn write_from<R>(&mut self, r: &mut R, limit: Option<u64>) -> io::Result<u64>
+    where
+        R: Read,
+    {
+        let mut buf = vec![0u8; self.chunk_cap];
+        let mut total = 0u64;
+        loop {
+            let want = match limit {
+                Some(limit) => {
+                    let remaining = limit - total;
+                    if remaining == 0 {
+                        break;
+                    }
+                    remaining.min(buf.len() as u64) as usize
+                }
+                None => buf.len(),
+            };
+            let n = r.read(&mut buf[..want])?;
+            if n == 0 {
+                break;
+            }
+            self.write_all(&buf[..n])?;
+            total += n as u64;
+        }
+        Ok(total)
+    }
+
+    /// Derives a subkey bound to this stream's key and `nonce_prefix`,
+    /// via HKDF-SHA256. `context` distinguishes one export from another
+    /// (a MAC key from a filename-encryption key, say); the same
+    /// `context` always returns the same subkey for this stream, and
+    /// [`Reader::export_key`](crate::Reader::export_key) returns the
+    /// same value again once the stream is reopened for reading. See
+    /// the [`export`](crate::export) module.
+    pub fn export_key(&self, context: &[u8]) -> [u8; crate::export::EXPORT_KEY_LEN] {
+        self.exporter.export(context)
+    }
+
+    /// Returns a snapshot of this `Writer`'s chunk and byte counters.
+    /// See the [`stats`](crate::stats) module.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::stats::Stats {
+        crate::stats::Stats {
+            chunks: self.counter,
+            bytes_in: self.total_written,
+            bytes_out: self.bytes_out,
+            auth_failures: 0,
+            rekeys: 0,
+        }
+    }
+
+    /// Builds the nonce for chunk `self.counter`, from the deriver if
+    /// one is in play, otherwise by concatenating the prefix with the
+    /// counter and final-chunk flag.
+    fn chunk_nonce(&self, last: bool) -> aead::Nonce<A> {
+        match &self.nonce_deriver {
+            Some(d) => d.derive(self.counter, last),
+            None => nonce::build(&self.nonce_prefix, self.counter, last),
+        }
+    }
+
+    /// Seals and writes out the chunk currently buffered, advancing the
+    /// nonce counter.
+    ///
+    /// The tag is copied into `storage` right after the sealed
+    /// plaintext, rather than written out in a second call: there's no
+    /// `flush_internal` split between a body write and a tag write
+    /// anywhere in this crate to begin with, so every chunk --
+    /// ciphertext and tag together -- reaches `w` out of the one
+    /// buffer. Outside the `embedded` feature that write happens in a
+    /// resumable loop rather than a single `write_all`: if `w` (a
+    /// non-blocking socket, say) accepts only part of the sealed chunk
+    /// before erroring, this function can be called again later and
+    /// picks the write back up from where it stopped instead of
+    /// re-sealing the same bytes or losing track of how much already
+    /// went out.
+    ///
+    /// Behind the `embedded` feature, the sealing and writing below
+    /// happen in [`seal_and_write`], a free function bound only by `A`
+    /// (not `W`), so firmware instantiating `Writer` over several
+    /// concrete `W`s (one per peripheral, say) compiles this chunk's
+    /// worth of logic once per AEAD algorithm instead of once per
+    /// `(W, A)` pair. It's handed `self.flush_progress` the same way
+    /// the non-`embedded` path below uses it directly, so a `WouldBlock`
+    /// partway through `w.write` is just as resumable through the `dyn
+    /// Write` path as through the generic one. Outside that feature
+    /// this does the same work directly against `self.w: W`, so the
+    /// compiler can inline the write all the way through for the common
+    /// case of a handful of concrete `W`s, where the duplication doesn't
+    /// matter and the extra `dyn Write` indirection would only cost a
+    /// vtable call.
+    fn flush_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = self.chunk_nonce(last);
+        #[cfg_attr(
+            all(feature = "embedded", not(any(feature = "tracing", feature = "stats"))),
+            allow(unused_variables)
+        )]
+        let plaintext_len = self.buf.len();
+        #[cfg(feature = "embedded")]
+        #[cfg_attr(not(feature = "tracing"), allow(clippy::map_identity))]
+        seal_and_write(
+            &self.aead,
+            &nonce,
+            &self.comment,
+            &mut self.buf,
+            &mut self.w,
+            &mut self.flush_progress,
+        )
+        .map_err(|e| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(chunk = self.counter, last, error = %e, "chunk encryption failed");
+            e
+        })?;
+        #[cfg(not(feature = "embedded"))]
+        {
+            let sealed_len = plaintext_len + TAG_SIZE;
+            // `flush_progress` survives a prior call to this function
+            // returning early: a `write` that failed partway through
+            // (a `WouldBlock` from a non-blocking `w`, say) still left
+            // `storage` holding the sealed ciphertext from that call,
+            // not the plaintext it started as, so re-running the AEAD
+            // call here would seal already-sealed bytes into garbage.
+            // Resuming instead just means picking the write back up
+            // from however far it got.
+            if self.flush_progress.is_none() {
+                let storage = self.buf.storage_mut();
+                let tag = self
+                    .aead
+                    .encrypt_in_place_detached(&nonce, &self.comment, &mut storage[..plaintext_len])
+                    .map_err(|_| {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(chunk = self.counter, last, "chunk encryption failed");
+                        io::Error::other(Error::Aead)
+                    })?;
+                storage[plaintext_len..sealed_len].copy_from_slice(&tag);
+                self.flush_progress = Some(0);
+            }
+            while self.flush_progress != Some(sealed_len) {
+                let written = self.flush_progress.expect("set above if it was `None`");
+                let storage = self.buf.storage_mut();
+                let n = self.w.write(&storage[written..sealed_len])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole chunk",
+                    ));
+                }
+                self.flush_progress = Some(written + n);
+            }
+            self.flush_progress = None;
+            self.buf.clear();
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(chunk = self.counter, last, plaintext_len, "encrypted chunk");
+        #[cfg(feature = "stats")]
+        {
+            self.bytes_out += plaintext_len as u64 + TAG_SIZE as u64;
+        }
+        self.counter = self.counter.checked_add(1).ok_or_else(|| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(chunk = self.counter, "nonce counter overflow");
+            io::Error::other(Error::NonceOverflow)
+        })?;
+        Ok(())
+    }
+
+    /// Zero-pads the plaintext currently buffered for the in-progress
+    /// chunk out to a full chunk, then seals and writes it as a
+    /// non-final chunk -- forcing the next byte written to start a
+    /// fresh chunk instead of landing wherever this write happens to
+    /// end. Returns the number of zero bytes added, or `0` if nothing
+    /// was buffered (the stream was already sitting on a chunk
+    /// boundary, so there's nothing to pad).
+    ///
+    /// For callers that want a logical record to start on a chunk
+    /// boundary ahead of later random access via
+    /// [`patch`](crate::patch), [`RangeReader`](crate::RangeReader), or
+    /// [`MmapReader`](crate::MmapReader), which all seek straight to a
+    /// chunk by index rather than reading the stream from the start.
+    ///
+    /// The padding added isn't recorded in the ciphertext itself the
+    /// way [`Writer::with_padding`]'s final-chunk padding is: doing so
+    /// here would mean giving an interior chunk a length footer
+    /// [`Reader`](crate::Reader)'s ordinary streaming decode has no
+    /// protocol for, corrupting the plaintext for any consumer reading
+    /// this stream the normal way instead of chunk-by-chunk. A caller
+    /// that needs to strip this padding back out already has what it
+    /// needs from this method's return value, tracked the same way it
+    /// already tracks which chunk index a given record landed in.
+    pub fn pad_to_boundary(&mut self) -> io::Result<usize> {
+        if self.buf.len() == 0 {
+            return Ok(0);
+        }
+        let pad_len = self.chunk_cap - self.buf.len();
+        self.write_all(&vec![0u8; pad_len])?;
+        Ok(pad_len)
+    }
+
+    /// Finishes the stream: seals any buffered plaintext, with the
+    /// padding and digest footers (if enabled) appended after it, as
+    /// the final chunk, then returns the underlying writer.
+    ///
+    /// Taking `self` by value means a `WouldBlock`-style error from
+    /// this method can't be retried the way one from
+    /// [`Write::write`](io::Write::write) or
+    /// [`Write::flush`](io::Write::flush) can: there's no `Writer` left
+    /// to hold the resumable state afterwards. Call
+    /// [`Write::flush`](io::Write::flush) first and treat it as done
+    /// before calling this method on a non-blocking `w`, the same way
+    /// a caller already needs to drain a non-blocking socket before
+    /// tearing it down.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.buf.is_full() {
+            self.flush_chunk(false)?;
+        }
+        if self.padded {
+            let target = padding::padme_target(self.total_written);
+            let ideal_pad = target.saturating_sub(self.total_written);
+            let room = self.buf.footer_room() - LENGTH_FOOTER_LEN;
+            let pad_len = usize::try_from(ideal_pad).unwrap_or(room).min(room);
+            self.buf.append_footer(&vec![0u8; pad_len]);
+            self.buf.append_footer(&self.total_written.to_be_bytes());
+        }
+        if let Some((_, hasher)) = self.digest.take() {
+            let digest = hasher.finalize();
+            self.buf.append_footer(&digest);
+        }
+        self.flush_chunk(true)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            chunks = self.counter,
+            total_written = self.total_written,
+            "stream finished"
+        );
+        Ok(self.w)
+    }
+}
+
+/// The non-`W`-generic half of [`Writer::flush_chunk`]: seals `buf`'s
+/// buffered plaintext in place under `nonce` and writes the result
+/// (ciphertext plus tag) to `w`, resuming from `flush_progress` instead
+/// of `write_all`-ing in one call.
+///
+/// Taking `w: &mut dyn Write` instead of a generic `W: Write` is the
+/// whole point: this function's body -- the AEAD call and the write
+/// loop -- is monomorphized once per `A`, not once per `(W, A)` pair, so
+/// code size doesn't grow with the number of distinct stream types a
+/// firmware image instantiates `Writer` over. Only compiled in behind
+/// the `embedded` feature; see [`Writer::flush_chunk`].
+///
+/// `flush_progress` is `&mut self.flush_progress`, threaded in rather
+/// than taken directly off `self` so this stays a free function bound
+/// only by `A`. If a prior call returned early (`w.write` blocked or
+/// only wrote part of the sealed chunk), `flush_progress` is `Some`,
+/// and this resumes the write against the ciphertext already sitting in
+/// `buf`'s storage instead of sealing `buf` again -- sealing twice
+/// would encrypt the first call's ciphertext as if it were plaintext,
+/// under a nonce this chunk already used. See
+/// [`Writer::flush_chunk`]'s doc comment for why the non-`embedded`
+/// path needs the same care.
+#[cfg(feature = "embedded")]
+fn seal_and_write<A>(
+    aead: &A,
+    nonce: &aead::Nonce<A>,
+    aad: &[u8],
+    buf: &mut Buf,
+    w: &mut dyn Write,
+    flush_progress: &mut Option<usize>,
+) -> io::Result<()>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16>,
+{
+    let plaintext_len = buf.len();
+    let sealed_len = plaintext_len + TAG_SIZE;
+    if flush_progress.is_none() {
+        let storage = buf.storage_mut();
+        let tag = aead
+            .encrypt_in_place_detached(nonce, aad, &mut storage[..plaintext_len])
+            .map_err(|_| io::Error::other(Error::Aead))?;
+        storage[plaintext_len..sealed_len].copy_from_slice(&tag);
+        *flush_progress = Some(0);
+    }
+    while *flush_progress != Some(sealed_len) {
+        let written = flush_progress.expect("set above if it was `None`");
+        let storage = buf.storage_mut();
+        let n = w.write(&storage[written..sealed_len])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "failed to write whole chunk",
+            ));
+        }
+        *flush_progress = Some(written + n);
+    }
+    *flush_progress = None;
+    buf.clear();
+    Ok(())
+}
+
+impl<W, A> Write for Writer<W, A>
+where
+    W: Write,
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    // `write_all` isn't overridden here: there's no `do_write` in this
+    // crate for it to loop through, and the default `io::Write::write_all`
+    // just calls `write` in a loop until its input is exhausted, which
+    // is exactly what a short write from `write` below (see its own
+    // doc comment) needs to be retried correctly.
+
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A previous call may have filled a chunk and then hit a
+        // `WouldBlock`-style error partway through flushing it --
+        // `buf` stays full (and `flush_chunk` stays resumable, see its
+        // own doc comment) until that flush completes. Finish it
+        // before accepting anything new: there's no room in `buf` for
+        // more plaintext until this chunk is out the door, and a
+        // caller retrying with `write(&[])` after such an error needs
+        // this to be the thing that makes progress.
+        if self.buf.is_full() {
+            self.flush_chunk(false)?;
+        }
+
+        let mut written = 0usize;
+        let mut data = data;
+
+        // Fast path for bulk, chunk-aligned writes: while the buffer is
+        // empty and a full chunk is available, copy straight from the
+        // caller's slice into the chunk scratch and seal it, instead of
+        // trickling it through `Buf::fill` one (possibly short) write
+        // at a time.
+        while self.buf.len() == 0 && data.len() >= self.chunk_cap {
+            let (chunk, rest) = data.split_at(self.chunk_cap);
+            if let Some((_, hasher)) = &mut self.digest {
+                hasher.update(chunk);
+            }
+            self.buf.storage_mut()[..self.chunk_cap].copy_from_slice(chunk);
+            self.buf.set_len(self.chunk_cap);
+            self.total_written += self.chunk_cap as u64;
+            written += self.chunk_cap;
+            // `chunk` is committed -- hashed, buffered, and accounted
+            // for -- regardless of whether this flush completes, so a
+            // failure here is reported as a short write of `written`
+            // bytes rather than propagated as an error. That keeps
+            // this call's contract intact (an `Err` return means none
+            // of `data` was newly consumed by *this* call) even though
+            // the flush itself hasn't finished; the pending flush is
+            // retried, and any error it's still hitting surfaced, the
+            // next time `write` (or `flush`) runs.
+            if self.flush_chunk(false).is_err() {
+                return Ok(written);
+            }
+            data = rest;
+        }
+
+        while !data.is_empty() {
+            let n = self.buf.fill(data);
+            if let Some((_, hasher)) = &mut self.digest {
+                hasher.update(&data[..n]);
+            }
+            data = &data[n..];
+            self.total_written += n as u64;
+            written += n;
+            if self.buf.is_full() && self.flush_chunk(false).is_err() {
+                return Ok(written);
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_full() {
+            self.flush_chunk(false)?;
+        }
+        self.w.flush()
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+fn stream_cmd(args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_stream"));
+    cmd.args(args);
+    cmd
+}
+
+fn run(cmd: &mut Command, stdin: &[u8]) -> (Vec<u8>, Vec<u8>, bool) {
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    let mut child = cmd.spawn().unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.stdout, output.stderr, output.status.success())
+}
+
+fn write_key(dir: &std::path::Path) -> std::path::PathBuf {
+    let path = dir.join("key");
+    std::fs::write(&path, [0x42u8; 32]).unwrap();
+    path
+}
+
+#[test]
+fn roundtrip_through_stdin_stdout() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path = key_path.to_str().unwrap();
+
+    let (ciphertext, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path]).stdin(Stdio::piped()),
+        b"hello, world",
+    );
+    assert!(ok, "encrypt failed: {}", String::from_utf8_lossy(&stderr));
+
+    let (plaintext, stderr, ok) = run(
+        stream_cmd(&["decrypt", "--key-file", key_path]).stdin(Stdio::piped()),
+        &ciphertext,
+    );
+    assert!(ok, "decrypt failed: {}", String::from_utf8_lossy(&stderr));
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn unsupported_aead_is_rejected() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path = key_path.to_str().unwrap();
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path, "--aead", "aes256gcm"])
+            .stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("unsupported --aead"));
+}
+
+#[test]
+fn mismatched_chunk_size_is_rejected() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path = key_path.to_str().unwrap();
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path, "--chunk-size", "10"])
+            .stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("unsupported --chunk-size"));
+}
+
+#[test]
+fn non_empty_ad_is_rejected() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path = key_path.to_str().unwrap();
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path, "--ad", "context"]).stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("unsupported --ad"));
+}
+
+#[test]
+fn wrong_key_length_is_rejected() {
+    let dir = std::env::temp_dir();
+    let key_path = dir.join("short_key");
+    std::fs::write(&key_path, [0x42u8; 16]).unwrap();
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path.to_str().unwrap()]).stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("must hold exactly 32 bytes"));
+}
+
+// `--passphrase` itself prompts on /dev/tty, not stdin, so a roundtrip
+// through it needs a real pty rather than the piped stdio `Command`
+// gives a plain `cargo test` run -- not exercised here. The
+// `--key-file`/`--passphrase` validation below runs before any prompt
+// and needs no tty.
+
+#[test]
+fn key_file_and_passphrase_are_mutually_exclusive() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path = key_path.to_str().unwrap();
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path, "--passphrase"]).stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("mutually exclusive"));
+}
+
+#[test]
+fn one_of_key_file_or_passphrase_is_required() {
+    let (_, stderr, ok) = run(stream_cmd(&["encrypt"]).stdin(Stdio::piped()), b"");
+    assert!(!ok);
+    assert!(String::from_utf8_lossy(&stderr).contains("is required"));
+}
+
+#[test]
+fn keygen_writes_a_key_file() {
+    let dir = std::env::temp_dir();
+    let out_path = dir.join(format!("keygen-test-{}", std::process::id()));
+
+    let (_, stderr, ok) = run(
+        stream_cmd(&["keygen", "--out", out_path.to_str().unwrap()]).stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(ok, "keygen failed: {}", String::from_utf8_lossy(&stderr));
+
+    let key = std::fs::read(&out_path).unwrap();
+    assert_eq!(key.len(), 32);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&out_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    std::fs::remove_file(&out_path).unwrap();
+}
+
+#[test]
+fn inspect_prints_header_fields_without_a_key() {
+    let dir = std::env::temp_dir();
+    let key_path = write_key(&dir);
+    let key_path_str = key_path.to_str().unwrap();
+
+    let (ciphertext, stderr, ok) = run(
+        stream_cmd(&["encrypt", "--key-file", key_path_str]).stdin(Stdio::piped()),
+        b"hello, world",
+    );
+    assert!(ok, "encrypt failed: {}", String::from_utf8_lossy(&stderr));
+
+    let file_path = dir.join(format!("inspect-test-{}", std::process::id()));
+    std::fs::write(&file_path, &ciphertext).unwrap();
+
+    let (stdout, stderr, ok) = run(
+        stream_cmd(&["inspect", file_path.to_str().unwrap()]).stdin(Stdio::piped()),
+        b"",
+    );
+    assert!(ok, "inspect failed: {}", String::from_utf8_lossy(&stderr));
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    assert!(stdout.contains("version: V1"));
+    assert!(stdout.contains("key id: none"));
+    assert!(stdout.contains("digest: none"));
+    assert!(stdout.contains("padded: false"));
+    assert!(stdout.contains("recipient stanzas: not applicable"));
+    assert!(stdout.contains("estimated plaintext size: 12 bytes"));
+
+    std::fs::remove_file(&file_path).unwrap();
+}
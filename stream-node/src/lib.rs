@@ -0,0 +1,225 @@
+//! Node.js N-API bindings for the `stream` crate's STREAM construction,
+//! built on `napi-rs`, so a Node service can read and write the same
+//! ciphertext format a Rust backend does without shelling out to a
+//! separate process.
+//!
+//! `napi-rs` can't make a Rust type extend a JavaScript base class like
+//! `stream.Transform`, so [`StreamEncryptor`] and [`StreamDecryptor`]
+//! expose a `push`/`finish` chunk-feeding API instead; a thin JS
+//! `Transform` subclass shipped alongside this package calls these
+//! from its `_transform()`/`_flush()` methods and pushes whatever comes
+//! back downstream.
+//!
+//! Keys are pinned to ChaCha20-Poly1305, the same choice the `stream-py`
+//! and `stream-uniffi` bindings make and for the same reason: it's the
+//! only AEAD this crate depends on unconditionally.
+//!
+//! This crate has no `cargo test` suite of its own: napi-rs resolves its
+//! `napi_*` symbols from the Node executable it's loaded into, so a
+//! plain Rust test binary can't link against code that touches them
+//! (see napi-rs's own README, under "Testing"). Exercising
+//! [`StreamEncryptor`] and [`StreamDecryptor`] needs a built `.node`
+//! addon driven from a JavaScript test runner instead.
+
+#![deny(clippy::all)]
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use aead::Key;
+use chacha20poly1305::ChaCha20Poly1305;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use stream::{Reader, Writer};
+
+/// The length, in bytes, of a ChaCha20-Poly1305 key.
+const KEY_LEN: usize = 32;
+
+/// The length, in bytes, of this crate's nonce prefix; must match
+/// `stream::nonce::PREFIX_LEN`, which isn't itself public since
+/// callers only ever need to know its length, not its value.
+const NONCE_PREFIX_LEN: usize = 4;
+
+fn key_from_bytes(key: &[u8]) -> Result<Key<ChaCha20Poly1305>> {
+    if key.len() != KEY_LEN {
+        return Err(Error::from_reason(format!(
+            "key must be {KEY_LEN} bytes, got {}",
+            key.len()
+        )));
+    }
+    Ok(Key::<ChaCha20Poly1305>::clone_from_slice(key))
+}
+
+fn nonce_prefix_from_bytes(nonce_prefix: &[u8]) -> Result<[u8; NONCE_PREFIX_LEN]> {
+    nonce_prefix
+        .try_into()
+        .map_err(|_| Error::from_reason(format!("nonce_prefix must be {NONCE_PREFIX_LEN} bytes")))
+}
+
+fn stream_err(e: io::Error) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+/// A [`Write`] sink that appends to a shared, externally drainable
+/// buffer, so [`StreamEncryptor`] can hand back only the ciphertext
+/// produced since the last drain instead of everything [`Writer`] has
+/// ever written -- [`Writer`] doesn't expose its inner writer, so
+/// there's no other way to peek at what it's written so far.
+#[derive(Clone, Default)]
+struct SharedSink(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedSink {
+    fn drain(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+/// Encrypts chunks fed to it as a STREAM-framed ChaCha20-Poly1305
+/// ciphertext.
+///
+/// Call [`push`](StreamEncryptor::push) from a `Transform`'s
+/// `_transform()` and [`finish`](StreamEncryptor::finish) from its
+/// `_flush()`, pushing whatever bytes come back to the next stream in
+/// the pipeline.
+#[napi]
+pub struct StreamEncryptor {
+    inner: Option<Writer<SharedSink, ChaCha20Poly1305>>,
+    sink: SharedSink,
+}
+
+#[napi]
+impl StreamEncryptor {
+    /// Starts a new stream. Its header is buffered internally and
+    /// returned by the first call to [`push`](StreamEncryptor::push) or
+    /// [`finish`](StreamEncryptor::finish).
+    ///
+    /// `key` must be 32 bytes. `nonce_prefix` must be 4 bytes and
+    /// unique for every stream encrypted under `key`; reusing a `(key,
+    /// nonce_prefix)` pair breaks the security of the underlying AEAD.
+    #[napi(constructor)]
+    pub fn new(key: Buffer, nonce_prefix: Buffer) -> Result<Self> {
+        let key = key_from_bytes(&key)?;
+        let nonce_prefix = nonce_prefix_from_bytes(&nonce_prefix)?;
+        let sink = SharedSink::default();
+        let writer = Writer::<_, ChaCha20Poly1305>::new(sink.clone(), &key, nonce_prefix)
+            .map_err(stream_err)?;
+        Ok(Self {
+            inner: Some(writer),
+            sink,
+        })
+    }
+
+    /// Encrypts `chunk`, returning whatever ciphertext bytes -- the
+    /// header, on the first call, plus zero or more sealed chunks --
+    /// filled during this call.
+    #[napi]
+    pub fn push(&mut self, chunk: Buffer) -> Result<Buffer> {
+        let writer = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| Error::from_reason("push() called on a finished StreamEncryptor"))?;
+        writer.write_all(&chunk).map_err(stream_err)?;
+        Ok(self.sink.drain().into())
+    }
+
+    /// Seals any buffered plaintext as the stream's final chunk,
+    /// returning the remaining ciphertext. After this call, the
+    /// encryptor can no longer be used.
+    #[napi]
+    pub fn finish(&mut self) -> Result<Buffer> {
+        let writer = self
+            .inner
+            .take()
+            .ok_or_else(|| Error::from_reason("finish() called on a finished StreamEncryptor"))?;
+        writer.finish().map_err(stream_err)?;
+        Ok(self.sink.drain().into())
+    }
+}
+
+/// Re-decrypts `ciphertext` from the start and returns the full
+/// plaintext, or `Ok(None)` if `ciphertext` doesn't yet hold a whole
+/// stream.
+fn try_decode(ciphertext: &[u8], key: &Key<ChaCha20Poly1305>) -> io::Result<Option<Vec<u8>>> {
+    let mut reader = match Reader::<_, ChaCha20Poly1305>::new(ciphertext, key) {
+        Ok(r) => r,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut plaintext = Vec::new();
+    match reader.read_to_end(&mut plaintext) {
+        Ok(_) => Ok(Some(plaintext)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Decrypts a STREAM-framed ChaCha20-Poly1305 ciphertext fed to it in
+/// arbitrarily-sized pushes.
+///
+/// Like the `stream-uniffi` and `wasm`-feature decryptors, this buffers
+/// every pushed byte and re-decrypts from the start on each call rather
+/// than driving a long-lived [`Reader`] incrementally, trading
+/// redundant work across calls for not having to duplicate [`Reader`]'s
+/// chunk-boundary bookkeeping here; a service streaming request bodies
+/// through this is unlikely to notice the difference for anything
+/// short of a very large payload.
+#[napi]
+pub struct StreamDecryptor {
+    key: Key<ChaCha20Poly1305>,
+    ciphertext: Vec<u8>,
+    emitted: usize,
+}
+
+#[napi]
+impl StreamDecryptor {
+    /// Prepares to decrypt a stream whose ciphertext will arrive via
+    /// [`push`](StreamDecryptor::push). `key` must be 32 bytes.
+    #[napi(constructor)]
+    pub fn new(key: Buffer) -> Result<Self> {
+        let key = key_from_bytes(&key)?;
+        Ok(Self {
+            key,
+            ciphertext: Vec::new(),
+            emitted: 0,
+        })
+    }
+
+    /// Buffers `chunk` and returns whatever plaintext bytes it makes
+    /// newly available, which may be empty if `chunk` didn't complete
+    /// the header or another whole chunk.
+    #[napi]
+    pub fn push(&mut self, chunk: Buffer) -> Result<Buffer> {
+        self.ciphertext.extend_from_slice(&chunk);
+        match try_decode(&self.ciphertext, &self.key).map_err(stream_err)? {
+            Some(plaintext) => {
+                let new = plaintext[self.emitted..].to_vec();
+                self.emitted = plaintext.len();
+                Ok(new.into())
+            }
+            None => Ok(Vec::new().into()),
+        }
+    }
+
+    /// Returns any plaintext bytes not yet returned by
+    /// [`push`](StreamDecryptor::push), failing if the buffered
+    /// ciphertext doesn't hold a complete stream.
+    #[napi]
+    pub fn finish(&mut self) -> Result<Buffer> {
+        match try_decode(&self.ciphertext, &self.key).map_err(stream_err)? {
+            Some(plaintext) => Ok(plaintext[self.emitted..].to_vec().into()),
+            None => Err(Error::from_reason("stream truncated")),
+        }
+    }
+}
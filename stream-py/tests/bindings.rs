@@ -0,0 +1,136 @@
+use std::sync::Once;
+
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use stream_py::stream_py;
+
+fn run(py: Python<'_>, code: &str, locals: &Bound<'_, pyo3::types::PyDict>) -> PyResult<()> {
+    py.run_bound(code, None, Some(locals))
+}
+
+/// Registers `stream_py` in Python's built-in module table and starts
+/// the interpreter. Both steps can only happen once per process, so
+/// every test in this file shares one `Once` instead of each running
+/// them directly.
+fn init_python() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        pyo3::append_to_inittab!(stream_py);
+        pyo3::prepare_freethreaded_python();
+    });
+}
+
+#[test]
+fn roundtrip_through_python_bytesio() {
+    init_python();
+
+    Python::with_gil(|py| {
+        let locals = pyo3::types::PyDict::new_bound(py);
+        locals
+            .set_item("key", PyBytes::new_bound(py, &[0x42u8; 32]))
+            .unwrap();
+        locals
+            .set_item("nonce_prefix", PyBytes::new_bound(py, &[0x24u8; 4]))
+            .unwrap();
+
+        run(
+            py,
+            "
+import io
+import stream_py
+
+src = io.BytesIO()
+w = stream_py.EncryptWriter(src, key, nonce_prefix)
+w.write(b'hello, world')
+w.finish()
+ciphertext = src.getvalue()
+
+r = stream_py.DecryptReader(io.BytesIO(ciphertext), key)
+plaintext = r.read()
+",
+            &locals,
+        )
+        .unwrap();
+
+        let plaintext: Vec<u8> = locals
+            .get_item("plaintext")
+            .unwrap()
+            .unwrap()
+            .downcast::<PyBytes>()
+            .unwrap()
+            .as_bytes()
+            .to_vec();
+        assert_eq!(plaintext, b"hello, world");
+    });
+}
+
+#[test]
+fn context_manager_finishes_on_exit() {
+    init_python();
+
+    Python::with_gil(|py| {
+        let locals = pyo3::types::PyDict::new_bound(py);
+        locals
+            .set_item("key", PyBytes::new_bound(py, &[0x11u8; 32]))
+            .unwrap();
+        locals
+            .set_item("nonce_prefix", PyBytes::new_bound(py, &[0x22u8; 4]))
+            .unwrap();
+
+        run(
+            py,
+            "
+import io
+import stream_py
+
+src = io.BytesIO()
+with stream_py.EncryptWriter(src, key, nonce_prefix) as w:
+    w.write(b'streamed via a context manager')
+ciphertext = src.getvalue()
+
+with stream_py.DecryptReader(io.BytesIO(ciphertext), key) as r:
+    plaintext = r.read()
+",
+            &locals,
+        )
+        .unwrap();
+
+        let plaintext: Vec<u8> = locals
+            .get_item("plaintext")
+            .unwrap()
+            .unwrap()
+            .downcast::<PyBytes>()
+            .unwrap()
+            .as_bytes()
+            .to_vec();
+        assert_eq!(plaintext, b"streamed via a context manager");
+    });
+}
+
+#[test]
+fn wrong_key_length_is_a_value_error() {
+    init_python();
+
+    Python::with_gil(|py| {
+        let locals = pyo3::types::PyDict::new_bound(py);
+        locals
+            .set_item("short_key", PyBytes::new_bound(py, &[0x42u8; 16]))
+            .unwrap();
+        locals
+            .set_item("nonce_prefix", PyBytes::new_bound(py, &[0x24u8; 4]))
+            .unwrap();
+
+        let err = run(
+            py,
+            "
+import io
+import stream_py
+
+stream_py.EncryptWriter(io.BytesIO(), short_key, nonce_prefix)
+",
+            &locals,
+        )
+        .unwrap_err();
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+    });
+}
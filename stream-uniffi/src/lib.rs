@@ -0,0 +1,239 @@
+//! UniFFI bindings for the `stream` crate's STREAM construction, so a
+//! Kotlin or Swift app can read and write the same ciphertext format a
+//! Rust backend does.
+//!
+//! UniFFI exports objects by shared reference -- Kotlin/Swift callers
+//! hold onto one handle and call methods on it from wherever, so every
+//! method here takes `&self`, not `&mut self` -- which means the
+//! mutable `stream::Writer`/`stream::Reader` state underneath has to
+//! sit behind a [`Mutex`]. That's the only reason for the locking
+//! below; nothing here is meant to be called from more than one thread
+//! at a time.
+//!
+//! [`StreamEncryptor`] and [`StreamDecryptor`] operate on in-memory
+//! buffers rather than wrapping a platform file handle or socket --
+//! the "sans-io" part -- so the same core logic works whether the
+//! caller is writing to an iOS file, an Android `OutputStream`, or
+//! just accumulating bytes to upload; the mobile side is responsible
+//! for moving the returned bytes the rest of the way.
+//!
+//! Keys are pinned to ChaCha20-Poly1305, the same choice
+//! [`stream-py`](https://github.com/ericlagergren/stream-rs) and the
+//! `wasm` feature make and for the same reason: it's the only AEAD
+//! this crate depends on unconditionally.
+
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use aead::Key;
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+uniffi::setup_scaffolding!();
+
+/// The length, in bytes, of a ChaCha20-Poly1305 key.
+const KEY_LEN: usize = 32;
+
+/// The length, in bytes, of this crate's nonce prefix; must match
+/// `stream::nonce::PREFIX_LEN`, which isn't itself public since
+/// callers only ever need to know its length, not its value.
+const NONCE_PREFIX_LEN: usize = 4;
+
+/// The error type returned by this crate's fallible operations.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum StreamUniffiError {
+    /// `key` wasn't [`KEY_LEN`] bytes.
+    #[error("key must be {KEY_LEN} bytes, got {len}")]
+    InvalidKeyLength { len: u32 },
+    /// `nonce_prefix` wasn't [`NONCE_PREFIX_LEN`] bytes.
+    #[error("nonce_prefix must be {NONCE_PREFIX_LEN} bytes")]
+    InvalidNoncePrefixLength,
+    /// A method was called on an [`StreamEncryptor`] that already
+    /// finished.
+    #[error("method called on a finished StreamEncryptor")]
+    AlreadyFinished,
+    /// Encryption or decryption failed; see [`stream::Error`].
+    #[error("{0}")]
+    Stream(String),
+}
+
+fn key_from_bytes(key: &[u8]) -> Result<Key<ChaCha20Poly1305>, StreamUniffiError> {
+    if key.len() != KEY_LEN {
+        return Err(StreamUniffiError::InvalidKeyLength {
+            len: key.len() as u32,
+        });
+    }
+    Ok(Key::<ChaCha20Poly1305>::clone_from_slice(key))
+}
+
+fn nonce_prefix_from_bytes(
+    nonce_prefix: &[u8],
+) -> Result<[u8; NONCE_PREFIX_LEN], StreamUniffiError> {
+    nonce_prefix
+        .try_into()
+        .map_err(|_| StreamUniffiError::InvalidNoncePrefixLength)
+}
+
+fn stream_err(e: std::io::Error) -> StreamUniffiError {
+    StreamUniffiError::Stream(e.to_string())
+}
+
+/// A [`Write`] sink that appends to a shared, externally drainable
+/// buffer, so [`StreamEncryptor`] can hand back only the ciphertext
+/// produced since the last drain instead of everything [`Writer`] has
+/// ever written -- [`Writer`] doesn't expose its inner writer, so
+/// there's no other way to peek at what it's written so far.
+#[derive(Clone, Default)]
+struct SharedSink(std::sync::Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedSink {
+    fn drain(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+/// Encrypts bytes fed to it as a STREAM-framed ChaCha20-Poly1305
+/// ciphertext, buffering the sealed output for [`push`](Self::push)
+/// and [`finish`](Self::finish) to return.
+#[derive(uniffi::Object)]
+pub struct StreamEncryptor {
+    inner: Mutex<Option<Writer<SharedSink, ChaCha20Poly1305>>>,
+    sink: SharedSink,
+}
+
+#[uniffi::export]
+impl StreamEncryptor {
+    /// Starts a new stream, writing its header to an internal buffer
+    /// immediately; the header is returned by the first call to
+    /// [`push`](Self::push) or [`finish`](Self::finish).
+    ///
+    /// `key` must be 32 bytes. `nonce_prefix` must be 4 bytes and
+    /// unique for every stream encrypted under `key`; reusing a `(key,
+    /// nonce_prefix)` pair breaks the security of the underlying AEAD.
+    #[uniffi::constructor]
+    pub fn new(key: Vec<u8>, nonce_prefix: Vec<u8>) -> Result<Self, StreamUniffiError> {
+        let key = key_from_bytes(&key)?;
+        let nonce_prefix = nonce_prefix_from_bytes(&nonce_prefix)?;
+        let sink = SharedSink::default();
+        let writer = Writer::<_, ChaCha20Poly1305>::new(sink.clone(), &key, nonce_prefix)
+            .map_err(stream_err)?;
+        Ok(Self {
+            inner: Mutex::new(Some(writer)),
+            sink,
+        })
+    }
+
+    /// Encrypts `chunk`, returning whatever ciphertext bytes -- the
+    /// header, on the first call, plus zero or more sealed chunks --
+    /// filled during this call.
+    pub fn push(&self, chunk: Vec<u8>) -> Result<Vec<u8>, StreamUniffiError> {
+        let mut guard = self.inner.lock().unwrap();
+        let writer = guard.as_mut().ok_or(StreamUniffiError::AlreadyFinished)?;
+        writer.write_all(&chunk).map_err(stream_err)?;
+        Ok(self.sink.drain())
+    }
+
+    /// Seals any buffered plaintext as the stream's final chunk,
+    /// returning the remaining ciphertext. After this call, every
+    /// other method returns [`StreamUniffiError::AlreadyFinished`].
+    pub fn finish(&self) -> Result<Vec<u8>, StreamUniffiError> {
+        let writer = self
+            .inner
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(StreamUniffiError::AlreadyFinished)?;
+        writer.finish().map_err(stream_err)?;
+        Ok(self.sink.drain())
+    }
+}
+
+/// Re-decrypts `ciphertext` from the start and returns the full
+/// plaintext, or `Ok(None)` if `ciphertext` doesn't yet hold a whole
+/// stream.
+fn try_decode(ciphertext: &[u8], key: &Key<ChaCha20Poly1305>) -> io::Result<Option<Vec<u8>>> {
+    let mut reader = match Reader::<_, ChaCha20Poly1305>::new(ciphertext, key) {
+        Ok(r) => r,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut plaintext = Vec::new();
+    match reader.read_to_end(&mut plaintext) {
+        Ok(_) => Ok(Some(plaintext)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Decrypts a STREAM-framed ChaCha20-Poly1305 ciphertext fed to it in
+/// arbitrarily-sized pushes.
+///
+/// Like the `wasm` feature's decryptor, this buffers every pushed byte
+/// and re-decrypts from the start on each call rather than driving a
+/// long-lived [`Reader`] incrementally, trading redundant work across
+/// calls for not having to duplicate [`Reader`]'s chunk-boundary
+/// bookkeeping here; files passed between a phone and its backend are
+/// short enough for this not to matter.
+#[derive(uniffi::Object)]
+pub struct StreamDecryptor {
+    key: Key<ChaCha20Poly1305>,
+    state: Mutex<DecryptState>,
+}
+
+#[derive(Default)]
+struct DecryptState {
+    ciphertext: Vec<u8>,
+    emitted: usize,
+}
+
+#[uniffi::export]
+impl StreamDecryptor {
+    /// Prepares to decrypt a stream whose ciphertext will arrive via
+    /// [`push`](Self::push). `key` must be 32 bytes.
+    #[uniffi::constructor]
+    pub fn new(key: Vec<u8>) -> Result<Self, StreamUniffiError> {
+        let key = key_from_bytes(&key)?;
+        Ok(Self {
+            key,
+            state: Mutex::new(DecryptState::default()),
+        })
+    }
+
+    /// Buffers `chunk` and returns whatever plaintext bytes it makes
+    /// newly available, which may be empty if `chunk` didn't complete
+    /// the header or another whole chunk.
+    pub fn push(&self, chunk: Vec<u8>) -> Result<Vec<u8>, StreamUniffiError> {
+        let mut state = self.state.lock().unwrap();
+        state.ciphertext.extend_from_slice(&chunk);
+        match try_decode(&state.ciphertext, &self.key).map_err(stream_err)? {
+            Some(plaintext) => {
+                let new = plaintext[state.emitted..].to_vec();
+                state.emitted = plaintext.len();
+                Ok(new)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns any plaintext bytes not yet returned by
+    /// [`push`](Self::push), failing if the buffered ciphertext
+    /// doesn't hold a complete stream.
+    pub fn finish(&self) -> Result<Vec<u8>, StreamUniffiError> {
+        let state = self.state.lock().unwrap();
+        match try_decode(&state.ciphertext, &self.key).map_err(stream_err)? {
+            Some(plaintext) => Ok(plaintext[state.emitted..].to_vec()),
+            None => Err(StreamUniffiError::Stream("stream truncated".to_string())),
+        }
+    }
+}
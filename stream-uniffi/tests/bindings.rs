@@ -0,0 +1,56 @@
+use stream_uniffi::{StreamDecryptor, StreamEncryptor};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn roundtrip_in_one_push_each() {
+    let enc = StreamEncryptor::new(KEY.to_vec(), NONCE_PREFIX.to_vec()).unwrap();
+    let mut ciphertext = enc.push(b"hello, world".to_vec()).unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+
+    let dec = StreamDecryptor::new(KEY.to_vec()).unwrap();
+    let mut plaintext = dec.push(ciphertext).unwrap();
+    plaintext.extend(dec.finish().unwrap());
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn decryptor_yields_nothing_until_a_chunk_has_fully_arrived() {
+    let enc = StreamEncryptor::new(KEY.to_vec(), NONCE_PREFIX.to_vec()).unwrap();
+    let mut ciphertext = enc.push(b"hello, world".to_vec()).unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+
+    let dec = StreamDecryptor::new(KEY.to_vec()).unwrap();
+    let (first, rest) = ciphertext.split_at(ciphertext.len() / 2);
+    assert!(dec.push(first.to_vec()).unwrap().is_empty());
+    let mut plaintext = dec.push(rest.to_vec()).unwrap();
+    plaintext.extend(dec.finish().unwrap());
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn decryptor_finish_fails_on_truncated_ciphertext() {
+    let enc = StreamEncryptor::new(KEY.to_vec(), NONCE_PREFIX.to_vec()).unwrap();
+    let mut ciphertext = enc.push(b"hello, world".to_vec()).unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+    ciphertext.truncate(ciphertext.len() - 1);
+
+    let dec = StreamDecryptor::new(KEY.to_vec()).unwrap();
+    let pushed = dec.push(ciphertext);
+    assert!(pushed.is_err() || dec.finish().is_err());
+}
+
+#[test]
+fn wrong_key_length_is_an_error() {
+    assert!(StreamEncryptor::new(vec![0x42; 16], NONCE_PREFIX.to_vec()).is_err());
+    assert!(StreamDecryptor::new(vec![0x42; 16]).is_err());
+}
+
+#[test]
+fn methods_after_finish_are_an_error() {
+    let enc = StreamEncryptor::new(KEY.to_vec(), NONCE_PREFIX.to_vec()).unwrap();
+    enc.finish().unwrap();
+    assert!(enc.push(b"too late".to_vec()).is_err());
+    assert!(enc.finish().is_err());
+}
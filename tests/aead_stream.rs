@@ -0,0 +1,69 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{AeadStreamReader, AeadStreamWriter, Endian};
+
+const KEY: [u8; 32] = [0x42; 32];
+const PREFIX: [u8; 8] = [0x24; 8];
+const CHUNK_SIZE: usize = 256;
+
+fn roundtrip(endian: Endian, plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w =
+        AeadStreamWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, endian, PREFIX, CHUNK_SIZE);
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = AeadStreamReader::<_, ChaCha20Poly1305>::new(
+        &ciphertext[..],
+        &key,
+        endian,
+        PREFIX,
+        CHUNK_SIZE,
+    );
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn be32_roundtrip() {
+    let plaintext = b"hello, world";
+    assert_eq!(roundtrip(Endian::Big, plaintext), plaintext);
+}
+
+#[test]
+fn le31_roundtrip() {
+    let plaintext = b"hello, world";
+    assert_eq!(roundtrip(Endian::Little, plaintext), plaintext);
+}
+
+#[test]
+fn be32_multi_chunk_roundtrip() {
+    let plaintext = vec![0x5a; CHUNK_SIZE * 3 + 17];
+    assert_eq!(roundtrip(Endian::Big, &plaintext), plaintext);
+}
+
+#[test]
+fn mismatched_endian_fails_to_decrypt() {
+    let key = KEY.into();
+    let mut w = AeadStreamWriter::<_, ChaCha20Poly1305>::new(
+        Vec::new(),
+        &key,
+        Endian::Big,
+        PREFIX,
+        CHUNK_SIZE,
+    );
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = AeadStreamReader::<_, ChaCha20Poly1305>::new(
+        &ciphertext[..],
+        &key,
+        Endian::Little,
+        PREFIX,
+        CHUNK_SIZE,
+    );
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "age")]
+
+use std::io::{Read, Write};
+
+use stream::{AgeReader, AgeWriter, AGE_CHUNK_SIZE, FILE_KEY_LEN, PAYLOAD_NONCE_LEN};
+
+const FILE_KEY: [u8; FILE_KEY_LEN] = [0x42; FILE_KEY_LEN];
+const PAYLOAD_NONCE: [u8; PAYLOAD_NONCE_LEN] = [0x24; PAYLOAD_NONCE_LEN];
+
+#[test]
+fn age_roundtrip() {
+    let mut w = AgeWriter::new(Vec::new(), &FILE_KEY, PAYLOAD_NONCE).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = AgeReader::new(&ciphertext[..], &FILE_KEY).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn age_empty_payload_roundtrip() {
+    let w = AgeWriter::new(Vec::new(), &FILE_KEY, PAYLOAD_NONCE).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = AgeReader::new(&ciphertext[..], &FILE_KEY).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn age_chunk_boundary_gets_explicit_empty_final_chunk() {
+    let mut w = AgeWriter::new(Vec::new(), &FILE_KEY, PAYLOAD_NONCE).unwrap();
+    let plaintext = vec![0x5a; AGE_CHUNK_SIZE];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    // One full chunk plus an explicit empty final chunk: nonce + two
+    // ciphertexts, each with a 16-byte tag.
+    assert_eq!(
+        ciphertext.len(),
+        PAYLOAD_NONCE_LEN + (AGE_CHUNK_SIZE + 16) + 16
+    );
+
+    let mut r = AgeReader::new(&ciphertext[..], &FILE_KEY).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn age_multi_chunk_roundtrip() {
+    let mut w = AgeWriter::new(Vec::new(), &FILE_KEY, PAYLOAD_NONCE).unwrap();
+    let plaintext = vec![0x99; AGE_CHUNK_SIZE * 2 + 17];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = AgeReader::new(&ciphertext[..], &FILE_KEY).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn age_wrong_key_fails_to_decrypt() {
+    let mut w = AgeWriter::new(Vec::new(), &FILE_KEY, PAYLOAD_NONCE).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let wrong_key = [0x43; FILE_KEY_LEN];
+    let mut r = AgeReader::new(&ciphertext[..], &wrong_key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "armor")]
+
+use std::io::{Read, Write};
+
+use stream::{
+    ArmorReader, ArmorWriter, BEGIN_MARKER, BEGIN_MESSAGE_MARKER, END_MARKER, END_MESSAGE_MARKER,
+};
+
+#[test]
+fn armor_roundtrip() {
+    let data = b"hello, world";
+    let mut w = ArmorWriter::new(Vec::new()).unwrap();
+    w.write_all(data).unwrap();
+    let armored = w.finish().unwrap();
+
+    let text = String::from_utf8(armored.clone()).unwrap();
+    assert!(text.starts_with(BEGIN_MARKER));
+    assert!(text.trim_end().ends_with(END_MARKER));
+
+    let mut r = ArmorReader::new(&armored[..]).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn armor_wraps_long_lines_at_64_columns() {
+    let data = vec![0x5a; 300];
+    let mut w = ArmorWriter::new(Vec::new()).unwrap();
+    w.write_all(&data).unwrap();
+    let armored = w.finish().unwrap();
+
+    let text = String::from_utf8(armored.clone()).unwrap();
+    for line in text.lines().filter(|l| !l.starts_with("-----")) {
+        assert!(line.len() <= 64);
+    }
+
+    let mut r = ArmorReader::new(&armored[..]).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn armor_empty_payload_roundtrip() {
+    let w = ArmorWriter::new(Vec::new()).unwrap();
+    let armored = w.finish().unwrap();
+
+    let mut r = ArmorReader::new(&armored[..]).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn armor_rejects_missing_begin_marker() {
+    let data = b"not armored at all\n";
+    assert!(ArmorReader::new(&data[..]).is_err());
+}
+
+#[test]
+fn armor_message_roundtrip_with_headers() {
+    let data = b"hello, world";
+    let headers = [("version", "1"), ("key-id", "0102030405060708")];
+    let mut w = ArmorWriter::with_headers(Vec::new(), &headers).unwrap();
+    w.write_all(data).unwrap();
+    let armored = w.finish().unwrap();
+
+    let text = String::from_utf8(armored.clone()).unwrap();
+    assert!(text.starts_with(BEGIN_MESSAGE_MARKER));
+    assert!(text.trim_end().ends_with(END_MESSAGE_MARKER));
+
+    let mut r = ArmorReader::new(&armored[..]).unwrap();
+    assert_eq!(
+        r.headers(),
+        &[
+            ("version".to_string(), "1".to_string()),
+            ("key-id".to_string(), "0102030405060708".to_string()),
+        ]
+    );
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn armor_message_with_no_headers_still_parses() {
+    let mut w = ArmorWriter::with_headers(Vec::new(), &[]).unwrap();
+    w.write_all(b"hi").unwrap();
+    let armored = w.finish().unwrap();
+
+    let mut r = ArmorReader::new(&armored[..]).unwrap();
+    assert!(r.headers().is_empty());
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hi");
+}
+
+#[test]
+fn armor_rejects_truncated_body() {
+    let mut w = ArmorWriter::new(Vec::new()).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let armored = w.finish().unwrap();
+
+    // Drop the END_MARKER line.
+    let text = String::from_utf8(armored).unwrap();
+    let truncated = text.lines().next().unwrap().to_string() + "\n";
+
+    let mut r = ArmorReader::new(truncated.as_bytes()).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
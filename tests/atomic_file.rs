@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{decrypt_file, encrypt_file};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "stream-atomic-file-test-{}-{name}",
+        std::process::id()
+    ))
+}
+
+#[test]
+fn round_trips_a_file_through_encrypt_and_decrypt() {
+    let src = temp_path("roundtrip-src");
+    let ciphertext = temp_path("roundtrip-ct");
+    let plaintext_out = temp_path("roundtrip-pt");
+    std::fs::write(&src, b"the quick brown fox jumps over the lazy dog").unwrap();
+
+    let key = KEY.into();
+    encrypt_file::<ChaCha20Poly1305>(&src, &ciphertext, &key, NONCE_PREFIX).unwrap();
+    decrypt_file::<ChaCha20Poly1305>(&ciphertext, &plaintext_out, &key).unwrap();
+
+    assert_eq!(
+        std::fs::read(&plaintext_out).unwrap(),
+        std::fs::read(&src).unwrap()
+    );
+}
+
+#[test]
+fn encrypt_file_leaves_an_existing_dst_untouched_on_failure() {
+    let src = temp_path("failure-src");
+    let dst = temp_path("failure-dst");
+    // `src` doesn't exist, so `encrypt_file` should fail before ever
+    // touching `dst`.
+    std::fs::write(&dst, b"original contents").unwrap();
+
+    let key = KEY.into();
+    assert!(encrypt_file::<ChaCha20Poly1305>(&src, &dst, &key, NONCE_PREFIX).is_err());
+
+    assert_eq!(std::fs::read(&dst).unwrap(), b"original contents");
+    assert!(!tmp_path_for(&dst).exists());
+}
+
+fn tmp_path_for(dst: &std::path::Path) -> PathBuf {
+    let mut name = dst.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+#[test]
+fn decrypt_file_rejects_a_tampered_ciphertext_without_writing_dst() {
+    let src = temp_path("tamper-src");
+    let ciphertext = temp_path("tamper-ct");
+    let plaintext_out = temp_path("tamper-pt");
+    let _ = std::fs::remove_file(&plaintext_out);
+    std::fs::write(&src, b"some plaintext").unwrap();
+
+    let key = KEY.into();
+    encrypt_file::<ChaCha20Poly1305>(&src, &ciphertext, &key, NONCE_PREFIX).unwrap();
+    let mut bytes = std::fs::read(&ciphertext).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xff;
+    std::fs::write(&ciphertext, &bytes).unwrap();
+
+    assert!(decrypt_file::<ChaCha20Poly1305>(&ciphertext, &plaintext_out, &key).is_err());
+    assert!(!plaintext_out.exists());
+}
@@ -0,0 +1,56 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, Reader, Version, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn writer_and_reader_roundtrip_with_bound_nonces() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_bound_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_bound_nonces(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn a_bound_nonces_stream_is_written_as_version_2() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_bound_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert_eq!(info.version, Version::V2);
+    assert!(info.derived_nonce);
+}
+
+#[test]
+fn reader_new_rejects_a_bound_nonces_stream() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_bound_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert!(Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).is_err());
+}
+
+#[test]
+fn with_bound_nonces_rejects_a_plain_derived_nonces_stream() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_derived_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert!(Reader::<_, ChaCha20Poly1305>::with_bound_nonces(&ciphertext[..], &key).is_err());
+}
@@ -0,0 +1,103 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, Reader, Version, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn writer_and_reader_roundtrip_with_comment() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_comment(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        b"2024 Q1 payroll backup",
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert_eq!(r.comment(), b"2024 Q1 payroll backup");
+}
+
+#[test]
+fn a_comment_stream_is_written_as_version_6_with_the_comment_visible_to_peek_header() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_comment(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        b"not a secret",
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert_eq!(info.version, Version::V6);
+    assert_eq!(info.comment, b"not a secret");
+}
+
+#[test]
+fn an_empty_comment_round_trips_too() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_comment(Vec::new(), &key, NONCE_PREFIX, b"").unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert!(r.comment().is_empty());
+}
+
+#[test]
+fn tampering_with_the_comment_is_caught_only_once_the_first_chunk_is_decrypted() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_comment(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        b"2024 Q1 payroll backup",
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // The comment immediately follows the fixed-size header and its
+    // 2-byte length prefix; flip a bit in its first byte. Unlike a
+    // tampered `Version::V4` extension area, this isn't caught by the
+    // header itself -- there's no key-check value involved -- so
+    // opening the stream still succeeds.
+    let tamper_offset = stream::HEADER_LEN + 2;
+    ciphertext[tamper_offset] ^= 0xff;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn a_plain_stream_has_no_comment() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert!(info.comment.is_empty());
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert!(r.comment().is_empty());
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "compression")]
+
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{CompressReader, CompressWriter, Reader, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn compression_roundtrip() {
+    let plaintext = b"hello, world".repeat(100);
+    let mut w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn compression_shrinks_compressible_plaintext() {
+    let plaintext = vec![0x41u8; CHUNK_SIZE * 2];
+    let mut w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert!(ciphertext.len() < plaintext.len() / 2);
+
+    let mut r = CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn compression_empty_payload_roundtrip() {
+    let w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn compression_multi_chunk_roundtrip() {
+    let plaintext: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| (i % 251) as u8).collect();
+    let mut w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn compression_rejects_tampered_ciphertext() {
+    let mut w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 0x01;
+
+    let mut r = CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn compression_reader_rejects_uncompressed_stream() {
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert!(CompressReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).is_err());
+}
+
+#[test]
+fn plain_reader_still_opens_compressed_stream_as_raw_deflate() {
+    let mut w =
+        CompressWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    // The plain Reader doesn't know about compression, so it decrypts
+    // fine but hands back the still-compressed bytes.
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_ne!(out, b"hello, world");
+}
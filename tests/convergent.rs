@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{convergent_nonce_prefix, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+
+fn seal(key: [u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    let nonce_prefix = convergent_nonce_prefix(&key, plaintext);
+    let aead_key = key.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &aead_key, nonce_prefix).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+#[test]
+fn identical_plaintexts_produce_identical_ciphertexts() {
+    let plaintext = b"the quick brown fox jumps over the lazy dog";
+    assert_eq!(seal(KEY, plaintext), seal(KEY, plaintext));
+}
+
+#[test]
+fn different_plaintexts_produce_different_ciphertexts() {
+    let a = seal(KEY, b"the quick brown fox");
+    let b = seal(KEY, b"the lazy dog");
+    assert_ne!(a, b);
+}
+
+#[test]
+fn a_convergently_sealed_stream_decrypts_normally() {
+    let plaintext = b"dedup me if you can";
+    let ciphertext = seal(KEY, plaintext);
+
+    let key = KEY.into();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
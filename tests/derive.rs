@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn derived_nonce_roundtrip() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_derived_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn derived_nonce_multi_chunk_roundtrip() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_derived_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let plaintext = vec![0x77; stream::CHUNK_SIZE * 2 + 31];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn derived_nonce_ciphertext_differs_from_concatenated() {
+    let key = KEY.into();
+
+    let mut w1 = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w1.write_all(b"hello, world").unwrap();
+    let concatenated = w1.finish().unwrap();
+
+    let mut w2 =
+        Writer::<_, ChaCha20Poly1305>::with_derived_nonces(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w2.write_all(b"hello, world").unwrap();
+    let derived = w2.finish().unwrap();
+
+    assert_ne!(concatenated, derived);
+}
@@ -0,0 +1,118 @@
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{EncryptedFile, Reader, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+fn open(ciphertext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(ciphertext, &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn reads_back_the_original_plaintext() {
+    let plaintext = b"the quick brown fox jumps over the lazy dog";
+    let ciphertext = seal(plaintext);
+
+    let key = KEY.into();
+    let mut f = EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).unwrap();
+    assert_eq!(f.len(), plaintext.len() as u64);
+    let mut out = Vec::new();
+    f.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn overwriting_a_range_changes_only_that_range() {
+    let plaintext = vec![b'a'; CHUNK_SIZE * 2 + 100];
+    let ciphertext = seal(&plaintext);
+
+    let key = KEY.into();
+    let mut f = EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).unwrap();
+    f.seek(SeekFrom::Start(10)).unwrap();
+    f.write_all(b"PATCHED").unwrap();
+    f.flush().unwrap();
+
+    let ciphertext = f.into_inner().unwrap().into_inner();
+    let decrypted = open(&ciphertext);
+
+    let mut expected = plaintext;
+    expected[10..17].copy_from_slice(b"PATCHED");
+    assert_eq!(decrypted, expected);
+}
+
+#[test]
+fn a_write_crossing_a_chunk_boundary_patches_both_chunks() {
+    let plaintext = vec![b'x'; CHUNK_SIZE * 2];
+    let ciphertext = seal(&plaintext);
+
+    let key = KEY.into();
+    let mut f = EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).unwrap();
+    f.seek(SeekFrom::Start((CHUNK_SIZE - 3) as u64)).unwrap();
+    f.write_all(b"123456").unwrap();
+    f.flush().unwrap();
+
+    let ciphertext = f.into_inner().unwrap().into_inner();
+    let decrypted = open(&ciphertext);
+
+    let mut expected = plaintext;
+    expected[CHUNK_SIZE - 3..CHUNK_SIZE + 3].copy_from_slice(b"123456");
+    assert_eq!(decrypted, expected);
+}
+
+#[test]
+fn writing_at_or_past_the_end_is_rejected() {
+    let plaintext = b"short stream";
+    let ciphertext = seal(plaintext);
+
+    let key = KEY.into();
+    let mut f = EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).unwrap();
+    f.seek(SeekFrom::End(0)).unwrap();
+    assert!(f.write_all(b"more").is_err());
+}
+
+#[test]
+fn rewriting_the_same_chunk_twice_is_rejected() {
+    let plaintext = vec![b'a'; CHUNK_SIZE * 2];
+    let ciphertext = seal(&plaintext);
+
+    let key = KEY.into();
+    let mut f = EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).unwrap();
+    f.seek(SeekFrom::Start(0)).unwrap();
+    f.write_all(b"first").unwrap();
+    // Evicts chunk 0 from the cache, resealing and writing it back.
+    f.seek(SeekFrom::Start(CHUNK_SIZE as u64)).unwrap();
+    f.write_all(b"second").unwrap();
+
+    // Chunk 0 was already resealed once above; writing to it again
+    // reuses its nonce, so this must be rejected rather than silently
+    // resealing it a second time.
+    f.seek(SeekFrom::Start(0)).unwrap();
+    f.write_all(b"third").unwrap();
+    let err = f.flush().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+}
+
+#[test]
+fn a_padded_stream_is_rejected() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    assert!(EncryptedFile::<_, ChaCha20Poly1305>::open(Cursor::new(ciphertext), &key).is_err());
+}
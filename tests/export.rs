@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn writer_and_reader_agree_on_an_exported_subkey() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let writer_key = w.export_key(b"filename encryption");
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    assert_eq!(writer_key, r.export_key(b"filename encryption"));
+}
+
+#[test]
+fn different_contexts_export_different_subkeys() {
+    let key = KEY.into();
+    let w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    assert_ne!(
+        w.export_key(b"filename encryption"),
+        w.export_key(b"metadata mac")
+    );
+}
+
+#[test]
+fn different_streams_export_different_subkeys_for_the_same_context() {
+    let key = KEY.into();
+    let a = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, [0x24; 4]).unwrap();
+    let b = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, [0x11; 4]).unwrap();
+    assert_ne!(a.export_key(b"metadata mac"), b.export_key(b"metadata mac"));
+}
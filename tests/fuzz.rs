@@ -0,0 +1,27 @@
+#![cfg(feature = "fuzz")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use stream::{Reader, ReaderOpts, StreamDescription, Version};
+
+#[test]
+fn stream_description_ciphertext_round_trips_through_reader() {
+    let bytes = [0x11u8; 512];
+    let mut u = Unstructured::new(&bytes);
+    let desc = StreamDescription::arbitrary(&mut u).unwrap();
+
+    let ciphertext = desc.to_ciphertext();
+    let key = desc.key.into();
+    let mut r =
+        Reader::<_, chacha20poly1305::ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    std::io::Read::read_to_end(&mut r, &mut out).unwrap();
+    assert_eq!(out, desc.plaintext);
+}
+
+#[test]
+fn version_and_reader_opts_are_arbitrary() {
+    let bytes = [0x22u8; 64];
+    let mut u = Unstructured::new(&bytes);
+    let _version = Version::arbitrary(&mut u).unwrap();
+    let _opts = ReaderOpts::arbitrary(&mut u).unwrap();
+}
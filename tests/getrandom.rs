@@ -0,0 +1,35 @@
+#![cfg(feature = "getrandom")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+
+#[test]
+fn new_default_roundtrip() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new_default(Vec::new(), &key).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn new_default_uses_distinct_nonce_prefixes() {
+    let key = KEY.into();
+    let mut a = Writer::<_, ChaCha20Poly1305>::new_default(Vec::new(), &key).unwrap();
+    a.write_all(b"same plaintext").unwrap();
+    let ciphertext_a = a.finish().unwrap();
+
+    let mut b = Writer::<_, ChaCha20Poly1305>::new_default(Vec::new(), &key).unwrap();
+    b.write_all(b"same plaintext").unwrap();
+    let ciphertext_b = b.finish().unwrap();
+
+    assert_ne!(ciphertext_a, ciphertext_b);
+}
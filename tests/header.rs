@@ -0,0 +1,33 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+
+#[test]
+fn writer_and_reader_agree_on_a_streams_id() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, [0x24; 4]).unwrap();
+    let writer_id = w.stream_id();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    assert_eq!(writer_id, r.stream_id());
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert_eq!(writer_id, info.stream_id());
+}
+
+#[test]
+fn different_nonce_prefixes_produce_different_stream_ids() {
+    let key = KEY.into();
+    let a = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, [0x24; 4])
+        .unwrap()
+        .stream_id();
+    let b = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, [0x11; 4])
+        .unwrap()
+        .stream_id();
+    assert_ne!(a, b);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "pq_hybrid")]
+
+use std::io::Write;
+
+use aead::Key;
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{unwrap_key, wrap_key, Identity, Reader, Writer};
+
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn wrap_and_unwrap_key_roundtrips() {
+    let identity = Identity::generate();
+    let recipient = identity.recipient();
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x42; 32]);
+    let wrapped = wrap_key::<ChaCha20Poly1305>(&recipient, &dek).unwrap();
+    let unwrapped = unwrap_key::<ChaCha20Poly1305>(&identity, &wrapped).unwrap();
+    assert_eq!(dek, unwrapped);
+}
+
+#[test]
+fn unwrapping_with_the_wrong_identity_fails() {
+    let identity = Identity::generate();
+    let recipient = identity.recipient();
+    let other_identity = Identity::generate();
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x11; 32]);
+    let wrapped = wrap_key::<ChaCha20Poly1305>(&recipient, &dek).unwrap();
+    assert!(unwrap_key::<ChaCha20Poly1305>(&other_identity, &wrapped).is_err());
+}
+
+#[test]
+fn tampered_wrapped_bytes_fail_to_unwrap() {
+    let identity = Identity::generate();
+    let recipient = identity.recipient();
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x99; 32]);
+    let mut wrapped = wrap_key::<ChaCha20Poly1305>(&recipient, &dek).unwrap();
+    let last = wrapped.len() - 1;
+    wrapped[last] ^= 0x01;
+    assert!(unwrap_key::<ChaCha20Poly1305>(&identity, &wrapped).is_err());
+}
+
+#[test]
+fn truncated_wrapped_bytes_fail_to_unwrap() {
+    let identity = Identity::generate();
+    let recipient = identity.recipient();
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x07; 32]);
+    let wrapped = wrap_key::<ChaCha20Poly1305>(&recipient, &dek).unwrap();
+    assert!(unwrap_key::<ChaCha20Poly1305>(&identity, &wrapped[..wrapped.len() / 2]).is_err());
+}
+
+#[test]
+fn a_key_wrapped_to_a_recipient_can_seal_a_stream_the_holder_of_its_identity_can_open() {
+    let identity = Identity::generate();
+    let recipient = identity.recipient();
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x24; 32]);
+    let wrapped = wrap_key::<ChaCha20Poly1305>(&recipient, &dek).unwrap();
+
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &dek, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let recovered_dek = unwrap_key::<ChaCha20Poly1305>(&identity, &wrapped).unwrap();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &recovered_dek).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "interop")]
+
+//! Cross-implementation interop harness, gated behind the `interop`
+//! feature.
+//!
+//! The emitting side already exists as `examples/golden_vectors`: run
+//! `cargo run --example golden_vectors --features vectors > vectors.json`
+//! and hand the file to another `stream` implementation (e.g. a Go
+//! port) to verify against its own decoder. This test is the
+//! consuming side: point `STREAM_INTEROP_VECTORS` at a
+//! [`Vector`](stream::Vector)-format JSON file produced by that other
+//! implementation's own generator, and this crate's [`Reader`] is
+//! checked against every case in it. Wiring both sides into CI on
+//! every release catches a wire-format regression in either
+//! implementation before it ships.
+//!
+//! Without `STREAM_INTEROP_VECTORS` set, there's no other
+//! implementation's output to check against, so the test is skipped
+//! rather than failed.
+
+use std::{env, fs};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{load_vectors, run_vector};
+
+#[test]
+fn vectors_from_another_implementation_decrypt_correctly() {
+    let Ok(path) = env::var("STREAM_INTEROP_VECTORS") else {
+        eprintln!(
+            "STREAM_INTEROP_VECTORS not set; skipping cross-implementation interop check"
+        );
+        return;
+    };
+    let json = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {path}: {e}"));
+    let vectors = load_vectors(&json).unwrap_or_else(|e| panic!("parsing {path}: {e}"));
+    assert!(!vectors.is_empty(), "{path} contained no vectors");
+    for vector in &vectors {
+        assert!(
+            run_vector::<ChaCha20Poly1305>(vector),
+            "vector {:?} did not match its expected outcome",
+            vector.name
+        );
+    }
+}
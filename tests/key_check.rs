@@ -0,0 +1,56 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn the_right_key_opens_a_key_checked_stream_normally() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_check(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn the_wrong_key_is_rejected_by_new_before_any_chunk_is_decrypted() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_check(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let wrong_key = [0x11; 32].into();
+    assert!(Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &wrong_key).is_err());
+}
+
+#[test]
+fn a_stream_without_a_key_check_has_no_key_checked_flag_set() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert!(!info.key_checked);
+}
+
+#[test]
+fn a_key_checked_stream_reports_the_flag_in_its_header() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_check(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert!(info.key_checked);
+}
@@ -0,0 +1,74 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Keyring, Reader, Writer};
+
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn keyring_selects_matching_key() {
+    let key_a = [0x11; 32].into();
+    let key_b = [0x22; 32].into();
+    let key_id_a = [1u8; 8];
+    let key_id_b = [2u8; 8];
+
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_id(Vec::new(), &key_b, NONCE_PREFIX, key_id_b)
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut keyring = Keyring::<ChaCha20Poly1305>::new();
+    keyring.add(key_id_a, key_a);
+    keyring.add(key_id_b, key_b);
+
+    let mut r = keyring.open(&ciphertext[..]).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn keyring_rejects_unknown_key_id() {
+    let key_a = [0x11; 32].into();
+    let other_key = [0x99; 32].into();
+
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_id(Vec::new(), &other_key, NONCE_PREFIX, [9u8; 8])
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut keyring = Keyring::<ChaCha20Poly1305>::new();
+    keyring.add([1u8; 8], key_a);
+
+    assert!(keyring.open(&ciphertext[..]).is_err());
+}
+
+#[test]
+fn keyring_rejects_stream_without_key_id() {
+    let key = [0x11; 32].into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut keyring = Keyring::<ChaCha20Poly1305>::new();
+    keyring.add([1u8; 8], key);
+
+    assert!(keyring.open(&ciphertext[..]).is_err());
+}
+
+#[test]
+fn plain_reader_still_opens_key_id_stream() {
+    let key = [0x11; 32].into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_key_id(Vec::new(), &key, NONCE_PREFIX, [7u8; 8])
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
@@ -0,0 +1,205 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Chunker, LengthPrefixedReader, LengthPrefixedWriter};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn roundtrip(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w =
+        LengthPrefixedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn short_plaintext_roundtrips() {
+    let plaintext = b"hello, world";
+    assert_eq!(roundtrip(plaintext), plaintext);
+}
+
+#[test]
+fn empty_plaintext_roundtrips() {
+    assert_eq!(roundtrip(b""), b"");
+}
+
+#[test]
+fn plaintext_spanning_multiple_chunks_roundtrips() {
+    let plaintext = vec![0x5a; 64 * 1024 * 2 + 17];
+    assert_eq!(roundtrip(&plaintext), plaintext);
+}
+
+#[test]
+fn a_reader_does_not_need_to_know_the_writers_chunk_size() {
+    // The whole point of the length prefix: nothing about opening this
+    // stream depends on a chunk-size constant shared out of band, only
+    // on what's already on the wire.
+    let plaintext = vec![0x11; 100_000];
+    assert_eq!(roundtrip(&plaintext), plaintext);
+}
+
+#[test]
+fn tampered_length_prefix_fails_to_authenticate() {
+    let key = KEY.into();
+    let mut w =
+        LengthPrefixedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"message").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // The length prefix sits right after the 4-byte nonce prefix header
+    // and 1-byte flag; corrupting it is caught since it's authenticated
+    // as this chunk's associated data, not just used to frame the read.
+    ciphertext[4 + 1] ^= 0x01;
+
+    let key = KEY.into();
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn tampered_final_flag_fails_to_authenticate() {
+    let key = KEY.into();
+    let mut w =
+        LengthPrefixedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"message").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // The flag byte is the first byte after the nonce prefix header;
+    // flipping Final(1) to Continuation(0) should be caught the same
+    // way a tampered length is.
+    ciphertext[4] = 0;
+
+    let key = KEY.into();
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn flush_chunk_emits_a_short_chunk_without_waiting_for_the_buffer_to_fill() {
+    let key = KEY.into();
+    let mut w =
+        LengthPrefixedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    // Simulate a latency-sensitive caller flushing small, unevenly
+    // sized bursts as they arrive, rather than buffering up to a fixed
+    // chunk size first.
+    w.write_all(b"first burst").unwrap();
+    w.flush_chunk().unwrap();
+    w.write_all(b"a second, longer burst of plaintext").unwrap();
+    w.flush_chunk().unwrap();
+    w.write_all(b"third").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"first bursta second, longer burst of plaintextthird");
+}
+
+fn pseudo_random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut x = seed;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            (x & 0xff) as u8
+        })
+        .collect()
+}
+
+fn chunk_lengths(key: [u8; 32], nonce_prefix: [u8; 4], plaintext: &[u8]) -> Vec<usize> {
+    let key = key.into();
+    let mut w = LengthPrefixedWriter::<_, ChaCha20Poly1305>::with_chunker(
+        Vec::new(),
+        &key,
+        nonce_prefix,
+        Chunker::new(256, 1024, 4096),
+    )
+    .unwrap();
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    // Walk the ciphertext structurally, the way the module doc comment
+    // says content-defined chunking enables: no key needed, just the
+    // authenticated length prefix each chunk already carries.
+    let mut lengths = Vec::new();
+    let mut pos = 4; // past the nonce prefix header
+    loop {
+        let len = u32::from_be_bytes(ciphertext[pos + 1..pos + 5].try_into().unwrap()) as usize;
+        lengths.push(len);
+        pos += 1 + 4 + len + 16;
+        if pos >= ciphertext.len() {
+            break;
+        }
+    }
+    lengths
+}
+
+#[test]
+fn content_defined_chunks_roundtrip() {
+    let key = KEY.into();
+    let plaintext = pseudo_random_bytes(200_000, 1);
+    let mut w = LengthPrefixedWriter::<_, ChaCha20Poly1305>::with_chunker(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        Chunker::new(256, 1024, 4096),
+    )
+    .unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn content_defined_chunk_boundaries_realign_after_an_insertion() {
+    let original = pseudo_random_bytes(200_000, 2);
+    let mut edited = original.clone();
+    edited.splice(100_000..100_000, pseudo_random_bytes(37, 3));
+
+    let original_lengths = chunk_lengths(KEY, NONCE_PREFIX, &original);
+    let edited_lengths = chunk_lengths(KEY, NONCE_PREFIX, &edited);
+
+    // Most chunks before the edit are untouched by it; if every chunk
+    // length shifted, the boundaries wouldn't be content-defined at
+    // all.
+    let shared_prefix_len = original_lengths
+        .iter()
+        .zip(edited_lengths.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    assert!(shared_prefix_len > 0);
+}
+
+#[test]
+fn truncated_stream_is_rejected_rather_than_read_as_eof() {
+    let key = KEY.into();
+    let mut w =
+        LengthPrefixedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"first").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    // Drop the final chunk entirely: a reader that stopped as soon as
+    // its underlying reader ran dry would treat this the same as a
+    // clean, authenticated end of stream, which is exactly what this
+    // framing must not do.
+    let truncated = &ciphertext[..ciphertext.len() - 1];
+
+    let key = KEY.into();
+    let mut r = LengthPrefixedReader::<_, ChaCha20Poly1305>::new(truncated, &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,48 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn limits_roundtrip_within_bounds() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        Reader::<_, ChaCha20Poly1305>::with_limits(&ciphertext[..], &key, Some(10), Some(1024))
+            .unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn limits_rejects_too_many_chunks() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&vec![0x41u8; CHUNK_SIZE * 3]).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        Reader::<_, ChaCha20Poly1305>::with_limits(&ciphertext[..], &key, Some(2), None).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn limits_rejects_too_many_bytes() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        Reader::<_, ChaCha20Poly1305>::with_limits(&ciphertext[..], &key, None, Some(5)).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,57 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, sniff, Reader, Version, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn sniff_recognizes_a_stream_written_with_magic() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_magic(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert_eq!(sniff(&ciphertext), Some(Version::V3));
+}
+
+#[test]
+fn sniff_does_not_recognize_a_stream_written_without_magic() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    assert_eq!(sniff(&ciphertext), None);
+}
+
+#[test]
+fn sniff_does_not_recognize_arbitrary_binary_data() {
+    assert_eq!(sniff(b"not a stream at all"), None);
+    assert_eq!(sniff(b""), None);
+}
+
+#[test]
+fn writer_and_reader_roundtrip_with_magic() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_magic(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn peek_header_sees_through_the_magic_prefix() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_magic(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert_eq!(info.version, Version::V3);
+}
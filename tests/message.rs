@@ -0,0 +1,136 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{MessageReader, MessageWriter};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn multiple_messages_roundtrip() {
+    let key = KEY.into();
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"first message").unwrap();
+    w.flush_message().unwrap();
+    w.write_all(b"second message").unwrap();
+    w.flush_message().unwrap();
+    w.write_all(b"last message").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+
+    let mut first = Vec::new();
+    assert!(!r.read_message(&mut first).unwrap());
+    assert_eq!(first, b"first message");
+
+    let mut second = Vec::new();
+    assert!(!r.read_message(&mut second).unwrap());
+    assert_eq!(second, b"second message");
+
+    let mut last = Vec::new();
+    assert!(r.read_message(&mut last).unwrap());
+    assert_eq!(last, b"last message");
+    assert!(r.is_done());
+}
+
+#[test]
+fn message_spanning_multiple_chunks_roundtrips() {
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_message(&mut out).unwrap());
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn empty_flush_message_marks_an_empty_message() {
+    let key = KEY.into();
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.flush_message().unwrap();
+    w.write_all(b"second").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+
+    let mut first = Vec::new();
+    assert!(!r.read_message(&mut first).unwrap());
+    assert!(first.is_empty());
+
+    let mut second = Vec::new();
+    assert!(r.read_message(&mut second).unwrap());
+    assert_eq!(second, b"second");
+}
+
+#[test]
+fn rekey_mid_stream_roundtrips() {
+    let key = KEY.into();
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"before rekey").unwrap();
+    w.rekey().unwrap();
+    w.write_all(b"after rekey").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+
+    let mut first = Vec::new();
+    assert!(!r.read_message(&mut first).unwrap());
+    assert_eq!(first, b"before rekey");
+
+    let mut second = Vec::new();
+    assert!(r.read_message(&mut second).unwrap());
+    assert_eq!(second, b"after rekey");
+    assert!(r.is_done());
+}
+
+#[test]
+fn heartbeats_are_skipped_transparently() {
+    let key = KEY.into();
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"first ").unwrap();
+    w.heartbeat().unwrap();
+    w.write_all(b"half").unwrap();
+    w.flush_message().unwrap();
+    w.heartbeat().unwrap();
+    w.heartbeat().unwrap();
+    w.write_all(b"second message").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+
+    let mut first = Vec::new();
+    assert!(!r.read_message(&mut first).unwrap());
+    assert_eq!(first, b"first half");
+
+    let mut second = Vec::new();
+    assert!(r.read_message(&mut second).unwrap());
+    assert_eq!(second, b"second message");
+}
+
+#[test]
+fn tampered_chunk_tag_fails_to_authenticate() {
+    let key = KEY.into();
+    let mut w = MessageWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"message").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // The tag byte is the first byte after the 4-byte nonce prefix
+    // header; flipping it from Final(2) to Message(1) should be caught
+    // since it's authenticated as this chunk's associated data.
+    ciphertext[4] = 1;
+
+    let key = KEY.into();
+    let mut r = MessageReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_message(&mut out).is_err());
+}
@@ -0,0 +1,104 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{peek_header, Metadata, Reader, Version, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn writer_and_reader_roundtrip_with_metadata() {
+    let key = KEY.into();
+    let metadata = Metadata {
+        filename: Some("report.pdf".to_string()),
+        mtime: Some(1_700_000_000),
+        content_type: Some("application/pdf".to_string()),
+    };
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_metadata(Vec::new(), &key, NONCE_PREFIX, &metadata)
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert_eq!(r.metadata(), Some(&metadata));
+}
+
+#[test]
+fn a_metadata_stream_is_written_as_version_5_without_exposing_fields_to_peek_header() {
+    let key = KEY.into();
+    let metadata = Metadata {
+        filename: Some("secret.txt".to_string()),
+        ..Metadata::default()
+    };
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_metadata(Vec::new(), &key, NONCE_PREFIX, &metadata)
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert_eq!(info.version, Version::V5);
+    assert!(info.metadata_sealed);
+}
+
+#[test]
+fn an_empty_metadata_round_trips_too() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_metadata(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        &Metadata::default(),
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert_eq!(r.metadata(), Some(&Metadata::default()));
+}
+
+#[test]
+fn tampering_with_the_sealed_metadata_block_is_caught_before_any_chunk_is_decrypted() {
+    let key = KEY.into();
+    let metadata = Metadata {
+        filename: Some("secret.txt".to_string()),
+        ..Metadata::default()
+    };
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_metadata(Vec::new(), &key, NONCE_PREFIX, &metadata)
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // The sealed metadata block immediately follows the fixed-size
+    // header and its 2-byte length prefix; flip a bit in its first byte.
+    let tamper_offset = stream::HEADER_LEN + 2;
+    ciphertext[tamper_offset] ^= 0xff;
+
+    assert!(Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).is_err());
+}
+
+#[test]
+fn a_plain_stream_has_no_metadata() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let info = peek_header(&ciphertext[..]).unwrap();
+    assert!(!info.metadata_sealed);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+    assert_eq!(r.metadata(), None);
+}
@@ -0,0 +1,78 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{MmapReader, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+#[test]
+fn reads_back_the_original_plaintext_sequentially() {
+    let plaintext = b"the quick brown fox jumps over the lazy dog";
+    let ciphertext = seal(plaintext);
+
+    let key = KEY.into();
+    let mut r = MmapReader::<ChaCha20Poly1305>::new(&ciphertext, &key).unwrap();
+    assert_eq!(r.len(), plaintext.len() as u64);
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn read_chunk_decrypts_any_chunk_by_index_without_reading_earlier_ones() {
+    let plaintext = vec![b'a'; CHUNK_SIZE]
+        .into_iter()
+        .chain(vec![b'b'; CHUNK_SIZE])
+        .chain(vec![b'c'; 10])
+        .collect::<Vec<u8>>();
+    let ciphertext = seal(&plaintext);
+
+    let key = KEY.into();
+    let r = MmapReader::<ChaCha20Poly1305>::new(&ciphertext, &key).unwrap();
+    assert_eq!(r.chunk_count(), 3);
+    assert_eq!(r.read_chunk(1).unwrap(), vec![b'b'; CHUNK_SIZE]);
+    assert_eq!(r.read_chunk(2).unwrap(), vec![b'c'; 10]);
+    assert_eq!(r.read_chunk(0).unwrap(), vec![b'a'; CHUNK_SIZE]);
+}
+
+#[test]
+fn seeking_lands_reads_at_the_right_offset() {
+    let plaintext = vec![b'x'; CHUNK_SIZE * 2];
+    let ciphertext = seal(&plaintext);
+
+    let key = KEY.into();
+    let mut r = MmapReader::<ChaCha20Poly1305>::new(&ciphertext, &key).unwrap();
+    r.seek(SeekFrom::Start((CHUNK_SIZE - 2) as u64)).unwrap();
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"xxxx");
+}
+
+#[test]
+fn an_out_of_range_chunk_index_is_rejected() {
+    let ciphertext = seal(b"short stream");
+
+    let key = KEY.into();
+    let r = MmapReader::<ChaCha20Poly1305>::new(&ciphertext, &key).unwrap();
+    assert!(r.read_chunk(1).is_err());
+}
+
+#[test]
+fn a_padded_stream_is_rejected() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let key = KEY.into();
+    assert!(MmapReader::<ChaCha20Poly1305>::new(&ciphertext, &key).is_err());
+}
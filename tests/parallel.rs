@@ -0,0 +1,34 @@
+#![cfg(feature = "parallel")]
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{par_encrypt, reference_decrypt, reference_encrypt};
+
+const KEY: [u8; 32] = [0x42; 32];
+const PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn par_encrypt_matches_sequential_encrypt() {
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 5 + 17];
+
+    let sequential = reference_encrypt::<ChaCha20Poly1305>(&plaintext, &key, PREFIX);
+    let parallel = par_encrypt::<ChaCha20Poly1305>(&plaintext, &key, PREFIX);
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn par_encrypt_roundtrip() {
+    let key = KEY.into();
+    let plaintext = vec![0x11; stream::CHUNK_SIZE * 3 + 1];
+    let ciphertext = par_encrypt::<ChaCha20Poly1305>(&plaintext, &key, PREFIX);
+    let out = reference_decrypt::<ChaCha20Poly1305>(&ciphertext, &key).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn par_encrypt_empty_plaintext_roundtrip() {
+    let key = KEY.into();
+    let ciphertext = par_encrypt::<ChaCha20Poly1305>(b"", &key, PREFIX);
+    let out = reference_decrypt::<ChaCha20Poly1305>(&ciphertext, &key).unwrap();
+    assert_eq!(out, b"");
+}
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{rewrite_chunk, rewrite_chunk_at, Reader, WriteAt, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal_two_chunks() -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&[0xaa; CHUNK_SIZE]).unwrap();
+    w.write_all(b"final chunk plaintext").unwrap();
+    w.finish().unwrap()
+}
+
+fn open(ciphertext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(ciphertext, &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+/// A [`WriteAt`] over an in-memory buffer, for exercising
+/// [`rewrite_chunk_at`] without touching the filesystem.
+struct InMemory(RefCell<Vec<u8>>);
+
+impl WriteAt for InMemory {
+    fn write_at(&self, offset: u64, buf: &[u8]) -> std::io::Result<usize> {
+        let mut inner = self.0.borrow_mut();
+        let offset = offset as usize;
+        if inner.len() < offset + buf.len() {
+            inner.resize(offset + buf.len(), 0);
+        }
+        inner[offset..offset + buf.len()].copy_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+#[test]
+fn rewriting_a_chunk_changes_only_that_chunks_plaintext() {
+    let ciphertext = seal_two_chunks();
+    let mut cursor = Cursor::new(ciphertext);
+
+    let key = KEY.into();
+    // SAFETY: `ciphertext` is only ever rewritten this once, and no
+    // copy of it from before this call outlives the test.
+    unsafe {
+        rewrite_chunk::<_, ChaCha20Poly1305>(&mut cursor, &key, NONCE_PREFIX, 0, &[0xbb; CHUNK_SIZE])
+            .unwrap();
+    }
+
+    let plaintext = open(cursor.get_ref());
+    assert_eq!(&plaintext[..CHUNK_SIZE], [0xbb; CHUNK_SIZE].as_slice());
+    assert_eq!(&plaintext[CHUNK_SIZE..], b"final chunk plaintext");
+}
+
+#[test]
+fn rewrite_chunk_at_changes_only_that_chunks_plaintext() {
+    let ciphertext = seal_two_chunks();
+    let dest = InMemory(RefCell::new(ciphertext.clone()));
+
+    let key = KEY.into();
+    // SAFETY: `dest` is only ever rewritten this once, and no copy of
+    // it from before this call outlives the test.
+    unsafe {
+        rewrite_chunk_at::<_, ChaCha20Poly1305>(&dest, &key, NONCE_PREFIX, 0, &[0xbb; CHUNK_SIZE])
+            .unwrap();
+    }
+
+    let plaintext = open(&dest.0.into_inner());
+    assert_eq!(&plaintext[..CHUNK_SIZE], [0xbb; CHUNK_SIZE].as_slice());
+    assert_eq!(&plaintext[CHUNK_SIZE..], b"final chunk plaintext");
+}
+
+#[test]
+fn wrong_size_plaintext_is_rejected() {
+    let ciphertext = seal_two_chunks();
+    let mut cursor = Cursor::new(ciphertext);
+
+    let key = KEY.into();
+    // SAFETY: this call is rejected before it seals anything (wrong
+    // plaintext length), so no nonce is ever reused.
+    let err = unsafe {
+        rewrite_chunk::<_, ChaCha20Poly1305>(&mut cursor, &key, NONCE_PREFIX, 0, &[0xbb; 16])
+            .unwrap_err()
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn rewriting_with_the_wrong_key_fails_to_authenticate() {
+    let ciphertext = seal_two_chunks();
+    let mut cursor = Cursor::new(ciphertext);
+
+    let wrong_key = [0x24u8; 32].into();
+    // SAFETY: `cursor` is only ever rewritten this once, and no copy of
+    // it from before this call outlives the test.
+    unsafe {
+        rewrite_chunk::<_, ChaCha20Poly1305>(
+            &mut cursor,
+            &wrong_key,
+            NONCE_PREFIX,
+            0,
+            &[0xbb; CHUNK_SIZE],
+        )
+        .unwrap();
+    }
+
+    let key = KEY.into();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(cursor.get_ref().as_slice(), &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "pool")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{BufferPool, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+#[test]
+fn with_pool_roundtrip() {
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+    let ciphertext = seal(&plaintext);
+    let key = KEY.into();
+    let pool = BufferPool::new();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_pool(&ciphertext[..], &key, &pool).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn pool_buffer_is_reused_across_streams() {
+    let key = KEY.into();
+    let pool = BufferPool::new();
+
+    for _ in 0..4 {
+        let plaintext = vec![0x11; stream::CHUNK_SIZE + 3];
+        let ciphertext = seal(&plaintext);
+        let mut r = Reader::<_, ChaCha20Poly1305>::with_pool(&ciphertext[..], &key, &pool).unwrap();
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(out, plaintext);
+    }
+}
+
+#[test]
+fn with_pool_empty_stream() {
+    let ciphertext = seal(b"");
+    let key = KEY.into();
+    let pool = BufferPool::new();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_pool(&ciphertext[..], &key, &pool).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "proptest")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use proptest::prelude::*;
+use stream::proptest::{key, mutation, nonce_prefix, plaintext};
+use stream::{Reader, Writer};
+
+proptest! {
+    // Under `large_chunks`, every `Writer`/`Reader` in this file's
+    // tests carries an 8 MiB chunk buffer regardless of how much
+    // plaintext a given case uses, so the default case count still adds
+    // up. Cut it down for just that feature; every other feature
+    // combination keeps the default.
+    #![proptest_config(ProptestConfig {
+        cases: if cfg!(feature = "large_chunks") {
+            32
+        } else {
+            ProptestConfig::default().cases
+        },
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn every_plaintext_length_round_trips(key in key(), nonce_prefix in nonce_prefix(), plaintext in plaintext()) {
+        let key = key.into();
+        let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, nonce_prefix).unwrap();
+        w.write_all(&plaintext).unwrap();
+        let ciphertext = w.finish().unwrap();
+
+        let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+        let mut out = Vec::new();
+        std::io::Read::read_to_end(&mut r, &mut out).unwrap();
+        prop_assert_eq!(out, plaintext);
+    }
+
+    #[test]
+    fn a_mutated_non_empty_ciphertext_never_authenticates_as_the_original_plaintext(
+        key in key(),
+        nonce_prefix in nonce_prefix(),
+        plaintext in prop::collection::vec(any::<u8>(), 1..8192),
+        mutation in mutation(),
+    ) {
+        let key = key.into();
+        let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, nonce_prefix).unwrap();
+        w.write_all(&plaintext).unwrap();
+        let mut ciphertext = w.finish().unwrap();
+
+        mutation.apply(&mut ciphertext);
+
+        let outcome = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).and_then(|mut r| {
+            let mut out = Vec::new();
+            std::io::Read::read_to_end(&mut r, &mut out)?;
+            Ok(out)
+        });
+        if let Ok(out) = outcome {
+            prop_assert_eq!(out, plaintext, "mutation authenticated as different plaintext");
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use aead::Key;
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{KeyId, KeyProvider, Reader, Writer};
+
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[derive(Debug)]
+struct UnknownKey;
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown key id")
+    }
+}
+
+impl std::error::Error for UnknownKey {}
+
+/// A stand-in for a real KMS/Vault-backed provider.
+struct MockProvider {
+    keys: HashMap<KeyId, Key<ChaCha20Poly1305>>,
+}
+
+impl KeyProvider<ChaCha20Poly1305> for MockProvider {
+    type Error = UnknownKey;
+
+    fn resolve(&self, key_id: KeyId) -> Result<Key<ChaCha20Poly1305>, Self::Error> {
+        self.keys.get(&key_id).cloned().ok_or(UnknownKey)
+    }
+
+    fn wrap(&self, key_id: KeyId, dek: &Key<ChaCha20Poly1305>) -> Result<Vec<u8>, Self::Error> {
+        let key = self.keys.get(&key_id).ok_or(UnknownKey)?;
+        Ok(dek.iter().zip(key.iter()).map(|(a, b)| a ^ b).collect())
+    }
+
+    fn unwrap_dek(
+        &self,
+        key_id: KeyId,
+        wrapped: &[u8],
+    ) -> Result<Key<ChaCha20Poly1305>, Self::Error> {
+        let key = self.keys.get(&key_id).ok_or(UnknownKey)?;
+        let mut dek = Key::<ChaCha20Poly1305>::default();
+        for (d, (w, k)) in dek.iter_mut().zip(wrapped.iter().zip(key.iter())) {
+            *d = w ^ k;
+        }
+        Ok(dek)
+    }
+}
+
+#[test]
+fn provider_roundtrip() {
+    let key_id = [3u8; 8];
+    let mut keys = HashMap::new();
+    keys.insert(key_id, Key::<ChaCha20Poly1305>::from([0x55; 32]));
+    let provider = MockProvider { keys };
+
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_provider(Vec::new(), &provider, key_id, NONCE_PREFIX)
+            .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_provider(&ciphertext[..], &provider).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn provider_wrap_unwrap_dek_roundtrip() {
+    let key_id = [4u8; 8];
+    let mut keys = HashMap::new();
+    keys.insert(key_id, Key::<ChaCha20Poly1305>::from([0x11; 32]));
+    let provider = MockProvider { keys };
+
+    let dek = Key::<ChaCha20Poly1305>::from([0x99; 32]);
+    let wrapped = provider.wrap(key_id, &dek).unwrap();
+    let unwrapped = provider.unwrap_dek(key_id, &wrapped).unwrap();
+    assert_eq!(dek, unwrapped);
+}
+
+#[test]
+fn provider_rejects_unknown_key_id() {
+    let provider = MockProvider {
+        keys: HashMap::new(),
+    };
+    let result =
+        Writer::<_, ChaCha20Poly1305>::with_provider(Vec::new(), &provider, [9u8; 8], NONCE_PREFIX);
+    assert!(result.is_err());
+}
+
+/// A provider whose error type is `std::io::Error` itself, the way a
+/// provider backed by a network call to a KMS might fail.
+struct FailingProvider;
+
+impl KeyProvider<ChaCha20Poly1305> for FailingProvider {
+    type Error = std::io::Error;
+
+    fn resolve(&self, _key_id: KeyId) -> Result<Key<ChaCha20Poly1305>, Self::Error> {
+        Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+    }
+
+    fn wrap(&self, _key_id: KeyId, _dek: &Key<ChaCha20Poly1305>) -> Result<Vec<u8>, Self::Error> {
+        Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+    }
+
+    fn unwrap_dek(
+        &self,
+        _key_id: KeyId,
+        _wrapped: &[u8],
+    ) -> Result<Key<ChaCha20Poly1305>, Self::Error> {
+        Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+    }
+}
+
+#[test]
+fn provider_error_preserves_io_error_kind() {
+    let result = Writer::<_, ChaCha20Poly1305>::with_provider(
+        Vec::new(),
+        &FailingProvider,
+        [1u8; 8],
+        NONCE_PREFIX,
+    );
+    let err = match result {
+        Ok(_) => panic!("expected an error"),
+        Err(e) => e,
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
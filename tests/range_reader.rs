@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{read_exact_at, write_all_at, RangeReader, ReadAt, Writer, CHUNK_SIZE};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+struct InMemory(Vec<u8>);
+
+impl ReadAt for InMemory {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let offset = offset as usize;
+        if offset >= self.0.len() {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.0.len() - offset);
+        buf[..n].copy_from_slice(&self.0[offset..offset + n]);
+        Ok(n)
+    }
+}
+
+/// A [`ReadAt`] that never returns more than one byte at a time, to
+/// exercise [`stream::read_exact_at`]'s retry loop the way a flaky
+/// network source would.
+struct OneByteAtATime(Vec<u8>);
+
+impl ReadAt for OneByteAtATime {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let offset = offset as usize;
+        if offset >= self.0.len() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[offset];
+        Ok(1)
+    }
+}
+
+#[test]
+fn read_chunk_decrypts_any_chunk_by_index() {
+    let plaintext = vec![b'a'; CHUNK_SIZE]
+        .into_iter()
+        .chain(vec![b'b'; CHUNK_SIZE])
+        .chain(vec![b'c'; 10])
+        .collect::<Vec<u8>>();
+    let ciphertext = seal(&plaintext);
+    let len = ciphertext.len() as u64;
+
+    let key = KEY.into();
+    let r = RangeReader::<_, ChaCha20Poly1305>::open(InMemory(ciphertext), &key, len).unwrap();
+    assert_eq!(r.len(), plaintext.len() as u64);
+    assert_eq!(r.chunk_count(), 3);
+    assert_eq!(r.read_chunk(2).unwrap(), vec![b'c'; 10]);
+    assert_eq!(r.read_chunk(0).unwrap(), vec![b'a'; CHUNK_SIZE]);
+    assert_eq!(r.read_chunk(1).unwrap(), vec![b'b'; CHUNK_SIZE]);
+}
+
+#[test]
+fn short_reads_from_the_source_are_retried_until_a_chunk_is_filled() {
+    let plaintext = b"hello from a flaky source";
+    let ciphertext = seal(plaintext);
+    let len = ciphertext.len() as u64;
+
+    let key = KEY.into();
+    let r =
+        RangeReader::<_, ChaCha20Poly1305>::open(OneByteAtATime(ciphertext), &key, len).unwrap();
+    assert_eq!(r.read_chunk(0).unwrap(), plaintext);
+}
+
+#[test]
+fn an_out_of_range_chunk_index_is_rejected() {
+    let ciphertext = seal(b"short stream");
+    let len = ciphertext.len() as u64;
+
+    let key = KEY.into();
+    let r = RangeReader::<_, ChaCha20Poly1305>::open(InMemory(ciphertext), &key, len).unwrap();
+    assert!(r.read_chunk(1).is_err());
+}
+
+#[test]
+fn a_padded_stream_is_rejected() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello").unwrap();
+    let ciphertext = w.finish().unwrap();
+    let len = ciphertext.len() as u64;
+
+    let key = KEY.into();
+    assert!(RangeReader::<_, ChaCha20Poly1305>::open(InMemory(ciphertext), &key, len).is_err());
+}
+
+#[test]
+fn std_fs_file_implements_read_at_and_write_at() {
+    let path = std::env::temp_dir().join(format!(
+        "stream-rs-test-file-read-write-at-{}",
+        std::process::id()
+    ));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(20).unwrap();
+
+    write_all_at(&file, 10, b"hello").unwrap();
+    let mut buf = [0u8; 5];
+    read_exact_at(&file, 10, &mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn tampered_ciphertext_fails_to_authenticate() {
+    let ciphertext = seal(b"some plaintext");
+    let len = ciphertext.len() as u64;
+    let mut tampered = ciphertext;
+    *tampered.last_mut().unwrap() ^= 0xff;
+
+    let key = KEY.into();
+    let r = RangeReader::<_, ChaCha20Poly1305>::open(InMemory(tampered), &key, len).unwrap();
+    assert!(r.read_chunk(0).is_err());
+}
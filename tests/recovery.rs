@@ -0,0 +1,64 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Error, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn recovery_mode_substitutes_a_gap_marker_for_a_corrupted_chunk() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    // Flip a byte inside the first (full-sized) chunk, leaving the
+    // second chunk's ciphertext untouched.
+    ciphertext[stream::HEADER_LEN] ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_recovery_mode(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+
+    assert_eq!(out.len(), plaintext.len());
+    assert_eq!(
+        &out[..stream::CHUNK_SIZE],
+        &vec![0u8; stream::CHUNK_SIZE][..]
+    );
+    assert_eq!(&out[stream::CHUNK_SIZE..], &plaintext[stream::CHUNK_SIZE..]);
+
+    let recovered = r.recovered();
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].chunk, 0);
+    assert_eq!(recovered[0].len, stream::CHUNK_SIZE);
+}
+
+#[test]
+fn default_reader_still_aborts_on_the_same_corruption() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    ciphertext[stream::HEADER_LEN] ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let err = r.read_to_end(&mut out).unwrap_err();
+    let err = err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(matches!(*err, Error::AeadAt { chunk: 0, .. }));
+}
+
+#[test]
+fn recovery_mode_does_not_recover_truncation() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&[0xab; 100]).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    ciphertext.truncate(ciphertext.len() - 110);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_recovery_mode(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+    assert!(r.recovered().is_empty());
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "reference")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{reference_decrypt, reference_encrypt, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn reference_roundtrip() {
+    let key = KEY.into();
+    let plaintext = b"hello, world";
+    let ciphertext = reference_encrypt::<ChaCha20Poly1305>(plaintext, &key, PREFIX);
+    let out = reference_decrypt::<ChaCha20Poly1305>(&ciphertext, &key).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn reference_matches_writer_reader() {
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let writer_ciphertext = w.finish().unwrap();
+
+    let reference_ciphertext = reference_encrypt::<ChaCha20Poly1305>(&plaintext, &key, PREFIX);
+    assert_eq!(writer_ciphertext, reference_ciphertext);
+
+    // Cross-check in both directions: each implementation must be able
+    // to open the other's ciphertext.
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&reference_ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+
+    let out = reference_decrypt::<ChaCha20Poly1305>(&writer_ciphertext, &key).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn reference_empty_plaintext_roundtrip() {
+    let key = KEY.into();
+    let ciphertext = reference_encrypt::<ChaCha20Poly1305>(b"", &key, PREFIX);
+    let out = reference_decrypt::<ChaCha20Poly1305>(&ciphertext, &key).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn reference_rejects_tampered_ciphertext() {
+    let key = KEY.into();
+    let mut ciphertext = reference_encrypt::<ChaCha20Poly1305>(b"hello, world", &key, PREFIX);
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 0x01;
+    assert!(reference_decrypt::<ChaCha20Poly1305>(&ciphertext, &key).is_err());
+}
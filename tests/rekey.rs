@@ -0,0 +1,72 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{reencrypt, Reader, Writer};
+
+const OLD_KEY: [u8; 32] = [0x42; 32];
+const NEW_KEY: [u8; 32] = [0x99; 32];
+const OLD_PREFIX: [u8; 4] = [0x24; 4];
+const NEW_PREFIX: [u8; 4] = [0x55; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = OLD_KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, OLD_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+#[test]
+fn reencrypt_roundtrip() {
+    let plaintext = vec![0x7a; stream::CHUNK_SIZE * 3 + 17];
+    let old_ciphertext = seal(&plaintext);
+
+    let new_ciphertext = reencrypt::<_, _, ChaCha20Poly1305>(
+        &old_ciphertext[..],
+        &OLD_KEY.into(),
+        Vec::new(),
+        &NEW_KEY.into(),
+        NEW_PREFIX,
+    )
+    .unwrap();
+
+    assert_ne!(old_ciphertext, new_ciphertext);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&new_ciphertext[..], &NEW_KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn reencrypt_empty_stream() {
+    let old_ciphertext = seal(b"");
+
+    let new_ciphertext = reencrypt::<_, _, ChaCha20Poly1305>(
+        &old_ciphertext[..],
+        &OLD_KEY.into(),
+        Vec::new(),
+        &NEW_KEY.into(),
+        NEW_PREFIX,
+    )
+    .unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&new_ciphertext[..], &NEW_KEY.into()).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn reencrypt_fails_under_wrong_old_key() {
+    let old_ciphertext = seal(b"secret");
+    let wrong_key: [u8; 32] = [0x11; 32];
+
+    let result = reencrypt::<_, _, ChaCha20Poly1305>(
+        &old_ciphertext[..],
+        &wrong_key.into(),
+        Vec::new(),
+        &NEW_KEY.into(),
+        NEW_PREFIX,
+    );
+    assert!(result.is_err());
+}
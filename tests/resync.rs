@@ -0,0 +1,92 @@
+use std::io::{Read, Write};
+
+use aead::generic_array::GenericArray;
+use aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Error, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn resync_recovers_chunk_boundary_after_inserted_junk_bytes() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    // Splice 10 junk bytes in right where the first chunk's ciphertext
+    // begins, simulating a bad sector that duplicated a read instead of
+    // destroying data -- everything from here on is shifted forward,
+    // but none of the real ciphertext is lost.
+    let first_chunk_len = stream::CHUNK_SIZE + 16;
+    let second_chunk_len = 17 + 16;
+    let boundary = ciphertext.len() - (first_chunk_len + second_chunk_len);
+    ciphertext.splice(boundary..boundary, [0u8; 10]);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut buf = vec![0u8; stream::CHUNK_SIZE];
+    let err = r.read(&mut buf).unwrap_err();
+    let err = *err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(matches!(err, Error::AeadAt { chunk: 0, .. }));
+
+    assert!(r.resync(64).unwrap());
+
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn resync_fails_when_corruption_exceeds_the_scan_window() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+
+    let first_chunk_len = stream::CHUNK_SIZE + 16;
+    let second_chunk_len = 17 + 16;
+    let boundary = ciphertext.len() - (first_chunk_len + second_chunk_len);
+    ciphertext.splice(boundary..boundary, [0u8; 10]);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut buf = vec![0u8; stream::CHUNK_SIZE];
+    r.read(&mut buf).unwrap_err();
+
+    assert!(!r.resync(4).unwrap());
+}
+
+#[test]
+fn find_chunk_boundary_locates_a_chunk_behind_leading_garbage() {
+    let key = GenericArray::from([0x11u8; 32]);
+    let aead = ChaCha20Poly1305::new(&key);
+    let nonce = GenericArray::from([0x22u8; 12]);
+
+    let mut chunk = b"hello, world".to_vec();
+    let tag = aead
+        .encrypt_in_place_detached(&nonce, b"", &mut chunk)
+        .unwrap();
+    chunk.extend_from_slice(&tag);
+
+    let mut ciphertext = vec![0u8; 7];
+    ciphertext.extend_from_slice(&chunk);
+
+    let offset = stream::find_chunk_boundary(
+        &ciphertext,
+        0,
+        chunk.len(),
+        &aead,
+        &nonce,
+        b"",
+        ciphertext.len(),
+    )
+    .expect("boundary should be found within the scan window");
+    assert_eq!(offset, 7);
+
+    assert!(
+        stream::find_chunk_boundary(&ciphertext, 0, chunk.len(), &aead, &nonce, b"", 3).is_none(),
+        "a scan window shorter than the garbage run shouldn't find anything"
+    );
+}
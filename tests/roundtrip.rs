@@ -0,0 +1,471 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{DigestAlgorithm, Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn roundtrip(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn empty_stream() {
+    assert_eq!(roundtrip(b""), b"");
+}
+
+#[test]
+fn empty_stream_is_a_single_authenticated_eof_chunk() {
+    let key = KEY.into();
+    let w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let ciphertext = w.finish().unwrap();
+    // Header plus one chunk holding zero plaintext bytes and its tag --
+    // not, say, a header with no chunk at all, which would leave a
+    // truncated stream indistinguishable from data lost in transit.
+    assert_eq!(ciphertext.len(), stream::HEADER_LEN + 16);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = [0u8; 1];
+    assert_eq!(r.read(&mut out).unwrap(), 0);
+}
+
+#[test]
+fn empty_padded_stream_roundtrips() {
+    let key = KEY.into();
+    let w = Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn empty_digest_stream_roundtrips() {
+    let key = KEY.into();
+    let w = Writer::<_, ChaCha20Poly1305>::with_digest(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        Some(DigestAlgorithm::Blake3),
+    )
+    .unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn single_short_chunk() {
+    assert_eq!(roundtrip(b"hello, world"), b"hello, world");
+}
+
+#[test]
+fn multiple_chunks() {
+    let plaintext = vec![0xab; stream::CHUNK_SIZE * 3 + 17];
+    assert_eq!(roundtrip(&plaintext), plaintext);
+}
+
+#[test]
+fn chunk_aligned_plaintext() {
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2];
+    assert_eq!(roundtrip(&plaintext), plaintext);
+}
+
+#[test]
+fn large_single_read_matches_chunked_reads() {
+    // Exercises Reader::read's chunk-aligned fast path, which kicks in
+    // whenever the caller's buffer is at least chunk-sized.
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = vec![0u8; stream::CHUNK_SIZE * 4];
+    let mut got = Vec::new();
+    loop {
+        let n = r.read(&mut out).unwrap();
+        if n == 0 {
+            break;
+        }
+        got.extend_from_slice(&out[..n]);
+    }
+    assert_eq!(got, plaintext);
+}
+
+#[test]
+fn tampered_ciphertext_fails_to_decrypt() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    *ciphertext.last_mut().unwrap() ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn tampered_ciphertext_kind_is_aead_and_not_retryable() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    *ciphertext.last_mut().unwrap() ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let err = r.read_to_end(&mut out).unwrap_err();
+    let err = *err
+        .into_inner()
+        .unwrap()
+        .downcast::<stream::Error>()
+        .unwrap();
+    assert_eq!(err.kind(), stream::ErrorKind::Aead);
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn tampered_second_chunk_reports_chunk_and_offset() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    w.write_all(&plaintext).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    // Tamper with the final (second) chunk's tag, leaving the first
+    // chunk untouched, so the failure is attributable to chunk index 1.
+    *ciphertext.last_mut().unwrap() ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let err = r.read_to_end(&mut out).unwrap_err();
+    let err = *err
+        .into_inner()
+        .unwrap()
+        .downcast::<stream::Error>()
+        .unwrap();
+    match err {
+        stream::Error::AeadAt { chunk, offset } => {
+            assert_eq!(chunk, 1);
+            assert!(offset > 0);
+        }
+        other => panic!("expected Error::AeadAt, got {other:?}"),
+    }
+}
+
+#[test]
+fn truncated_mid_chunk_reports_chunk_and_offset() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&[0xab; 100]).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    // However long the header turns out to be, truncating 110 bytes off
+    // a 100-byte plaintext's 116-byte chunk (100 bytes plus a 16-byte
+    // tag) leaves exactly 6 bytes of that chunk's ciphertext -- too few
+    // to hold its tag.
+    let keep = ciphertext.len() - 110;
+    ciphertext.truncate(keep);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let err = r.read_to_end(&mut out).unwrap_err();
+    let err = *err
+        .into_inner()
+        .unwrap()
+        .downcast::<stream::Error>()
+        .unwrap();
+    match err {
+        stream::Error::TruncatedChunk { chunk, offset } => {
+            assert_eq!(chunk, 0);
+            assert!(offset > 0);
+        }
+        other => panic!("expected Error::TruncatedChunk, got {other:?}"),
+    }
+}
+
+#[test]
+fn wrong_key_fails_to_decrypt() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let wrong_key = [0x43; 32].into();
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &wrong_key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn truncated_stream_fails() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let plaintext = vec![0x11; stream::CHUNK_SIZE * 2 + 5];
+    w.write_all(&plaintext).unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    ciphertext.truncate(ciphertext.len() - 3);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn digest_footer_roundtrip() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_digest(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        Some(DigestAlgorithm::Sha256),
+    )
+    .unwrap();
+    let plaintext = vec![0x77; stream::CHUNK_SIZE + 100];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn padded_stream_roundtrip() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let plaintext = b"hello, world";
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn padded_stream_hides_exact_length() {
+    let key = KEY.into();
+    let seal = |plaintext: &[u8]| {
+        let mut w =
+            Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+        w.write_all(plaintext).unwrap();
+        w.finish().unwrap()
+    };
+
+    // Two plaintexts close in length should round up to the same
+    // padded length and therefore produce the same ciphertext length.
+    let a = seal(&vec![0x11; 1000]);
+    let b = seal(&vec![0x22; 1001]);
+    assert_eq!(a.len(), b.len());
+}
+
+#[test]
+fn padded_multi_chunk_roundtrip() {
+    let key = KEY.into();
+    let mut w =
+        Writer::<_, ChaCha20Poly1305>::with_padding(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let plaintext = vec![0x99; stream::CHUNK_SIZE * 2 + 42];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn digest_footer_with_blake3() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_digest(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        Some(DigestAlgorithm::Blake3),
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn pad_to_boundary_aligns_the_next_write_to_a_chunk() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"record one").unwrap();
+    let pad_len = w.pad_to_boundary().unwrap();
+    assert_eq!(pad_len, stream::CHUNK_SIZE - b"record one".len());
+    w.write_all(b"record two").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+
+    let mut expected = b"record one".to_vec();
+    expected.resize(stream::CHUNK_SIZE, 0);
+    expected.extend_from_slice(b"record two");
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn pad_to_boundary_on_an_empty_chunk_is_a_no_op() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&vec![0xab; stream::CHUNK_SIZE]).unwrap();
+    // Already sitting on a chunk boundary: nothing to pad.
+    assert_eq!(w.pad_to_boundary().unwrap(), 0);
+    let ciphertext = w.finish().unwrap();
+    assert_eq!(ciphertext.len(), stream::HEADER_LEN + stream::CHUNK_SIZE + 16 + 16);
+}
+
+#[test]
+fn write_from_pulls_a_reader_until_eof() {
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let pulled = w.write_from(&mut &plaintext[..], None).unwrap();
+    assert_eq!(pulled, plaintext.len() as u64);
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn write_from_stops_at_the_byte_limit() {
+    let key = KEY.into();
+    let plaintext = [0x5a; 100];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    let pulled = w.write_from(&mut &plaintext[..], Some(40)).unwrap();
+    assert_eq!(pulled, 40);
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext[..40]);
+}
+
+#[test]
+fn read_exact_fills_a_buffer_spanning_several_chunks() {
+    let key = KEY.into();
+    let plaintext: Vec<u8> = (0..(stream::CHUNK_SIZE * 2 + 17) as u32)
+        .map(|i| i as u8)
+        .collect();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = vec![0u8; plaintext.len()];
+    r.read_exact(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn read_exact_past_eof_is_an_unexpected_eof_error() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = [0u8; 10];
+    let err = r.read_exact(&mut out).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn read_into_pushes_plaintext_to_a_write_sink() {
+    let key = KEY.into();
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 2 + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let n = r.read_into(&mut out, None).unwrap();
+    assert_eq!(n, plaintext.len() as u64);
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn read_into_stops_at_the_byte_limit() {
+    let key = KEY.into();
+    let plaintext = [0x5a; 100];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    let n = r.read_into(&mut out, Some(40)).unwrap();
+    assert_eq!(n, 40);
+    assert_eq!(out, plaintext[..40]);
+}
+
+#[test]
+fn peek_does_not_consume_plaintext() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    assert_eq!(r.peek(5).unwrap(), b"hello");
+    // Peeking again returns the same bytes: nothing was consumed.
+    assert_eq!(r.peek(5).unwrap(), b"hello");
+
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn peek_is_capped_at_one_chunk_and_at_eof() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    // Asking for more than a chunk's worth only ever returns one
+    // chunk's plaintext, since that's all that's ever buffered.
+    assert_eq!(r.peek(stream::CHUNK_SIZE * 2).unwrap().len(), stream::CHUNK_SIZE);
+
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+
+    // Past EOF, there's nothing left to peek at.
+    assert_eq!(r.peek(16).unwrap().len(), 0);
+}
@@ -0,0 +1,50 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{derive_session_key, Reader, Writer};
+
+const EXPORTED_SECRET: [u8; 32] = [0x42; 32];
+
+#[test]
+fn each_direction_gets_its_own_key_and_prefix_from_one_exported_secret() {
+    let (c2s_key, c2s_prefix) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, b"client to server");
+    let (s2c_key, s2c_prefix) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, b"server to client");
+    assert_ne!(c2s_key, s2c_key);
+    assert_ne!(c2s_prefix, s2c_prefix);
+}
+
+#[test]
+fn both_peers_deriving_from_the_same_exported_secret_and_info_can_talk_to_each_other() {
+    let info = b"client to server";
+    let (client_key, client_prefix) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, info);
+    let (server_key, _server_prefix) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, info);
+
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &client_key, client_prefix).unwrap();
+    w.write_all(b"hello from the client").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &server_key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello from the client");
+}
+
+#[test]
+fn a_stream_sealed_under_one_directions_key_does_not_open_under_the_others() {
+    let (c2s_key, c2s_prefix) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, b"client to server");
+    let (s2c_key, _) =
+        derive_session_key::<ChaCha20Poly1305>(&EXPORTED_SECRET, b"server to client");
+
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &c2s_key, c2s_prefix).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &s2c_key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "stats")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn writer_stats_tracks_chunks_and_bytes() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE * 2 + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let stats = w.stats();
+    assert_eq!(stats.chunks, 2);
+    assert_eq!(stats.bytes_in, plaintext.len() as u64);
+    // Each sealed chunk grows by the AEAD's 16-byte tag.
+    assert_eq!(stats.bytes_out, (stream::CHUNK_SIZE * 2 + 16 * 2) as u64);
+    assert_eq!(stats.auth_failures, 0);
+    assert_eq!(stats.rekeys, 0);
+
+    w.finish().unwrap();
+}
+
+#[test]
+fn reader_stats_tracks_chunks_and_bytes() {
+    let key = KEY.into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+
+    let stats = r.stats();
+    assert_eq!(stats.chunks, 2);
+    assert_eq!(stats.bytes_out, plaintext.len() as u64);
+    assert_eq!(stats.auth_failures, 0);
+    assert_eq!(stats.rekeys, 0);
+}
+
+#[test]
+fn reader_stats_counts_auth_failures() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    *ciphertext.last_mut().unwrap() ^= 1;
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+    assert_eq!(r.stats().auth_failures, 1);
+}
+
+#[test]
+fn reencrypt_with_stats_reports_a_rekey() {
+    let old_key = KEY.into();
+    let new_key = [0x24u8; 32].into();
+    let plaintext = vec![0xab; stream::CHUNK_SIZE + 17];
+
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &old_key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let (rewrapped, stats) = stream::reencrypt_with_stats::<_, _, ChaCha20Poly1305>(
+        &ciphertext[..],
+        &old_key,
+        Vec::new(),
+        &new_key,
+        [0x99; 4],
+    )
+    .unwrap();
+    assert_eq!(stats.rekeys, 1);
+    assert!(stats.chunks >= 1);
+    assert_eq!(stats.auth_failures, 0);
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&rewrapped[..], &new_key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
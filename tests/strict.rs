@@ -0,0 +1,50 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn strict_mode_roundtrip() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_strict_mode(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn strict_mode_rejects_trailing_data() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w.finish().unwrap();
+    ciphertext.extend_from_slice(b"trailing garbage");
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_strict_mode(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
+
+#[test]
+fn strict_mode_rejects_concatenated_streams() {
+    let key = KEY.into();
+
+    let mut w1 = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w1.write_all(b"hello, world").unwrap();
+    let mut ciphertext = w1.finish().unwrap();
+
+    let mut w2 = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w2.write_all(b"goodbye, world").unwrap();
+    ciphertext.extend_from_slice(&w2.finish().unwrap());
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_strict_mode(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
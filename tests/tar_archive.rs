@@ -0,0 +1,75 @@
+#![cfg(feature = "tar")]
+
+use std::io::Read;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{open_tar, seal_tar};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn tar_archive_roundtrips_through_the_stream_ciphertext() {
+    let key = KEY.into();
+    let mut builder = seal_tar::<_, ChaCha20Poly1305>(Vec::new(), &key, NONCE_PREFIX).unwrap();
+
+    let mut header = tar::Header::new_gnu();
+    header.set_path("hello.txt").unwrap();
+    header.set_size(13);
+    header.set_cksum();
+    builder.append(&header, &b"hello, world!"[..]).unwrap();
+
+    let ciphertext = builder.into_inner().unwrap().finish().unwrap();
+
+    let mut archive = open_tar::<_, ChaCha20Poly1305>(&ciphertext[..], &key).unwrap();
+    let mut entries = archive.entries().unwrap();
+    let mut entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.path().unwrap().to_str().unwrap(), "hello.txt");
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "hello, world!");
+    assert!(entries.next().is_none());
+}
+
+#[test]
+fn tar_archive_with_multiple_entries_roundtrips() {
+    let key = KEY.into();
+    let mut builder = seal_tar::<_, ChaCha20Poly1305>(Vec::new(), &key, NONCE_PREFIX).unwrap();
+
+    for (name, contents) in [("a.txt", "aaa"), ("b.txt", "bbbbb")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_path(name).unwrap();
+        header.set_size(contents.len() as u64);
+        header.set_cksum();
+        builder.append(&header, contents.as_bytes()).unwrap();
+    }
+
+    let ciphertext = builder.into_inner().unwrap().finish().unwrap();
+
+    let mut archive = open_tar::<_, ChaCha20Poly1305>(&ciphertext[..], &key).unwrap();
+    let names: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().to_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(names, vec!["a.txt", "b.txt"]);
+}
+
+#[test]
+fn tampered_tar_ciphertext_fails_to_authenticate() {
+    let key = KEY.into();
+    let mut builder = seal_tar::<_, ChaCha20Poly1305>(Vec::new(), &key, NONCE_PREFIX).unwrap();
+
+    let mut header = tar::Header::new_gnu();
+    header.set_path("hello.txt").unwrap();
+    header.set_size(5);
+    header.set_cksum();
+    builder.append(&header, &b"hello"[..]).unwrap();
+
+    let mut ciphertext = builder.into_inner().unwrap().finish().unwrap();
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 0x01;
+
+    let mut archive = open_tar::<_, ChaCha20Poly1305>(&ciphertext[..], &key).unwrap();
+    assert!(archive.entries().unwrap().next().unwrap().is_err());
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "threaded")]
+
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, ThreadedReader, ThreadedWriter, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+fn roundtrip(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = ThreadedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn empty_stream() {
+    assert_eq!(roundtrip(b""), b"");
+}
+
+#[test]
+fn single_short_chunk() {
+    assert_eq!(roundtrip(b"hello, world"), b"hello, world");
+}
+
+#[test]
+fn many_chunks_across_many_writes() {
+    let plaintext = vec![0x7a; stream::CHUNK_SIZE * 4 + 123];
+    let key = KEY.into();
+    let mut w = ThreadedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    for chunk in plaintext.chunks(4096) {
+        w.write_all(chunk).unwrap();
+    }
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn dropping_without_finish_does_not_hang() {
+    let key = KEY.into();
+    let mut w = ThreadedWriter::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"not finished").unwrap();
+    drop(w);
+}
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+#[test]
+fn threaded_reader_matches_plain_reader() {
+    let plaintext = vec![0x5a; stream::CHUNK_SIZE * 4 + 123];
+    let ciphertext = seal(&plaintext);
+    let key = KEY.into();
+
+    let mut r =
+        ThreadedReader::<_, ChaCha20Poly1305>::new(std::io::Cursor::new(ciphertext), &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn threaded_reader_empty_stream() {
+    let ciphertext = seal(b"");
+    let key = KEY.into();
+
+    let mut r =
+        ThreadedReader::<_, ChaCha20Poly1305>::new(std::io::Cursor::new(ciphertext), &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn threaded_reader_small_reads() {
+    let plaintext = vec![0x11; stream::CHUNK_SIZE * 2 + 7];
+    let ciphertext = seal(&plaintext);
+    let key = KEY.into();
+
+    let mut r =
+        ThreadedReader::<_, ChaCha20Poly1305>::new(std::io::Cursor::new(ciphertext), &key).unwrap();
+    let mut out = Vec::new();
+    let mut small = [0u8; 16];
+    loop {
+        let n = r.read(&mut small).unwrap();
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&small[..n]);
+    }
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn threaded_reader_dropped_early_does_not_hang() {
+    let plaintext = vec![0x33; stream::CHUNK_SIZE * 8];
+    let ciphertext = seal(&plaintext);
+    let key = KEY.into();
+
+    let mut r =
+        ThreadedReader::<_, ChaCha20Poly1305>::new(std::io::Cursor::new(ciphertext), &key).unwrap();
+    let mut small = [0u8; 16];
+    let _ = r.read(&mut small).unwrap();
+    drop(r);
+}
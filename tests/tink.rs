@@ -0,0 +1,100 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{TinkReader, TinkWriter, DEFAULT_SEGMENT_SIZE};
+
+const IKM: [u8; 32] = [0x42; 32];
+const SALT: [u8; 32] = [0x11; 32];
+const NONCE_PREFIX: [u8; 7] = [0x24; 7];
+const AAD: &[u8] = b"file.txt";
+
+#[test]
+fn tink_roundtrip() {
+    let mut w = TinkWriter::<_, ChaCha20Poly1305>::new(
+        Vec::new(),
+        &IKM,
+        &SALT,
+        NONCE_PREFIX,
+        AAD,
+        DEFAULT_SEGMENT_SIZE,
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        TinkReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &IKM, AAD, DEFAULT_SEGMENT_SIZE)
+            .unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn tink_multi_segment_roundtrip() {
+    let segment_size = 256;
+    let mut w = TinkWriter::<_, ChaCha20Poly1305>::new(
+        Vec::new(),
+        &IKM,
+        &SALT,
+        NONCE_PREFIX,
+        AAD,
+        segment_size,
+    )
+    .unwrap();
+    let plaintext = vec![0x5a; segment_size * 5 + 37];
+    w.write_all(&plaintext).unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        TinkReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &IKM, AAD, segment_size).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn tink_empty_plaintext_roundtrip() {
+    let w = TinkWriter::<_, ChaCha20Poly1305>::new(
+        Vec::new(),
+        &IKM,
+        &SALT,
+        NONCE_PREFIX,
+        AAD,
+        DEFAULT_SEGMENT_SIZE,
+    )
+    .unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r =
+        TinkReader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &IKM, AAD, DEFAULT_SEGMENT_SIZE)
+            .unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"");
+}
+
+#[test]
+fn tink_wrong_aad_fails_to_decrypt() {
+    let mut w = TinkWriter::<_, ChaCha20Poly1305>::new(
+        Vec::new(),
+        &IKM,
+        &SALT,
+        NONCE_PREFIX,
+        AAD,
+        DEFAULT_SEGMENT_SIZE,
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut r = TinkReader::<_, ChaCha20Poly1305>::new(
+        &ciphertext[..],
+        &IKM,
+        b"different.txt",
+        DEFAULT_SEGMENT_SIZE,
+    )
+    .unwrap();
+    let mut out = Vec::new();
+    assert!(r.read_to_end(&mut out).is_err());
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "tracing")]
+
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, Writer};
+use tracing::{span, Event, Level, Metadata};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+/// A `Subscriber` that does nothing but count events, split out by
+/// whether they're at `WARN` level or above -- enough to tell "some
+/// instrumentation fired" apart from "a failure was specifically
+/// logged" without pulling in a real tracing backend.
+#[derive(Clone, Default)]
+struct EventCounter {
+    total: Arc<AtomicUsize>,
+    warnings: Arc<AtomicUsize>,
+}
+
+impl tracing::Subscriber for EventCounter {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        self.total.fetch_add(1, Ordering::SeqCst);
+        if *event.metadata().level() <= Level::WARN {
+            self.warnings.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn roundtrip_emits_events_without_warnings() {
+    let counter = EventCounter::default();
+    let key = KEY.into();
+
+    tracing::subscriber::with_default(counter.clone(), || {
+        let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+        w.write_all(b"hello, world").unwrap();
+        let ciphertext = w.finish().unwrap();
+
+        let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+    });
+
+    assert!(counter.total.load(Ordering::SeqCst) > 0);
+    assert_eq!(counter.warnings.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn tampered_ciphertext_emits_a_warning() {
+    let counter = EventCounter::default();
+    let key = KEY.into();
+
+    tracing::subscriber::with_default(counter.clone(), || {
+        let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+        w.write_all(b"hello, world").unwrap();
+        let mut ciphertext = w.finish().unwrap();
+        *ciphertext.last_mut().unwrap() ^= 1;
+
+        let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &key).unwrap();
+        let mut out = Vec::new();
+        assert!(r.read_to_end(&mut out).is_err());
+    });
+
+    assert!(counter.warnings.load(Ordering::SeqCst) > 0);
+}
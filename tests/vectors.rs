@@ -0,0 +1,74 @@
+#![cfg(feature = "vectors")]
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{load_vectors, run_vector, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const PREFIX: [u8; 4] = [0x24; 4];
+
+fn seal(plaintext: &[u8]) -> Vec<u8> {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn valid_vector_passes() {
+    let ciphertext = seal(b"hello, world");
+    let json = format!(
+        r#"[{{"name":"valid","key":"{}","ciphertext":"{}","valid":true,"plaintext":"{}"}}]"#,
+        to_hex(&KEY),
+        to_hex(&ciphertext),
+        to_hex(b"hello, world"),
+    );
+    let vectors = load_vectors(&json).unwrap();
+    assert_eq!(vectors.len(), 1);
+    assert!(run_vector::<ChaCha20Poly1305>(&vectors[0]));
+}
+
+#[test]
+fn truncated_vector_is_rejected() {
+    let mut ciphertext = seal(b"hello, world");
+    ciphertext.truncate(ciphertext.len() - 4);
+    let json = format!(
+        r#"[{{"name":"truncated","key":"{}","ciphertext":"{}","valid":false}}]"#,
+        to_hex(&KEY),
+        to_hex(&ciphertext),
+    );
+    let vectors = load_vectors(&json).unwrap();
+    assert!(run_vector::<ChaCha20Poly1305>(&vectors[0]));
+}
+
+#[test]
+fn bit_flipped_vector_is_rejected() {
+    let mut ciphertext = seal(b"hello, world");
+    let last = ciphertext.len() - 1;
+    ciphertext[last] ^= 0x01;
+    let json = format!(
+        r#"[{{"name":"bit flip","key":"{}","ciphertext":"{}","valid":false}}]"#,
+        to_hex(&KEY),
+        to_hex(&ciphertext),
+    );
+    let vectors = load_vectors(&json).unwrap();
+    assert!(run_vector::<ChaCha20Poly1305>(&vectors[0]));
+}
+
+#[test]
+fn mismatched_expectation_fails() {
+    let ciphertext = seal(b"hello, world");
+    let json = format!(
+        r#"[{{"name":"wrong plaintext","key":"{}","ciphertext":"{}","valid":true,"plaintext":"{}"}}]"#,
+        to_hex(&KEY),
+        to_hex(&ciphertext),
+        to_hex(b"goodbye, world"),
+    );
+    let vectors = load_vectors(&json).unwrap();
+    assert!(!run_vector::<ChaCha20Poly1305>(&vectors[0]));
+}
@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, ReaderOpts, Version, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn display_and_from_str_round_trip_every_version() {
+    let versions = [
+        Version::V1,
+        Version::V2,
+        Version::V3,
+        Version::V4,
+        Version::V5,
+        Version::V6,
+    ];
+    for version in versions {
+        let rendered = version.to_string();
+        assert_eq!(rendered.parse::<Version>().unwrap(), version);
+    }
+}
+
+#[test]
+fn from_str_is_case_insensitive() {
+    assert_eq!("v1".parse::<Version>().unwrap(), Version::V1);
+    assert_eq!("V1".parse::<Version>().unwrap(), Version::V1);
+}
+
+#[test]
+fn from_str_rejects_garbage() {
+    assert!("v0".parse::<Version>().is_err());
+    assert!("v7".parse::<Version>().is_err());
+    assert!("latest".parse::<Version>().is_err());
+    assert!("".parse::<Version>().is_err());
+}
+
+#[test]
+fn latest_is_the_newest_version_this_crate_writes() {
+    assert_eq!(Version::latest(), Version::V6);
+}
+
+#[test]
+fn with_opts_accepts_a_version_on_the_allowlist() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let opts = ReaderOpts {
+        allowed_versions: vec![Version::V1],
+    };
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_opts(&ciphertext[..], &key, &opts).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[test]
+fn with_opts_rejects_a_version_not_on_the_allowlist() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &key, NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let opts = ReaderOpts {
+        allowed_versions: vec![Version::V6],
+    };
+    assert!(Reader::<_, ChaCha20Poly1305>::with_opts(&ciphertext[..], &key, &opts).is_err());
+}
+
+#[test]
+fn default_opts_accept_whatever_reader_new_would() {
+    let key = KEY.into();
+    let mut w = Writer::<_, ChaCha20Poly1305>::with_comment(
+        Vec::new(),
+        &key,
+        NONCE_PREFIX,
+        b"a comment",
+    )
+    .unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let opts = ReaderOpts::default();
+    let mut r = Reader::<_, ChaCha20Poly1305>::with_opts(&ciphertext[..], &key, &opts).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"hello, world");
+}
+
+#[cfg(all(feature = "serde", feature = "vectors"))]
+#[test]
+fn version_serializes_and_deserializes() {
+    let json = serde_json::to_string(&Version::V6).unwrap();
+    let version: Version = serde_json::from_str(&json).unwrap();
+    assert_eq!(version, Version::V6);
+}
+
+#[cfg(all(feature = "serde", feature = "vectors"))]
+#[test]
+fn reader_opts_serializes_and_deserializes() {
+    let opts = ReaderOpts {
+        allowed_versions: vec![Version::V4, Version::V5, Version::V6],
+    };
+    let json = serde_json::to_string(&opts).unwrap();
+    let round_tripped: ReaderOpts = serde_json::from_str(&json).unwrap();
+    assert_eq!(round_tripped.allowed_versions, opts.allowed_versions);
+}
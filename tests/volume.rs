@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, VolumeReader, VolumeWriter, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+/// A `Vec<u8>`-backed stand-in for one open volume, indexed into a
+/// shared `RefCell<Vec<Vec<u8>>>` so tests don't need real files.
+struct Volume<'a>(usize, &'a RefCell<Vec<Vec<u8>>>);
+
+impl Write for Volume<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.1.borrow_mut()[self.0].extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_stream_split_across_volumes_roundtrips_even_when_volume_boundaries_fall_mid_chunk() {
+    let volumes = RefCell::new(Vec::<Vec<u8>>::new());
+    // A volume size that doesn't evenly divide a STREAM chunk (or its
+    // tag), so at least one volume boundary lands in the middle of a
+    // sealed chunk -- exactly the case VolumeWriter/VolumeReader exist
+    // to make invisible to the AEAD layer above them.
+    let volume_writer = VolumeWriter::new(777, |index| {
+        volumes.borrow_mut().push(Vec::new());
+        Ok(Volume(index as usize, &volumes))
+    })
+    .unwrap();
+
+    let key = KEY.into();
+    let plaintext = vec![0xab; 10_000];
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(volume_writer, &key, NONCE_PREFIX).unwrap();
+    w.write_all(&plaintext).unwrap();
+    w.finish().unwrap();
+
+    let volumes = volumes.into_inner();
+    assert!(volumes.len() > 1, "test is pointless with a single volume");
+
+    let volume_reader =
+        VolumeReader::new(|index: u64| Ok(volumes.get(index as usize).map(|v| &v[..]))).unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(volume_reader, &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
+
+#[test]
+fn a_single_volume_large_enough_for_the_whole_stream_roundtrips() {
+    let volumes = RefCell::new(Vec::<Vec<u8>>::new());
+    let volume_writer = VolumeWriter::new(1 << 20, |index| {
+        volumes.borrow_mut().push(Vec::new());
+        Ok(Volume(index as usize, &volumes))
+    })
+    .unwrap();
+
+    let key = KEY.into();
+    let plaintext = b"hello, world";
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(volume_writer, &key, NONCE_PREFIX).unwrap();
+    w.write_all(plaintext).unwrap();
+    w.finish().unwrap();
+
+    let volumes = volumes.into_inner();
+    assert_eq!(volumes.len(), 1);
+
+    let volume_reader =
+        VolumeReader::new(|index: u64| Ok(volumes.get(index as usize).map(|v| &v[..]))).unwrap();
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(volume_reader, &key).unwrap();
+    let mut out = Vec::new();
+    r.read_to_end(&mut out).unwrap();
+    assert_eq!(out, plaintext);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "wasm")]
+
+// Every error path here constructs a `JsValue` (via `js_err`), and
+// `JsValue` only works with an actual JS engine behind it -- calling
+// it from a plain `cargo test` run on a native target panics. So this
+// file only covers the success paths; exercising the error paths
+// needs `wasm-bindgen-test` against a wasm32 target instead.
+
+use std::io::Write;
+
+use chacha20poly1305::ChaCha20Poly1305;
+use stream::{Reader, WasmDecryptor, WasmEncryptor, Writer};
+
+const KEY: [u8; 32] = [0x42; 32];
+const NONCE_PREFIX: [u8; 4] = [0x24; 4];
+
+#[test]
+fn encryptor_decryptor_roundtrip_in_one_push_each() {
+    let mut enc = WasmEncryptor::new(&KEY, &NONCE_PREFIX).unwrap();
+    let mut ciphertext = enc.push(b"hello, world").unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+
+    let mut dec = WasmDecryptor::new(&KEY).unwrap();
+    let mut plaintext = dec.push(&ciphertext).unwrap();
+    plaintext.extend(dec.finish().unwrap());
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn decryptor_yields_nothing_until_a_chunk_has_fully_arrived() {
+    let mut enc = WasmEncryptor::new(&KEY, &NONCE_PREFIX).unwrap();
+    let mut ciphertext = enc.push(b"hello, world").unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+
+    let mut dec = WasmDecryptor::new(&KEY).unwrap();
+    let (first, rest) = ciphertext.split_at(ciphertext.len() / 2);
+    assert!(dec.push(first).unwrap().is_empty());
+    let mut plaintext = dec.push(rest).unwrap();
+    plaintext.extend(dec.finish().unwrap());
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn encryptor_matches_the_plain_writer_and_reader() {
+    let mut enc = WasmEncryptor::new(&KEY, &NONCE_PREFIX).unwrap();
+    let mut ciphertext = enc.push(b"hello, world").unwrap();
+    ciphertext.extend(enc.finish().unwrap());
+
+    let mut r = Reader::<_, ChaCha20Poly1305>::new(&ciphertext[..], &KEY.into()).unwrap();
+    let mut plaintext = Vec::new();
+    r.read_to_end(&mut plaintext).unwrap();
+    assert_eq!(plaintext, b"hello, world");
+}
+
+#[test]
+fn decryptor_matches_a_stream_from_the_plain_writer() {
+    let mut w = Writer::<_, ChaCha20Poly1305>::new(Vec::new(), &KEY.into(), NONCE_PREFIX).unwrap();
+    w.write_all(b"hello, world").unwrap();
+    let ciphertext = w.finish().unwrap();
+
+    let mut dec = WasmDecryptor::new(&KEY).unwrap();
+    let mut plaintext = dec.push(&ciphertext).unwrap();
+    plaintext.extend(dec.finish().unwrap());
+    assert_eq!(plaintext, b"hello, world");
+}